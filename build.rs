@@ -0,0 +1,24 @@
+use std::env;
+use std::process::Command;
+
+// Record how the crate was built, for `Environment::detect()`.
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    let target = env::var("TARGET").unwrap_or_default();
+    println!("cargo:rustc-env=BENCHMARK_SIMPLE_TARGET={}", target);
+    let profile = env::var("PROFILE").unwrap_or_default();
+    println!("cargo:rustc-env=BENCHMARK_SIMPLE_PROFILE={}", profile);
+    let opt_level = env::var("OPT_LEVEL").unwrap_or_default();
+    println!("cargo:rustc-env=BENCHMARK_SIMPLE_OPT_LEVEL={}", opt_level);
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_default();
+    println!(
+        "cargo:rustc-env=BENCHMARK_SIMPLE_RUSTC_VERSION={}",
+        rustc_version.trim()
+    );
+}
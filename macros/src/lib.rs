@@ -0,0 +1,55 @@
+//! Attribute macros for `benchmark-simple`.
+//!
+//! Use them through the `macros` feature of `benchmark-simple`.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::Parser;
+use syn::{parse_macro_input, ItemFn, LitStr};
+
+/// Register a function taking no arguments as a benchmark.
+///
+/// The benchmark is named after the function, unless a name is given with
+/// `#[benchmark(name = "...")]`. Registered benchmarks are added to a suite
+/// with `Suite::register_annotated()`, which `main!()` does.
+#[proc_macro_attribute]
+pub fn benchmark(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let function = parse_macro_input!(item as ItemFn);
+    let ident = &function.sig.ident;
+    let mut name = ident.to_string();
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("name") {
+            name = meta.value()?.parse::<LitStr>()?.value();
+            Ok(())
+        } else {
+            Err(meta.error("unsupported benchmark property"))
+        }
+    });
+    if let Err(e) = parser.parse(attr) {
+        return e.to_compile_error().into();
+    }
+    if !function.sig.inputs.is_empty() {
+        return syn::Error::new_spanned(
+            &function.sig.inputs,
+            "benchmark functions must not take any arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+    quote! {
+        #function
+
+        const _: () = {
+            fn register(suite: &mut ::benchmark_simple::Suite<'_>) {
+                suite.register(#name, #ident);
+            }
+            ::benchmark_simple::__private::inventory::submit! {
+                ::benchmark_simple::__private::Registration {
+                    name: #name,
+                    register,
+                }
+            }
+        };
+    }
+    .into()
+}
@@ -0,0 +1,106 @@
+//! Destinations for exported reports.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::Report;
+
+/// A place where artifacts such as exported reports can be stored.
+pub trait ArtifactSink {
+    /// Store an artifact under the given name, replacing any previous version.
+    fn put(&self, name: &str, contents: &[u8]) -> io::Result<()>;
+}
+
+/// Stores artifacts as files in a local directory.
+#[derive(Clone, Debug)]
+pub struct DirectorySink {
+    dir: PathBuf,
+}
+
+impl DirectorySink {
+    /// Store artifacts in `dir`, which is created if needed.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        DirectorySink { dir: dir.into() }
+    }
+}
+
+impl ArtifactSink for DirectorySink {
+    fn put(&self, name: &str, contents: &[u8]) -> io::Result<()> {
+        let path = self.dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)
+    }
+}
+
+/// Stores artifacts in an object store, using the `aws` (for `s3://` URLs) or
+/// `gsutil` (for `gs://` URLs) command-line tools and their usual credentials.
+///
+/// Only available with the `object-store` feature.
+#[cfg(feature = "object-store")]
+#[derive(Clone, Debug)]
+pub struct ObjectStoreSink {
+    url: String,
+}
+
+#[cfg(feature = "object-store")]
+impl ObjectStoreSink {
+    /// Store artifacts under a URL prefix such as `s3://bucket/benchmarks`.
+    pub fn new(url: impl Into<String>) -> io::Result<Self> {
+        let url = url.into();
+        if !url.starts_with("s3://") && !url.starts_with("gs://") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "unsupported object store URL, expected s3:// or gs://",
+            ));
+        }
+        Ok(ObjectStoreSink {
+            url: url.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+#[cfg(feature = "object-store")]
+impl ArtifactSink for ObjectStoreSink {
+    fn put(&self, name: &str, contents: &[u8]) -> io::Result<()> {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+
+        let url = format!("{}/{}", self.url, name);
+        let mut command = if self.url.starts_with("s3://") {
+            let mut command = Command::new("aws");
+            command.args(["s3", "cp", "-", &url]);
+            command
+        } else {
+            let mut command = Command::new("gsutil");
+            command.args(["cp", "-", &url]);
+            command
+        };
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()?;
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(contents)?;
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "upload to {} failed: {}",
+                url, status
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Report {
+    /// Store the report as JSON under the given name.
+    pub fn store(&self, sink: &dyn ArtifactSink, name: &str) -> io::Result<()> {
+        sink.put(name, self.to_json().as_bytes())
+    }
+}
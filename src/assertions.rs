@@ -0,0 +1,96 @@
+//! Performance assertions, so that benchmarks can double as coarse
+//! performance tests.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+
+use crate::{format_rate, format_secs, BenchResult, Throughput};
+
+/// An error returned when a result doesn't meet a performance requirement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PerformanceError {
+    /// Name of the benchmark, if it has one.
+    pub name: Option<String>,
+    /// What was measured, such as `850.12 MB/s`.
+    pub measured: String,
+    /// The requirement that wasn't met, such as `at least 1.00 GB/s`.
+    pub requirement: String,
+}
+
+impl Display for PerformanceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.name {
+            write!(f, "{}: ", name)?;
+        }
+        write!(f, "{}, expected {}", self.measured, self.requirement)
+    }
+}
+
+impl Error for PerformanceError {}
+
+impl BenchResult {
+    /// Check that an iteration takes at most `max`.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    /// use std::time::Duration;
+    ///
+    /// let res = Bench::new().run(&Options::default(), || (0..1000u64).sum::<u64>());
+    /// res.assert_at_most_duration(Duration::from_secs(1)).unwrap();
+    /// ```
+    pub fn assert_at_most_duration(&self, max: Duration) -> Result<(), PerformanceError> {
+        if self.options.dry_run || self.secs_per_iteration() <= max.as_secs_f64() {
+            return Ok(());
+        }
+        Err(PerformanceError {
+            name: self.name().map(str::to_string),
+            measured: format_secs(self.secs_per_iteration()),
+            requirement: format!("at most {}", format_secs(max.as_secs_f64())),
+        })
+    }
+
+    /// Check that processing `bytes` bytes per iteration runs at least at
+    /// `min_bytes_per_sec` bytes per second.
+    ///
+    /// This is a shortcut for [`Throughput::assert_at_least()`] with a
+    /// [`BenchResult::throughput_bytes()`] throughput.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// let data = vec![1u8; 4096];
+    /// let res = Bench::new().run(&Options::default(), || data.iter().map(|&x| x as u64).sum::<u64>());
+    /// res.assert_at_least_throughput(data.len() as u128, 1e6).unwrap();
+    /// ```
+    pub fn assert_at_least_throughput(
+        &self,
+        bytes: u128,
+        min_bytes_per_sec: f64,
+    ) -> Result<(), PerformanceError> {
+        self.clone()
+            .throughput_bytes(bytes)
+            .assert_at_least(min_bytes_per_sec)
+    }
+}
+
+impl Throughput {
+    /// Check that the throughput is at least `min`, per second, in the unit
+    /// of the throughput.
+    ///
+    /// Results below the timer resolution, whose throughput is infinite,
+    /// always pass.
+    pub fn assert_at_least(&self, min: f64) -> Result<(), PerformanceError> {
+        if self.result.options.dry_run || self.as_f64() >= min {
+            return Ok(());
+        }
+        Err(PerformanceError {
+            name: self.result.name().map(str::to_string),
+            measured: self.to_string(),
+            requirement: format!(
+                "at least {}",
+                format_rate(min, self.unit, self.binary_prefixes)
+            ),
+        })
+    }
+}
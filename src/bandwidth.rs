@@ -0,0 +1,93 @@
+//! Reference benchmarks measuring the memory bandwidth of the machine.
+//!
+//! Raw throughputs are hard to compare across machines. Relative to the
+//! speed of `memcpy` on the same machine, they are far more portable.
+
+use std::convert::TryInto;
+use std::fmt::{self, Display, Formatter};
+
+use crate::{black_box, buffers, Bench, Options, Throughput};
+
+/// The memory bandwidth of the machine, measured by reference benchmarks.
+#[derive(Clone, Debug)]
+pub struct MemoryBandwidth {
+    memcpy: Throughput,
+    memset: Throughput,
+    read: Throughput,
+}
+
+impl MemoryBandwidth {
+    /// Returns the throughput of copying a buffer, counting the bytes copied.
+    pub fn memcpy(&self) -> &Throughput {
+        &self.memcpy
+    }
+
+    /// Returns the throughput of filling a buffer.
+    pub fn memset(&self) -> &Throughput {
+        &self.memset
+    }
+
+    /// Returns the throughput of reading a buffer sequentially.
+    pub fn read(&self) -> &Throughput {
+        &self.read
+    }
+
+    /// Returns `throughput` relative to the `memcpy` throughput (e.g. `0.38`
+    /// for 38% of the speed of `memcpy`).
+    pub fn relative_to_memcpy(&self, throughput: &Throughput) -> f64 {
+        throughput.as_f64() / self.memcpy.as_f64()
+    }
+
+    /// Returns `throughput` relative to the sequential read throughput.
+    pub fn relative_to_read(&self, throughput: &Throughput) -> f64 {
+        throughput.as_f64() / self.read.as_f64()
+    }
+}
+
+impl Display for MemoryBandwidth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "memcpy: {}, memset: {}, read: {}",
+            self.memcpy, self.memset, self.read
+        )
+    }
+}
+
+impl Bench {
+    /// Measure the memory bandwidth, using buffers of `size` bytes.
+    ///
+    /// Buffers larger than the last level cache (e.g. 64 MiB) measure the
+    /// bandwidth of the main memory, and smaller buffers the bandwidth of
+    /// the caches they fit in. Use the same size as the benchmarks the
+    /// results are compared to.
+    pub fn memory_bandwidth(&self, options: &Options, size: usize) -> MemoryBandwidth {
+        let src = buffers::page_aligned_vec(size);
+        let mut dst = buffers::page_aligned_vec(size);
+        let volume = size as u128;
+        let memcpy = self
+            .run(options, || {
+                dst.copy_from_slice(black_box(&src));
+                black_box(&mut dst);
+            })
+            .throughput_bytes(volume);
+        let memset = self
+            .run(options, || {
+                dst.fill(black_box(0x5a));
+                black_box(&mut dst);
+            })
+            .throughput_bytes(volume);
+        let read = self
+            .run(options, || {
+                black_box(&src).chunks_exact(8).fold(0u64, |acc, word| {
+                    acc.wrapping_add(u64::from_le_bytes(word.try_into().unwrap()))
+                })
+            })
+            .throughput_bytes(volume);
+        MemoryBandwidth {
+            memcpy,
+            memset,
+            read,
+        }
+    }
+}
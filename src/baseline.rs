@@ -0,0 +1,327 @@
+//! Previous results to compare against.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::{json, stats, Report};
+
+/// The times of a previous run, by benchmark name.
+///
+/// Baselines can also be imported from criterion and hyperfine, to keep the
+/// history of benchmarks migrated from these tools.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Baseline {
+    /// The time of a sample, and its number of iterations.
+    times: HashMap<String, (Duration, u64)>,
+    /// The margin of error of the results, if known (in %).
+    margins: HashMap<String, f64>,
+    /// The calibration score of the machine the results come from.
+    calibration_score: Option<f64>,
+    /// The microarchitecture of the machine the results come from.
+    microarchitecture: Option<String>,
+}
+
+impl Baseline {
+    /// Load a baseline from a report saved with [`Report::to_json`], from
+    /// a hyperfine JSON export, from a criterion output directory (see
+    /// [`Baseline::load_criterion()`]), or from a CSV file saved with
+    /// [`Report::to_timeline_csv()`], if its extension is `.csv`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            return Self::load_criterion(path);
+        }
+        let content = fs::read_to_string(path)?;
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+        {
+            return Self::from_csv(&content);
+        }
+        Self::from_json(&content)
+    }
+
+    /// Parse a report saved with [`Report::to_json`], or a hyperfine JSON
+    /// export (`hyperfine --export-json`).
+    ///
+    /// The time of a hyperfine command is its fastest run, as for the
+    /// results of this crate, and its name is the command, or the name
+    /// given with `--command-name`.
+    ///
+    /// ```rust
+    /// use benchmark_simple::Baseline;
+    ///
+    /// let export = r#"{"results": [{"command": "gzip -9 data", "mean": 0.25, "min": 0.24}]}"#;
+    /// let baseline = Baseline::from_json(export).unwrap();
+    /// assert_eq!(baseline.get("gzip -9 data").unwrap().as_millis(), 240);
+    /// ```
+    pub fn from_json(report: &str) -> io::Result<Self> {
+        let report = json::parse(report).map_err(invalid)?;
+        let results = report
+            .get("results")
+            .and_then(|results| results.as_array())
+            .ok_or_else(|| invalid("missing results".to_string()))?;
+        let mut times = HashMap::new();
+        let mut margins = HashMap::new();
+        for result in results {
+            let number = |key: &str| result.get(key).and_then(|value| value.as_f64());
+            let entry = match result.get("command") {
+                // hyperfine, in seconds, for a single run.
+                Some(command) => command
+                    .as_str()
+                    .zip(number("min").or_else(|| number("mean")))
+                    .map(|(name, secs)| (name, secs * 1e9, 1.0)),
+                None => result
+                    .get("name")
+                    .and_then(|name| name.as_str())
+                    .zip(number("ns"))
+                    .map(|(name, ns)| (name, ns, number("iterations").unwrap_or(1.0))),
+            };
+            match entry {
+                Some((name, ns, iterations)) if ns >= 0.0 && iterations >= 1.0 => {
+                    if let Some(margin) = number("margin_of_error") {
+                        margins.insert(name.to_string(), margin);
+                    }
+                    times.insert(
+                        name.to_string(),
+                        (Duration::from_nanos(ns as u64), iterations as u64),
+                    );
+                }
+                _ => return Err(invalid("invalid result".to_string())),
+            }
+        }
+        let environment = report.get("environment");
+        let calibration_score = environment
+            .and_then(|environment| environment.get("calibration_score"))
+            .and_then(|score| score.as_f64())
+            .filter(|&score| score > 0.0);
+        let microarchitecture = environment
+            .and_then(|environment| environment.get("microarchitecture"))
+            .and_then(|microarchitecture| microarchitecture.as_str())
+            .map(str::to_string);
+        Ok(Baseline {
+            times,
+            margins,
+            calibration_score,
+            microarchitecture,
+        })
+    }
+
+    /// Parse the samples saved with [`Report::to_timeline_csv()`].
+    ///
+    /// The time of a benchmark is its fastest sample, and the margin of
+    /// error is computed from all its samples. The number of iterations of
+    /// the samples isn't saved: such a baseline should only be compared
+    /// with results collected with the same number of iterations.
+    ///
+    /// ```rust
+    /// use benchmark_simple::Baseline;
+    ///
+    /// let csv = "name,timestamp_ns,ns\nsum,1700000000000000000,1200\nsum,1700000000000001200,1000\n";
+    /// let baseline = Baseline::from_csv(csv).unwrap();
+    /// assert_eq!(baseline.get("sum").unwrap().as_nanos(), 1000);
+    /// assert!(baseline.margin_of_error("sum").is_some());
+    /// ```
+    pub fn from_csv(csv: &str) -> io::Result<Self> {
+        let mut lines = csv.lines();
+        if lines.next().map(str::trim) != Some("name,timestamp_ns,ns") {
+            return Err(invalid("missing CSV header".to_string()));
+        }
+        let mut samples: Vec<(String, Vec<f64>)> = Vec::new();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let (name, ns) = parse_csv_line(line)
+                .ok_or_else(|| invalid(format!("invalid CSV line: {}", line)))?;
+            match samples.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, ns_list)) => ns_list.push(ns),
+                None => samples.push((name, vec![ns])),
+            }
+        }
+        let mut baseline = Baseline::default();
+        for (name, ns_list) in samples {
+            let fastest = ns_list.iter().copied().fold(f64::INFINITY, f64::min);
+            if let Some(rsd) = stats::rsd(&ns_list) {
+                let margin = 1.96 * rsd / (ns_list.len() as f64).sqrt();
+                baseline.margins.insert(name.clone(), margin);
+            }
+            baseline
+                .times
+                .insert(name, (Duration::from_nanos(fastest as u64), 1));
+        }
+        Ok(baseline)
+    }
+
+    /// Import the results saved by criterion in `dir`, usually
+    /// `target/criterion`.
+    ///
+    /// Every benchmark with a `new/estimates.json` file is imported, with
+    /// the median time of an iteration. Benchmarks are named after their
+    /// criterion identifier, such as `group/function/parameter`.
+    ///
+    /// Criterion estimates are computed from all the samples, whereas this
+    /// crate reports the fastest sample: changes relative to an imported
+    /// baseline are biased towards improvements.
+    pub fn load_criterion(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let mut times = HashMap::new();
+        load_criterion_dir(dir.as_ref(), &mut Vec::new(), &mut times)?;
+        if times.is_empty() {
+            return Err(invalid("no criterion results".to_string()));
+        }
+        Ok(Baseline {
+            times,
+            margins: HashMap::new(),
+            calibration_score: None,
+            microarchitecture: None,
+        })
+    }
+
+    /// Returns the time of the benchmark with the given name.
+    pub fn get(&self, name: &str) -> Option<Duration> {
+        self.times.get(name).map(|&(time, _)| time)
+    }
+
+    /// Returns the margin of error of the benchmark with the given name,
+    /// relative to its time (in 0...100), if it is known.
+    pub fn margin_of_error(&self, name: &str) -> Option<f64> {
+        self.margins.get(name).copied()
+    }
+
+    /// Returns the calibration score of the machine the results come from,
+    /// if it was measured (see [`Suite::calibrate()`](crate::Suite::calibrate)).
+    pub fn calibration_score(&self) -> Option<f64> {
+        self.calibration_score
+    }
+
+    /// A key to group baselines from machines sharing the same
+    /// microarchitecture, as [`Environment::microarchitecture_key()`](crate::Environment::microarchitecture_key).
+    /// It is `unknown` for baselines without an environment, such as
+    /// imported ones.
+    pub fn microarchitecture_key(&self) -> &str {
+        self.microarchitecture().unwrap_or("unknown")
+    }
+
+    pub(crate) fn microarchitecture(&self) -> Option<&str> {
+        self.microarchitecture.as_deref()
+    }
+
+    /// Returns the names of the benchmarks, in no particular order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.times.keys().map(String::as_str)
+    }
+
+    /// Returns the time of an iteration of the benchmark with the given
+    /// name, in seconds.
+    pub(crate) fn secs_per_iteration(&self, name: &str) -> Option<f64> {
+        self.times
+            .get(name)
+            .map(|&(time, iterations)| time.as_secs_f64() / iterations.max(1) as f64)
+    }
+
+    /// Returns the number of benchmarks in the baseline.
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    /// Returns `true` if the baseline doesn't contain any benchmarks.
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+}
+
+impl From<&Report> for Baseline {
+    fn from(report: &Report) -> Self {
+        let times = report
+            .iter()
+            .map(|(name, result)| {
+                let time = Duration::from_nanos(result.as_ns());
+                (name.to_string(), (time, result.iterations()))
+            })
+            .collect();
+        let margins = report
+            .iter()
+            .filter_map(|(name, result)| Some((name.to_string(), result.margin_of_error()?)))
+            .collect();
+        Baseline {
+            times,
+            margins,
+            calibration_score: report.environment().calibration_score,
+            microarchitecture: report.environment().microarchitecture.clone(),
+        }
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Parse a `name,timestamp_ns,ns` line, the name being quoted if it
+/// contains a comma, a quote or a newline.
+fn parse_csv_line(line: &str) -> Option<(String, f64)> {
+    let (name, rest) = match line.strip_prefix('"') {
+        Some(quoted) => {
+            let mut name = String::new();
+            let mut chars = quoted.char_indices();
+            let end = loop {
+                match chars.next()? {
+                    (i, '"') if quoted[i + 1..].starts_with('"') => {
+                        name.push('"');
+                        chars.next();
+                    }
+                    (i, '"') => break i + 1,
+                    (_, c) => name.push(c),
+                }
+            };
+            (name, quoted[end..].strip_prefix(',')?)
+        }
+        None => {
+            let (name, rest) = line.split_once(',')?;
+            (name.to_string(), rest)
+        }
+    };
+    let (_timestamp, ns) = rest.split_once(',')?;
+    let ns: f64 = ns.trim().parse().ok()?;
+    (ns >= 0.0).then_some((name, ns))
+}
+
+/// Import the criterion results found in `dir`, whose path relative to the
+/// root is `components`.
+fn load_criterion_dir(
+    dir: &Path,
+    components: &mut Vec<String>,
+    times: &mut HashMap<String, (Duration, u64)>,
+) -> io::Result<()> {
+    let new = dir.join("new");
+    let estimates = new.join("estimates.json");
+    if estimates.is_file() {
+        let estimates = json::parse(&fs::read_to_string(&estimates)?).map_err(invalid)?;
+        let ns = estimates
+            .get("median")
+            .and_then(|median| median.get("point_estimate"))
+            .and_then(|ns| ns.as_f64())
+            .filter(|&ns| ns >= 0.0)
+            .ok_or_else(|| invalid("invalid criterion estimates".to_string()))?;
+        // The directory name is sanitized; the identifier is exact.
+        let name = fs::read_to_string(new.join("benchmark.json"))
+            .ok()
+            .and_then(|benchmark| json::parse(&benchmark).ok())
+            .and_then(|benchmark| Some(benchmark.get("full_id")?.as_str()?.to_string()))
+            .unwrap_or_else(|| components.join("/"));
+        times.insert(name, (Duration::from_nanos(ns.round() as u64), 1));
+        return Ok(());
+    }
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // HTML reports.
+        if name == "report" || !entry.file_type()?.is_dir() {
+            continue;
+        }
+        components.push(name);
+        load_criterion_dir(&entry.path(), components, times)?;
+        components.pop();
+    }
+    Ok(())
+}
@@ -0,0 +1,95 @@
+//! A libtest-style interface to benchmarks.
+
+use std::panic::Location;
+
+use crate::{Bench, BenchResult, Options};
+
+/// A handle passed to benchmark functions, in the style of libtest's
+/// `test::Bencher`: the function prepares its input, then calls `iter()`
+/// with the code to measure.
+///
+/// With the `libtest` feature (nightly only), functions written against
+/// this type can also be driven by libtest with [`libtest`].
+pub struct Bencher<'a> {
+    inner: Inner<'a>,
+    result: Option<BenchResult>,
+}
+
+enum Inner<'a> {
+    Bench {
+        bench: &'a Bench,
+        options: &'a Options,
+        location: &'static Location<'static>,
+    },
+    #[cfg(feature = "libtest")]
+    Libtest(&'a mut test::Bencher),
+}
+
+impl Bencher<'_> {
+    /// Measure `f`.
+    pub fn iter<F, G>(&mut self, f: F)
+    where
+        F: FnMut() -> G,
+    {
+        match &mut self.inner {
+            Inner::Bench {
+                bench,
+                options,
+                location,
+            } => self.result = Some(bench.run_at(options, location, f)),
+            #[cfg(feature = "libtest")]
+            Inner::Libtest(b) => b.iter(f),
+        }
+    }
+
+    /// Returns the result of the last call to `iter()`, if any.
+    /// This is always `None` when driven by libtest.
+    pub fn result(&self) -> Option<&BenchResult> {
+        self.result.as_ref()
+    }
+}
+
+impl Bench {
+    /// Run a function written against [`Bencher`].
+    ///
+    /// Returns the result of the last call to `Bencher::iter()`, or `None`
+    /// if the function didn't call it.
+    #[track_caller]
+    pub fn run_bencher(
+        &self,
+        options: &Options,
+        f: impl FnOnce(&mut Bencher<'_>),
+    ) -> Option<BenchResult> {
+        let mut bencher = Bencher {
+            inner: Inner::Bench {
+                bench: self,
+                options,
+                location: Location::caller(),
+            },
+            result: None,
+        };
+        f(&mut bencher);
+        bencher.result
+    }
+}
+
+/// Run a function written against [`Bencher`] from a libtest `#[bench]`
+/// function:
+///
+/// ```rust,ignore
+/// #[bench]
+/// fn sum(b: &mut test::Bencher) {
+///     benchmark_simple::libtest(b, |b| b.iter(|| (0..1000u64).sum::<u64>()));
+/// }
+/// ```
+///
+/// The opposite direction isn't possible, as `test::Bencher` can only be
+/// created by libtest itself.
+#[cfg(feature = "libtest")]
+pub fn libtest(b: &mut test::Bencher, f: impl FnOnce(&mut Bencher<'_>)) {
+    let mut bencher = Bencher {
+        inner: Inner::Libtest(b),
+        result: None,
+    };
+    f(&mut bencher);
+}
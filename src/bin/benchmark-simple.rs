@@ -0,0 +1,108 @@
+//! Compare two saved sets of results.
+//!
+//! Each file can be a JSON report, a CSV timeline, a hyperfine JSON export
+//! or a criterion output directory, as loaded by `Baseline::load()`.
+//!
+//! Results from several machines, such as the runners of a CI matrix, can be
+//! compared at once: every new file is compared with the old file from the
+//! same microarchitecture, and the comparisons are grouped by
+//! microarchitecture.
+
+use std::process;
+
+use benchmark_simple::Baseline;
+
+const USAGE: &str = "Usage: benchmark-simple [--threshold <percent>] [--normalize] [--fail-on-regression] <old> <new>\n       benchmark-simple [options] <old>... -- <new>...";
+
+/// Significance threshold, matching the default `max_rsd`.
+const DEFAULT_THRESHOLD: f64 = 5.0;
+
+fn main() {
+    let mut threshold = DEFAULT_THRESHOLD;
+    let mut normalize = false;
+    let mut fail_on_regression = false;
+    let mut paths = Vec::new();
+    let mut separator = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threshold" => {
+                let value = args.next().unwrap_or_default();
+                threshold = match value.parse().ok().filter(|pct: &f64| *pct >= 0.0) {
+                    Some(threshold) => threshold,
+                    None => usage_error(&format!("Invalid value for --threshold: {}", value)),
+                }
+            }
+            "--normalize" => normalize = true,
+            "--fail-on-regression" => fail_on_regression = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            "--" if separator.is_none() => separator = Some(paths.len()),
+            arg if arg.starts_with('-') => usage_error(&format!("Unknown option: {}", arg)),
+            path => paths.push(path.to_string()),
+        }
+    }
+    let (old, new) = match separator {
+        Some(separator) => paths.split_at(separator),
+        None if paths.len() == 2 => paths.split_at(1),
+        None => usage_error("Two result files are required"),
+    };
+    if old.is_empty() || new.is_empty() {
+        usage_error("Old and new result files are required");
+    }
+    let old: Vec<_> = old.iter().map(|path| (path, load(path))).collect();
+    let new: Vec<_> = new.iter().map(|path| (path, load(path))).collect();
+    // Compare every new file with the old file from the same
+    // microarchitecture, or with the first one, which the comparison warns
+    // about.
+    let mut pairs: Vec<_> = new
+        .iter()
+        .map(|new| {
+            let key = new.1.microarchitecture_key();
+            let old = old
+                .iter()
+                .find(|old| old.1.microarchitecture_key() == key)
+                .unwrap_or(&old[0]);
+            (key, old, new)
+        })
+        .collect();
+    pairs.sort_by_key(|&(key, _, _)| key);
+    let mut regressions = false;
+    for (i, (key, (old_path, old), (new_path, new))) in pairs.iter().enumerate() {
+        if pairs.len() > 1 {
+            if i > 0 {
+                println!();
+            }
+            println!("{} ({} vs {}):", key, old_path, new_path);
+        }
+        let mut diff = old.diff(new, threshold);
+        if normalize {
+            diff = diff.normalized();
+            if !diff.is_normalized() {
+                eprintln!(
+                    "--normalize requires calibration scores in both files, see Suite::calibrate()"
+                );
+                process::exit(2);
+            }
+        }
+        diff.print();
+        regressions |= diff.regressions().next().is_some();
+    }
+    if fail_on_regression && regressions {
+        process::exit(1);
+    }
+}
+
+fn load(path: &str) -> Baseline {
+    Baseline::load(path).unwrap_or_else(|e| {
+        eprintln!("Unable to load the results [{}]: {}", path, e);
+        process::exit(2);
+    })
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    process::exit(2);
+}
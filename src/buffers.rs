@@ -0,0 +1,126 @@
+//! Buffers with a controlled alignment, for throughput benchmarks.
+//!
+//! The alignment of a `Vec<u8>` depends on the allocator, so SIMD kernels
+//! can randomly run on aligned or misaligned data from one run to another.
+//! These buffers make it explicit.
+//!
+//! ```rust
+//! use benchmark_simple::buffers;
+//!
+//! let aligned = buffers::aligned_vec(4096, 64);
+//! assert_eq!(aligned.as_ptr() as usize % 64, 0);
+//!
+//! let page = buffers::page_aligned_vec(4096);
+//! assert_eq!(page.as_ptr() as usize % buffers::page_size(), 0);
+//!
+//! // One byte past a 64-byte boundary, to measure the misaligned case.
+//! let misaligned = buffers::offset_vec(4096, 64, 1);
+//! assert_eq!(misaligned.as_ptr() as usize % 64, 1);
+//! ```
+
+use std::alloc::{self, Layout};
+use std::fmt::{self, Debug, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+
+/// A zero-initialized byte buffer starting at a given alignment.
+pub struct AlignedBuffer {
+    allocation: NonNull<u8>,
+    layout: Layout,
+    offset: usize,
+    len: usize,
+}
+
+// The buffer owns its allocation, as a `Vec<u8>` would.
+unsafe impl Send for AlignedBuffer {}
+unsafe impl Sync for AlignedBuffer {}
+
+impl AlignedBuffer {
+    fn new(len: usize, align: usize, offset: usize) -> Self {
+        assert!(align.is_power_of_two(), "alignment must be a power of two");
+        let size = (offset + len).max(1);
+        let layout = Layout::from_size_align(size, align).expect("buffer too large");
+        let allocation = unsafe { alloc::alloc_zeroed(layout) };
+        let allocation = match NonNull::new(allocation) {
+            Some(allocation) => allocation,
+            None => alloc::handle_alloc_error(layout),
+        };
+        AlignedBuffer {
+            allocation,
+            layout,
+            offset,
+            len,
+        }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        unsafe { alloc::dealloc(self.allocation.as_ptr(), self.layout) }
+    }
+}
+
+impl Deref for AlignedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.allocation.as_ptr().add(self.offset), self.len) }
+    }
+}
+
+impl DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe {
+            std::slice::from_raw_parts_mut(self.allocation.as_ptr().add(self.offset), self.len)
+        }
+    }
+}
+
+impl Clone for AlignedBuffer {
+    fn clone(&self) -> Self {
+        let mut buffer = AlignedBuffer::new(self.len, self.layout.align(), self.offset);
+        buffer.copy_from_slice(self);
+        buffer
+    }
+}
+
+impl Debug for AlignedBuffer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AlignedBuffer")
+            .field("ptr", &self.as_ptr())
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+/// Returns a zeroed buffer of `len` bytes, aligned to `align` bytes.
+///
+/// Panics if `align` is not a power of two.
+pub fn aligned_vec(len: usize, align: usize) -> AlignedBuffer {
+    AlignedBuffer::new(len, align, 0)
+}
+
+/// Returns a zeroed buffer of `len` bytes, starting `offset` bytes past an
+/// `align`-byte boundary.
+///
+/// Panics if `align` is not a power of two.
+pub fn offset_vec(len: usize, align: usize, offset: usize) -> AlignedBuffer {
+    AlignedBuffer::new(len, align, offset)
+}
+
+/// Returns a zeroed buffer of `len` bytes, starting at a page boundary.
+pub fn page_aligned_vec(len: usize) -> AlignedBuffer {
+    AlignedBuffer::new(len, page_size(), 0)
+}
+
+/// Returns the size of a memory page.
+pub fn page_size() -> usize {
+    #[cfg(unix)]
+    {
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if size > 0 {
+            return size as usize;
+        }
+    }
+    4096
+}
@@ -0,0 +1,98 @@
+//! Eviction of data from CPU caches, to measure cold-cache performance.
+
+use crate::black_box;
+
+/// Assumed cache line size.
+const LINE_SIZE: usize = 64;
+/// Size of the thrash buffer if the size of the caches can't be read.
+const DEFAULT_THRASH_SIZE: usize = 64 * 1024 * 1024;
+/// Upper bound on the size of the thrash buffer.
+const MAX_THRASH_SIZE: usize = 512 * 1024 * 1024;
+
+/// Evicts everything from the caches by touching a buffer larger than them.
+pub(crate) struct CacheEvictor {
+    buffer: Vec<u8>,
+}
+
+impl CacheEvictor {
+    pub(crate) fn new() -> Self {
+        let size = largest_cache_size()
+            .map_or(DEFAULT_THRASH_SIZE, |size| size * 2)
+            .min(MAX_THRASH_SIZE);
+        CacheEvictor {
+            buffer: vec![1; size],
+        }
+    }
+
+    /// Touch every cache line of the buffer.
+    pub(crate) fn evict(&mut self) {
+        for i in (0..self.buffer.len()).step_by(LINE_SIZE) {
+            self.buffer[i] = self.buffer[i].wrapping_add(1);
+        }
+        black_box(&mut self.buffer);
+    }
+}
+
+/// Flush the cache lines holding `data` from all the CPU caches.
+///
+/// This uses `clflush` on x86_64, which is much faster than the thrash
+/// buffer used by the `cold_cache` option, but only evicts `data`. On other
+/// architectures, a buffer larger than the caches is touched instead.
+///
+/// It can be called from a custom [`Measurement`](crate::Measurement) to
+/// evict the inputs of a benchmark before every sample.
+pub fn flush_cache<T>(data: &[T]) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        use std::arch::x86_64::{_mm_clflush, _mm_mfence};
+
+        let start = data.as_ptr() as *const u8;
+        let len = std::mem::size_of_val(data);
+        // SSE2, which provides `clflush`, is part of the x86_64 baseline.
+        unsafe {
+            for offset in (0..len).step_by(LINE_SIZE) {
+                _mm_clflush(start.add(offset));
+            }
+            if len > 0 {
+                _mm_clflush(start.add(len - 1));
+            }
+            _mm_mfence();
+        }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        black_box(data);
+        CacheEvictor::new().evict();
+    }
+}
+
+/// Returns the size of the largest CPU cache, in bytes.
+#[cfg(target_os = "linux")]
+fn largest_cache_size() -> Option<usize> {
+    let dir = std::fs::read_dir("/sys/devices/system/cpu/cpu0/cache").ok()?;
+    dir.filter_map(|entry| std::fs::read_to_string(entry.ok()?.path().join("size")).ok())
+        .filter_map(|size| parse_size(size.trim()))
+        .max()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn largest_cache_size() -> Option<usize> {
+    None
+}
+
+/// Parse a cache size such as `32K` or `8M`.
+#[cfg(target_os = "linux")]
+fn parse_size(size: &str) -> Option<usize> {
+    let (number, unit) = match size.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => size.split_at(i),
+        None => (size, ""),
+    };
+    let multiplier = match unit {
+        "" => 1,
+        "K" => 1024,
+        "M" => 1024 * 1024,
+        "G" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.parse::<usize>().ok().map(|n| n * multiplier)
+}
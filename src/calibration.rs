@@ -0,0 +1,49 @@
+//! A reference workload, to compare results across machines.
+
+use crate::{black_box, Bench, Options};
+
+/// Size of the table of the workload, fitting in the L2 cache of most CPUs.
+const TABLE_WORDS: usize = 16 * 1024;
+
+/// Number of steps of a run of the workload.
+const STEPS: usize = 64 * 1024;
+
+impl Bench {
+    /// Returns the calibration score of the machine: how many times per
+    /// second it runs a fixed workload, mixing integer arithmetic,
+    /// data-dependent branches and memory accesses. Higher is faster.
+    ///
+    /// The score is only meaningful relative to the score of another
+    /// machine. With [`Suite::calibrate()`](crate::Suite::calibrate), it is
+    /// stored in the [`Environment`](crate::Environment) of the report, so
+    /// that [`BaselineDiff::normalized()`](crate::BaselineDiff::normalized)
+    /// can compare results from different machines.
+    ///
+    /// The workload and the options it runs with are fixed, so that scores
+    /// measured by different programs can be compared.
+    pub fn calibration_score(&self) -> f64 {
+        let options = Options {
+            iterations: 4,
+            ..Options::deterministic()
+        };
+        let mut table: Vec<u64> = (0..TABLE_WORDS as u64)
+            .map(|i| i.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+            .collect();
+        let res = self.run(&options, || {
+            let mut state = black_box(0x2545_f491_4f6c_dd1d_u64);
+            for _ in 0..STEPS {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let slot = &mut table[state as usize % TABLE_WORDS];
+                if state & 1 == 0 {
+                    *slot = slot.wrapping_add(state);
+                } else {
+                    state = state.wrapping_mul(*slot | 1);
+                }
+            }
+            state
+        });
+        1.0 / res.secs_per_iteration().max(f64::MIN_POSITIVE)
+    }
+}
@@ -0,0 +1,215 @@
+//! System configuration checks, to run before benchmarking.
+
+use std::fmt::{self, Display, Formatter};
+
+/// Outcome of a system check.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum CheckStatus {
+    /// The configuration is suitable for benchmarking.
+    Pass,
+    /// The configuration is likely to add noise to the results.
+    Warn,
+    /// The configuration is very likely to produce unreliable results.
+    Fail,
+}
+
+impl Display for CheckStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckStatus::Pass => write!(f, "PASS"),
+            CheckStatus::Warn => write!(f, "WARN"),
+            CheckStatus::Fail => write!(f, "FAIL"),
+        }
+    }
+}
+
+/// The result of a system check.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CheckResult {
+    /// Name of the check.
+    pub name: &'static str,
+    /// Outcome of the check.
+    pub status: CheckStatus,
+    /// What was observed.
+    pub message: String,
+    /// How to fix the configuration, if it isn't optimal.
+    pub remediation: Option<&'static str>,
+}
+
+impl CheckResult {
+    fn new(
+        name: &'static str,
+        status: CheckStatus,
+        message: impl Into<String>,
+        remediation: &'static str,
+    ) -> Self {
+        CheckResult {
+            name,
+            status,
+            message: message.into(),
+            remediation: if status == CheckStatus::Pass {
+                None
+            } else {
+                Some(remediation)
+            },
+        }
+    }
+}
+
+impl Display for CheckResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.status, self.name, self.message)?;
+        if let Some(remediation) = self.remediation {
+            write!(f, " ({})", remediation)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check whether the system is configured for reliable benchmarking.
+///
+/// Only checks that can be performed on the current platform are returned.
+pub fn system_check() -> Vec<CheckResult> {
+    let mut checks = Vec::new();
+    #[cfg(target_os = "linux")]
+    linux::checks(&mut checks);
+    checks
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::{CheckResult, CheckStatus};
+
+    fn read(path: &str) -> Option<String> {
+        std::fs::read_to_string(path)
+            .ok()
+            .map(|s| s.trim().to_string())
+    }
+
+    fn meminfo(name: &str) -> Option<u64> {
+        let meminfo = read("/proc/meminfo")?;
+        meminfo.lines().find_map(|line| {
+            let value = line.strip_prefix(name)?.strip_prefix(':')?;
+            value.trim().trim_end_matches(" kB").parse().ok()
+        })
+    }
+
+    pub fn checks(checks: &mut Vec<CheckResult>) {
+        if let Some(governor) = crate::environment::cpu_governor() {
+            let status = match governor.as_str() {
+                "performance" => CheckStatus::Pass,
+                "powersave" | "conservative" => CheckStatus::Fail,
+                _ => CheckStatus::Warn,
+            };
+            checks.push(CheckResult::new(
+                "governor",
+                status,
+                format!("CPU frequency governor is '{}'", governor),
+                "use the 'performance' governor: cpupower frequency-set -g performance",
+            ));
+        }
+
+        if let Some(turbo) = crate::environment::turbo_enabled() {
+            checks.push(CheckResult::new(
+                "turbo",
+                if turbo {
+                    CheckStatus::Warn
+                } else {
+                    CheckStatus::Pass
+                },
+                if turbo {
+                    "turbo boost is enabled"
+                } else {
+                    "turbo boost is disabled"
+                },
+                "disable turbo boost: echo 1 > /sys/devices/system/cpu/intel_pstate/no_turbo \
+                 or echo 0 > /sys/devices/system/cpu/cpufreq/boost",
+            ));
+        }
+
+        if let Some(smt) = read("/sys/devices/system/cpu/smt/active") {
+            let load = read("/proc/loadavg")
+                .and_then(|loadavg| loadavg.split_whitespace().next()?.parse::<f64>().ok());
+            let cpus = std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1);
+            let busy = load.is_some_and(|load| load > cpus as f64 / 2.0);
+            let (status, message) = match (smt == "1", busy) {
+                (false, _) => (CheckStatus::Pass, "SMT is disabled".to_string()),
+                (true, false) => (
+                    CheckStatus::Pass,
+                    "SMT is enabled, but the system is mostly idle".to_string(),
+                ),
+                (true, true) => (
+                    CheckStatus::Warn,
+                    format!(
+                        "SMT is enabled and the load average ({:.2}) suggests busy siblings",
+                        load.unwrap_or_default()
+                    ),
+                ),
+            };
+            checks.push(CheckResult::new(
+                "smt",
+                status,
+                message,
+                "stop other workloads, or disable SMT: echo off > /sys/devices/system/cpu/smt/control",
+            ));
+        }
+
+        if let Some(aslr) = read("/proc/sys/kernel/randomize_va_space") {
+            checks.push(CheckResult::new(
+                "aslr",
+                if aslr == "0" {
+                    CheckStatus::Pass
+                } else {
+                    CheckStatus::Warn
+                },
+                format!("address space layout randomization level is {}", aslr),
+                "for reproducible layouts: echo 0 > /proc/sys/kernel/randomize_va_space",
+            ));
+        }
+
+        if let Some(numa_balancing) = read("/proc/sys/kernel/numa_balancing") {
+            checks.push(CheckResult::new(
+                "numa_balancing",
+                if numa_balancing == "0" {
+                    CheckStatus::Pass
+                } else {
+                    CheckStatus::Warn
+                },
+                if numa_balancing == "0" {
+                    "automatic NUMA balancing is disabled"
+                } else {
+                    "automatic NUMA balancing is enabled"
+                },
+                "disable it: echo 0 > /proc/sys/kernel/numa_balancing",
+            ));
+        }
+
+        if let (Some(swap_total), Some(swap_free), Some(mem_total), Some(mem_available)) = (
+            meminfo("SwapTotal"),
+            meminfo("SwapFree"),
+            meminfo("MemTotal"),
+            meminfo("MemAvailable"),
+        ) {
+            let swap_used = swap_total.saturating_sub(swap_free);
+            let status = if mem_available < mem_total / 10 {
+                CheckStatus::Fail
+            } else if swap_used > 0 {
+                CheckStatus::Warn
+            } else {
+                CheckStatus::Pass
+            };
+            checks.push(CheckResult::new(
+                "swap",
+                status,
+                format!(
+                    "{} MiB of swap used, {} MiB of memory available",
+                    swap_used / 1024,
+                    mem_available / 1024
+                ),
+                "free some memory before benchmarking",
+            ));
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! ANSI colors for terminal output.
+
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, IsTerminal};
+
+/// A terminal color.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Color {
+    Red,
+    Green,
+    Yellow,
+}
+
+impl Color {
+    fn code(self) -> &'static str {
+        match self {
+            Color::Red => "31",
+            Color::Green => "32",
+            Color::Yellow => "33",
+        }
+    }
+}
+
+/// Returns `true` if colors should be used on the standard output.
+///
+/// Colors are disabled when the standard output is not a terminal, or when
+/// the `NO_COLOR` environment variable is set to a non-empty value.
+pub(crate) fn stdout_supports_color() -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    !no_color && io::stdout().is_terminal()
+}
+
+/// A value displayed with an optional color.
+pub(crate) struct Paint<T> {
+    value: T,
+    color: Option<Color>,
+}
+
+/// Display `value` with `color`, or without colors if `color` is `None`.
+pub(crate) fn paint<T: Display>(value: T, color: Option<Color>) -> Paint<T> {
+    Paint { value, color }
+}
+
+impl<T: Display> Display for Paint<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.color {
+            Some(color) => write!(f, "\x1b[{}m{}\x1b[0m", color.code(), self.value),
+            None => write!(f, "{}", self.value),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Benchmarks of external commands.
+
+use std::io;
+use std::process::{Command, ExitStatus, Stdio};
+
+use crate::{Bench, BenchResult, Options};
+
+/// CPU time and memory of a child process.
+#[derive(Clone, Copy, Debug, Default)]
+struct ChildUsage {
+    user_ns: u64,
+    system_ns: u64,
+    max_rss_kib: u64,
+}
+
+impl Bench {
+    /// Run a single test of an external command, spawned and waited for on
+    /// every iteration, including the warm-up.
+    ///
+    /// The standard input, output and error of the command are redirected
+    /// to `/dev/null`. A command that can't be spawned, or that exits with a
+    /// non-zero status, stops the benchmark, and the error is returned.
+    ///
+    /// On Unix systems, the resources used by the command are recorded as
+    /// counters, per iteration: `user_time_ns` and `system_time_ns` for its
+    /// CPU time, and `max_rss_kib` for its peak memory usage.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    /// use std::process::Command;
+    ///
+    /// # if cfg!(unix) {
+    /// let bench = Bench::new();
+    /// let options = Options::quick()
+    ///     .into_builder()
+    ///     .iterations(3)
+    ///     .build()
+    ///     .unwrap();
+    /// let res = bench.run_command(&options, Command::new("true")).unwrap();
+    /// println!("true: {} per run", res.per_iteration_duration().as_micros());
+    ///
+    /// assert!(bench.run_command(&options, Command::new("false")).is_err());
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn run_command(&self, options: &Options, mut command: Command) -> io::Result<BenchResult> {
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        let mut calls = 0u64;
+        let mut total: Option<ChildUsage> = None;
+        let mut result = self.try_run(options, || {
+            calls += 1;
+            let (status, usage) = spawn_and_wait(&mut command)?;
+            if let Some(usage) = usage {
+                let total = total.get_or_insert_with(ChildUsage::default);
+                total.user_ns += usage.user_ns;
+                total.system_ns += usage.system_ns;
+                total.max_rss_kib += usage.max_rss_kib;
+            }
+            if !status.success() {
+                return Err(io::Error::other(format!(
+                    "{:?} exited with {}",
+                    command.get_program(),
+                    status
+                )));
+            }
+            Ok(())
+        })?;
+        if let Some(total) = total {
+            let calls = calls.max(1) as f64;
+            result.counters = [
+                ("user_time_ns", total.user_ns),
+                ("system_time_ns", total.system_ns),
+                ("max_rss_kib", total.max_rss_kib),
+            ]
+            .iter()
+            .map(|&(name, total)| (name.to_string(), total as f64 / calls))
+            .collect();
+        }
+        Ok(result)
+    }
+}
+
+/// Spawn the command and wait for it, returning its exit status and, if
+/// they are available, the resources it used.
+#[cfg(unix)]
+fn spawn_and_wait(command: &mut Command) -> io::Result<(ExitStatus, Option<ChildUsage>)> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let child = command.spawn()?;
+    let mut status = 0;
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    while unsafe { libc::wait4(child.id() as libc::pid_t, &mut status, 0, &mut usage) } < 0 {
+        let e = io::Error::last_os_error();
+        if e.kind() != io::ErrorKind::Interrupted {
+            return Err(e);
+        }
+    }
+    let ns = |tv: libc::timeval| tv.tv_sec as u64 * 1_000_000_000 + tv.tv_usec as u64 * 1_000;
+    // The peak RSS is in bytes on Apple platforms, and in KiB elsewhere.
+    let max_rss_kib = if cfg!(any(target_os = "macos", target_os = "ios")) {
+        usage.ru_maxrss as u64 / 1024
+    } else {
+        usage.ru_maxrss as u64
+    };
+    let usage = ChildUsage {
+        user_ns: ns(usage.ru_utime),
+        system_ns: ns(usage.ru_stime),
+        max_rss_kib,
+    };
+    Ok((ExitStatus::from_raw(status), Some(usage)))
+}
+
+/// Spawn the command and wait for it, returning its exit status and, if
+/// they are available, the resources it used.
+#[cfg(not(unix))]
+fn spawn_and_wait(command: &mut Command) -> io::Result<(ExitStatus, Option<ChildUsage>)> {
+    Ok((command.status()?, None))
+}
@@ -0,0 +1,387 @@
+//! Side-by-side comparison of two functions.
+
+use std::fmt::{self, Display, Formatter};
+use std::panic::Location;
+use std::sync::Arc;
+use std::time::Duration;
+
+use precision::Elapsed;
+
+use crate::engine::warm_up;
+use crate::{is_interrupted, stats, Bench, BenchResult, Options, StopReason, Volume};
+
+/// Significance level below which a difference is reported as significant.
+const SIGNIFICANCE_LEVEL: f64 = 0.05;
+
+/// The result of comparing two functions.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    a: BenchResult,
+    b: BenchResult,
+    p_value: Option<f64>,
+}
+
+impl Comparison {
+    /// Returns the result of the first function.
+    pub fn a(&self) -> &BenchResult {
+        &self.a
+    }
+
+    /// Returns the result of the second function.
+    pub fn b(&self) -> &BenchResult {
+        &self.b
+    }
+
+    /// Returns how many times faster the first function is than the second one.
+    /// Values below 1 mean that the first function is slower.
+    pub fn ratio(&self) -> f64 {
+        self.b.as_secs_f64() / self.a.as_secs_f64()
+    }
+
+    /// Returns the probability of observing such a difference if both
+    /// functions were equally fast (Welch's t-test on the samples), if
+    /// enough samples were collected.
+    pub fn p_value(&self) -> Option<f64> {
+        self.p_value
+    }
+
+    /// Returns `true` if the difference is statistically significant.
+    pub fn is_significant(&self) -> bool {
+        self.p_value.is_some_and(|p| p < SIGNIFICANCE_LEVEL)
+    }
+
+    /// Returns a one-line verdict using the given names for the functions,
+    /// such as `a is 1.52x faster than b (p = 0.003)`.
+    pub fn named<'a>(&'a self, a: &'a str, b: &'a str) -> impl Display + 'a {
+        Verdict {
+            comparison: self,
+            a,
+            b,
+        }
+    }
+}
+
+struct Verdict<'a> {
+    comparison: &'a Comparison,
+    a: &'a str,
+    b: &'a str,
+}
+
+impl Display for Verdict<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (comparison, a, b) = (self.comparison, self.a, self.b);
+        let ratio = comparison.ratio();
+        if !comparison.is_significant() {
+            write!(f, "no significant difference between {} and {}", a, b)?;
+        } else if ratio >= 1.0 {
+            write!(f, "{} is {:.2}x faster than {}", a, ratio, b)?;
+        } else {
+            write!(f, "{} is {:.2}x slower than {}", a, 1.0 / ratio, b)?;
+        }
+        write!(f, " [{:#} vs {:#}]", comparison.a, comparison.b)?;
+        match comparison.p_value {
+            Some(p) => write!(f, " (p = {:.3})", p),
+            None => write!(f, " (not enough samples)"),
+        }
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.named("A", "B"))
+    }
+}
+
+/// Variants of a function ranked from the fastest to the slowest, returned by
+/// [`Bench::compare_all()`].
+#[derive(Clone, Debug)]
+pub struct Ranking {
+    ranked: Vec<(String, BenchResult)>,
+    baseline: String,
+    volume: Option<Volume>,
+}
+
+impl Ranking {
+    /// Rank variants given in their original order, the first one being the
+    /// baseline.
+    fn new(ranked: Vec<(String, BenchResult)>) -> Self {
+        let baseline = ranked
+            .first()
+            .map(|(name, _)| name.clone())
+            .unwrap_or_default();
+        Ranking {
+            ranked,
+            baseline,
+            volume: None,
+        }
+    }
+
+    /// Returns the number of variants.
+    pub fn len(&self) -> usize {
+        self.ranked.len()
+    }
+
+    /// Returns `true` if there are no variants.
+    pub fn is_empty(&self) -> bool {
+        self.ranked.is_empty()
+    }
+
+    /// Returns the name and result of the fastest variant.
+    pub fn fastest(&self) -> Option<(&str, &BenchResult)> {
+        self.iter().next()
+    }
+
+    /// Returns the result of the variant `name`.
+    pub fn get(&self, name: &str) -> Option<&BenchResult> {
+        self.iter().find(|(n, _)| *n == name).map(|(_, r)| r)
+    }
+
+    /// Returns the names and results of the variants, from the fastest to
+    /// the slowest.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &BenchResult)> {
+        self.ranked
+            .iter()
+            .map(|(name, result)| (name.as_str(), result))
+    }
+
+    /// Returns how many times slower the variant `name` is than the fastest
+    /// one (`1.0` for the fastest one).
+    pub fn relative(&self, name: &str) -> Option<f64> {
+        let (_, fastest) = self.fastest()?;
+        Some(self.get(name)?.ratio(fastest))
+    }
+
+    /// Returns the name of the variant the other ones are compared to when
+    /// displayed. This is the first variant passed to
+    /// [`Bench::compare_all()`], unless it was changed with
+    /// [`Ranking::with_baseline()`].
+    pub fn baseline(&self) -> &str {
+        &self.baseline
+    }
+
+    /// Compare the variants to `name` when displayed. Unknown names are
+    /// ignored.
+    pub fn with_baseline(mut self, name: &str) -> Self {
+        if self.get(name).is_some() {
+            self.baseline = name.to_string();
+        }
+        self
+    }
+
+    /// Display throughputs, for `volume` processed in a single iteration,
+    /// instead of times.
+    pub fn with_volume(mut self, volume: Volume) -> Self {
+        self.volume = Some(volume);
+        self
+    }
+}
+
+/// A line per variant, from the fastest to the slowest, with its time or
+/// throughput, its speed relative to the baseline and its RSD, such as
+/// `avx2: 3.42 GB/s (2.10× faster than scalar, ±1.8%)`.
+impl Display for Ranking {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let baseline = self.get(&self.baseline);
+        for (i, (name, result)) in self.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            match self.volume {
+                Some(volume) => {
+                    write!(f, "{}: {}", name, result.clone().throughput_volume(volume))?
+                }
+                None => write!(f, "{}: {}", name, result)?,
+            }
+            let speedup = baseline.map_or(f64::NAN, |baseline| baseline.ratio(result));
+            if name == self.baseline {
+                write!(f, " (baseline")?;
+            } else if speedup >= 1.0 && speedup.is_finite() {
+                write!(f, " ({:.2}× faster than {}", speedup, self.baseline)?;
+            } else if speedup > 0.0 {
+                write!(f, " ({:.2}× slower than {}", 1.0 / speedup, self.baseline)?;
+            } else {
+                write!(f, " (n/a")?;
+            }
+            match result.rsd() {
+                Some(rsd) => write!(f, ", ±{:.1}%)", rsd)?,
+                None => write!(f, ")")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Bench {
+    /// Compare two functions.
+    ///
+    /// Samples of both functions are interleaved, so that changes in the
+    /// environment affect them equally. Sampling stops when the RSD of both
+    /// functions is below `max_rsd`, or when other limits are reached.
+    #[track_caller]
+    pub fn compare<F, G, H, I>(&self, options: &Options, mut a: F, mut b: H) -> Comparison
+    where
+        F: FnMut() -> G,
+        H: FnMut() -> I,
+    {
+        let location = Location::caller();
+        let options = Arc::new(options.clone());
+        if options.dry_run {
+            crate::black_box(a());
+            crate::black_box(b());
+            return Comparison {
+                a: self.result(Elapsed::from_ticks(0), options.clone(), location),
+                b: self.result(Elapsed::from_ticks(0), options, location),
+                p_value: None,
+            };
+        }
+        warm_up(&options, &self.precision, || {
+            crate::black_box(a());
+            crate::black_box(b());
+        });
+        let (mut results, stop_reason) = self.interleave(&options, 2, |i| match i {
+            0 => self.run_once(options.clone(), location, &mut a),
+            _ => self.run_once(options.clone(), location, &mut b),
+        });
+        let (results_b, results_a) = (results.pop().unwrap(), results.pop().unwrap());
+        let p_value = stats::welch_t_test(&secs(&results_a), &secs(&results_b));
+        Comparison {
+            a: summarize(results_a, stop_reason),
+            b: summarize(results_b, stop_reason),
+            p_value,
+        }
+    }
+
+    /// Compare any number of variants of a function, and rank them from the
+    /// fastest to the slowest.
+    ///
+    /// Samples of all the variants are interleaved, as with
+    /// [`Bench::compare()`], and sampling stops when the RSD of every
+    /// variant is below `max_rsd`, or when other limits are reached. The
+    /// variants must have the same type: closures that don't capture
+    /// anything, functions, or boxed closures.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// fn scalar() -> u64 {
+    ///     (0..1000u64).sum()
+    /// }
+    ///
+    /// fn unrolled() -> u64 {
+    ///     (0..1000u64).step_by(2).map(|i| i + (i + 1)).sum()
+    /// }
+    ///
+    /// let bench = Bench::new();
+    /// let ranking = bench.compare_all(
+    ///     &Options::default(),
+    ///     [("scalar", scalar as fn() -> u64), ("unrolled", unrolled)],
+    /// );
+    /// println!("{}", ranking);
+    /// assert_eq!(ranking.len(), 2);
+    /// ```
+    #[track_caller]
+    pub fn compare_all<I, N, F, G>(&self, options: &Options, variants: I) -> Ranking
+    where
+        I: IntoIterator<Item = (N, F)>,
+        N: Into<String>,
+        F: FnMut() -> G,
+    {
+        let location = Location::caller();
+        let options = Arc::new(options.clone());
+        let (names, mut fs): (Vec<String>, Vec<F>) = variants
+            .into_iter()
+            .map(|(name, f)| (name.into(), f))
+            .unzip();
+        if options.dry_run {
+            let ranked = names
+                .into_iter()
+                .zip(fs.iter_mut())
+                .map(|(name, f)| {
+                    crate::black_box(f());
+                    let result = self.result(Elapsed::from_ticks(0), options.clone(), location);
+                    (name, result)
+                })
+                .collect();
+            return Ranking::new(ranked);
+        }
+        warm_up(&options, &self.precision, || {
+            for f in fs.iter_mut() {
+                crate::black_box(f());
+            }
+        });
+        let (results, stop_reason) = self.interleave(&options, fs.len(), |i| {
+            self.run_once(options.clone(), location, &mut fs[i])
+        });
+        let ranked: Vec<_> = names
+            .into_iter()
+            .zip(results)
+            .map(|(name, results)| {
+                let result = summarize(results, stop_reason).with_name(name.as_str());
+                (name, result)
+            })
+            .collect();
+        let mut ranking = Ranking::new(ranked);
+        ranking.ranked.sort_by_key(|(_, result)| result.as_ns());
+        ranking
+    }
+
+    /// Collect samples of `count` functions, `sample(i)` measuring the
+    /// function `i`, in a different order for every round.
+    ///
+    /// Returns the samples of every function, and why sampling stopped.
+    fn interleave(
+        &self,
+        options: &Options,
+        count: usize,
+        mut sample: impl FnMut(usize) -> BenchResult,
+    ) -> (Vec<Vec<BenchResult>>, StopReason) {
+        let max_samples = std::cmp::max(1, options.max_samples);
+        let start = self.precision.now();
+        let mut results = vec![Vec::new(); count];
+        if count == 0 {
+            return (results, StopReason::MaxSamples);
+        }
+        for i in 1..=max_samples {
+            if i > 1 && is_interrupted() {
+                return (results, StopReason::Interrupted);
+            }
+            // Rotate the order, so that no function always runs first.
+            for k in 0..count {
+                let j = (i - 1 + k) % count;
+                results[j].push(sample(j));
+            }
+            let converged = results.iter().all(|results| {
+                stats::rsd(&secs(results))
+                    .is_some_and(|rsd| options.is_converged(rsd, results.len()))
+            });
+            if i >= options.min_samples && converged {
+                return (results, StopReason::Converged);
+            }
+            if let Some(max_duration) = options.max_duration {
+                if self.elapsed_since(start) >= max_duration {
+                    return (results, StopReason::MaxDuration);
+                }
+            }
+        }
+        (results, StopReason::MaxSamples)
+    }
+}
+
+fn secs(results: &[BenchResult]) -> Vec<f64> {
+    results.iter().map(|r| r.as_secs_f64()).collect()
+}
+
+/// Returns the fastest sample, along with statistics about all of them.
+fn summarize(results: Vec<BenchResult>, stop_reason: StopReason) -> BenchResult {
+    let rsd = stats::rsd(&secs(&results));
+    let samples: Arc<[Duration]> = results
+        .iter()
+        .map(|r| Duration::from_nanos(r.as_ns()))
+        .collect();
+    let mut result = results.into_iter().min_by_key(|r| r.as_ns()).unwrap();
+    result.samples = samples;
+    result.rsd = rsd;
+    result.interrupted = stop_reason == StopReason::Interrupted;
+    result.stop_reason = Some(stop_reason);
+    result
+}
@@ -0,0 +1,101 @@
+//! Throughput of concurrent clients.
+
+use std::panic::{self, Location};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::Duration;
+
+use crate::{black_box_ref, Bench, Options, Throughput};
+
+/// Duration of [`Bench::run_concurrent()`] without `max_duration`.
+const DEFAULT_DURATION: Duration = Duration::from_secs(1);
+
+impl Bench {
+    /// Call `f` repeatedly from `workers` threads for `max_duration` (one
+    /// second if it is not set), and return the aggregate throughput, in
+    /// completed calls per second.
+    ///
+    /// This measures shared resources under contention, such as connection
+    /// pools or data structures behind a lock. Every worker runs
+    /// `warmup_iterations` calls before all of them start together, and
+    /// calls that were running when time was up are included. A panic in
+    /// `f` is propagated to the caller.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    /// use std::sync::Mutex;
+    /// use std::time::Duration;
+    ///
+    /// let bench = Bench::new();
+    /// let options = Options::builder()
+    ///     .max_duration(Duration::from_millis(50))
+    ///     .build()
+    ///     .unwrap();
+    /// let counter = Mutex::new(0u64);
+    /// let throughput = bench.run_concurrent(&options, 4, || *counter.lock().unwrap() += 1);
+    /// println!("{}", throughput);
+    /// ```
+    #[track_caller]
+    pub fn run_concurrent<F, G>(&self, options: &Options, workers: usize, f: F) -> Throughput
+    where
+        F: Fn() -> G + Sync,
+    {
+        let location = Location::caller();
+        let workers = workers.max(1);
+        if options.dry_run {
+            black_box_ref(&f());
+            return self
+                .result(Default::default(), Arc::new(options.clone()), location)
+                .throughput_items(1);
+        }
+        let duration = options.max_duration.unwrap_or(DEFAULT_DURATION);
+        let stop = AtomicBool::new(false);
+        let barrier = Barrier::new(workers + 1);
+        let (calls, elapsed) = thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        for _ in 0..options.warmup_iterations {
+                            black_box_ref(&f());
+                        }
+                        barrier.wait();
+                        let mut calls = 0u64;
+                        while !stop.load(Ordering::Relaxed) {
+                            black_box_ref(&f());
+                            calls += 1;
+                        }
+                        calls
+                    })
+                })
+                .collect();
+            barrier.wait();
+            let start = self.precision.now();
+            thread::sleep(duration);
+            stop.store(true, Ordering::Relaxed);
+            let mut calls = 0;
+            for handle in handles {
+                match handle.join() {
+                    Ok(n) => calls += n,
+                    Err(payload) => panic::resume_unwind(payload),
+                }
+            }
+            (calls, self.precision.now() - start)
+        });
+        if options.verbose {
+            log!(
+                self,
+                "{} calls from {} workers in {:?}.",
+                calls,
+                workers,
+                Duration::from_nanos(self.precision.as_ns(elapsed))
+            );
+        }
+        let options = Options {
+            iterations: calls.max(1),
+            ..options.clone()
+        };
+        self.result(elapsed, Arc::new(options), location)
+            .throughput_items(1)
+    }
+}
@@ -0,0 +1,204 @@
+//! Options loaded from a TOML configuration file.
+//!
+//! Only available with the `toml` feature.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::path::Path;
+
+use crate::options::parse_duration;
+use crate::{Options, OptionsError, Priority};
+
+/// An error returned when a configuration file can't be loaded.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file couldn't be read.
+    Io(io::Error),
+    /// The file is not valid TOML.
+    Parse(String),
+    /// A key is not a known option.
+    UnknownKey(String),
+    /// A value has an unexpected type or format.
+    InvalidValue(String),
+    /// The resulting options are inconsistent.
+    Options(OptionsError),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "unable to read the configuration: {}", e),
+            ConfigError::Parse(e) => write!(f, "invalid configuration: {}", e),
+            ConfigError::UnknownKey(key) => write!(f, "unknown option: {}", key),
+            ConfigError::InvalidValue(key) => write!(f, "invalid value for option: {}", key),
+            ConfigError::Options(e) => write!(f, "invalid options: {}", e),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl From<OptionsError> for ConfigError {
+    fn from(e: OptionsError) -> Self {
+        ConfigError::Options(e)
+    }
+}
+
+/// Options for a set of benchmarks: global defaults, plus per-benchmark overrides.
+///
+/// ```toml
+/// max_rsd = 2.0
+/// max_duration = "10s"
+///
+/// [benchmarks."aes256gcm/encrypt"]
+/// iterations = 10000
+/// ```
+///
+/// Environment variables such as `BENCHMARK_MAX_RSD` still take precedence
+/// over the file.
+#[derive(Clone, Debug)]
+pub struct OptionsConfig {
+    defaults: Options,
+    overrides: HashMap<String, toml::Table>,
+}
+
+impl OptionsConfig {
+    /// Load a configuration file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse a configuration.
+    pub fn parse(config: &str) -> Result<Self, ConfigError> {
+        let mut table = config
+            .parse::<toml::Table>()
+            .map_err(|e| ConfigError::Parse(e.to_string()))?;
+        let mut overrides = HashMap::new();
+        if let Some(benchmarks) = table.remove("benchmarks") {
+            let benchmarks = match benchmarks {
+                toml::Value::Table(benchmarks) => benchmarks,
+                _ => return Err(ConfigError::InvalidValue("benchmarks".to_string())),
+            };
+            for (name, value) in benchmarks {
+                match value {
+                    toml::Value::Table(value) => {
+                        overrides.insert(name, value);
+                    }
+                    _ => return Err(ConfigError::InvalidValue(format!("benchmarks.{}", name))),
+                }
+            }
+        }
+        let mut defaults = Options::base();
+        apply(&mut defaults, &table)?;
+        let config = OptionsConfig {
+            defaults,
+            overrides,
+        };
+        config.defaults().validate()?;
+        for name in config.overrides.keys() {
+            config.try_for_benchmark(name)?;
+        }
+        Ok(config)
+    }
+
+    /// Returns the global options.
+    pub fn defaults(&self) -> Options {
+        self.defaults.clone().with_env_overrides()
+    }
+
+    /// Returns the options for the benchmark with the given name.
+    pub fn for_benchmark(&self, name: &str) -> Options {
+        // Overrides have already been checked when the configuration was loaded.
+        self.try_for_benchmark(name)
+            .unwrap_or_else(|_| self.defaults())
+    }
+
+    fn try_for_benchmark(&self, name: &str) -> Result<Options, ConfigError> {
+        let mut options = self.defaults.clone();
+        if let Some(overrides) = self.overrides.get(name) {
+            apply(&mut options, overrides)?;
+        }
+        let options = options.with_env_overrides();
+        options.validate()?;
+        Ok(options)
+    }
+}
+
+impl Options {
+    /// Load options from a TOML configuration file.
+    ///
+    /// Per-benchmark sections are ignored; use [`OptionsConfig`] to access them.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Ok(OptionsConfig::from_file(path)?.defaults())
+    }
+}
+
+fn apply(options: &mut Options, table: &toml::Table) -> Result<(), ConfigError> {
+    for (key, value) in table {
+        let invalid = || ConfigError::InvalidValue(key.clone());
+        let integer = || {
+            value
+                .as_integer()
+                .filter(|&n| n >= 0)
+                .map(|n| n as u64)
+                .ok_or_else(invalid)
+        };
+        let float = || match value {
+            toml::Value::Float(x) => Ok(*x),
+            toml::Value::Integer(n) => Ok(*n as f64),
+            _ => Err(invalid()),
+        };
+        let duration = || match value {
+            toml::Value::String(s) => parse_duration(s).ok_or_else(invalid),
+            toml::Value::Integer(n) if *n >= 0 => Ok(std::time::Duration::from_secs(*n as u64)),
+            toml::Value::Float(x) => parse_duration(&x.to_string()).ok_or_else(invalid),
+            _ => Err(invalid()),
+        };
+        match key.as_str() {
+            "iterations" => options.iterations = integer()?,
+            "warmup_iterations" => options.warmup_iterations = integer()?,
+            "warmup_time" => options.warmup_time = Some(duration()?),
+            "sample_time" => options.sample_time = Some(duration()?),
+            "min_samples" => options.min_samples = integer()? as usize,
+            "max_samples" => options.max_samples = integer()? as usize,
+            "max_rsd" => options.max_rsd = float()?,
+            "max_duration" => options.max_duration = Some(duration()?),
+            "sample_timeout" => options.sample_timeout = Some(duration()?),
+            "abort_on_sample_timeout" => {
+                options.abort_on_sample_timeout = value.as_bool().ok_or_else(invalid)?
+            }
+            "verbose" => options.verbose = value.as_bool().ok_or_else(invalid)?,
+            "dry_run" => options.dry_run = value.as_bool().ok_or_else(invalid)?,
+            "detect_throttling" => {
+                options.detect_throttling = value.as_bool().ok_or_else(invalid)?
+            }
+            "throttling_retries" => options.throttling_retries = integer()? as usize,
+            "iteration_jitter" => options.iteration_jitter = float()?,
+            "priority" => {
+                options.priority = match value.as_str() {
+                    Some("normal") => Priority::Normal,
+                    Some("high") => Priority::High,
+                    Some("realtime") => Priority::Realtime,
+                    _ => return Err(invalid()),
+                }
+            }
+            "cold_cache" => options.cold_cache = value.as_bool().ok_or_else(invalid)?,
+            "auto_iterations" => options.auto_iterations = value.as_bool().ok_or_else(invalid)?,
+            "cooldown" => options.cooldown = Some(duration()?),
+            "discard_samples" => options.discard_samples = integer()? as usize,
+            "extend_samples" => options.extend_samples = value.as_bool().ok_or_else(invalid)?,
+            "max_ci_width" => options.max_ci_width = Some(float()?),
+            "resource_usage" => options.resource_usage = value.as_bool().ok_or_else(invalid)?,
+            _ => return Err(ConfigError::UnknownKey(key.clone())),
+        }
+    }
+    Ok(())
+}
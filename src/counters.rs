@@ -0,0 +1,82 @@
+//! User-defined counters, recorded along with the time.
+
+use std::cell::RefCell;
+use std::panic::Location;
+
+use crate::{Bench, BenchResult, Options};
+
+/// Named counters, such as cache hits or bytes written, recorded by the
+/// function passed to [`Bench::run_with_counters()`].
+#[derive(Debug, Default)]
+pub struct Counters {
+    values: RefCell<Vec<(String, u64)>>,
+}
+
+impl Counters {
+    /// Add `value` to the counter `name`.
+    pub fn add(&self, name: &str, value: u64) {
+        let mut values = self.values.borrow_mut();
+        match values.iter_mut().find(|(n, _)| n == name) {
+            Some((_, total)) => *total += value,
+            None => values.push((name.to_string(), value)),
+        }
+    }
+
+    /// Add one to the counter `name`.
+    pub fn increment(&self, name: &str) {
+        self.add(name, 1)
+    }
+}
+
+impl Bench {
+    /// Run a single test, passing [`Counters`] to `f`, for it to record
+    /// auxiliary metrics.
+    ///
+    /// Counters are averaged over all the calls to `f`, and
+    /// [`BenchResult::counter()`] returns their value per iteration. They
+    /// are included in the JSON report and in the serialized results.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let bench = Bench::new();
+    /// let options = Options::quick()
+    ///     .into_builder()
+    ///     .iterations(100)
+    ///     .build()
+    ///     .unwrap();
+    /// let mut cache = HashMap::new();
+    /// let mut i = 0u64;
+    /// let res = bench.run_with_counters(&options, |counters| {
+    ///     i += 1;
+    ///     let key = i % 4;
+    ///     if cache.contains_key(&key) {
+    ///         counters.increment("hits");
+    ///     } else {
+    ///         cache.insert(key, key * 2);
+    ///     }
+    /// });
+    /// assert!(res.counter("hits").unwrap() > 0.9);
+    /// ```
+    #[track_caller]
+    pub fn run_with_counters<F, G>(&self, options: &Options, mut f: F) -> BenchResult
+    where
+        F: FnMut(&Counters) -> G,
+    {
+        let counters = Counters::default();
+        let mut calls = 0u64;
+        let mut result = self.run_at(options, Location::caller(), || {
+            calls += 1;
+            f(&counters)
+        });
+        let calls = calls.max(1) as f64;
+        result.counters = counters
+            .values
+            .into_inner()
+            .into_iter()
+            .map(|(name, total)| (name, total as f64 / calls))
+            .collect();
+        result
+    }
+}
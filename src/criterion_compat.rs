@@ -0,0 +1,240 @@
+//! A subset of the criterion API, to ease migrations.
+//!
+//! Existing bench files can be switched by replacing `use criterion::*`
+//! with `use benchmark_simple::criterion_compat::*`, and converted to the
+//! native API over time. `c.bench_function()`, benchmark groups with
+//! `throughput()`, `bench_with_input()`, and the `criterion_group!` and
+//! `criterion_main!` macros are supported.
+//!
+//! ```rust,no_run
+//! use benchmark_simple::criterion_compat::*;
+//! use benchmark_simple::{criterion_group, criterion_main};
+//!
+//! fn sum(c: &mut Criterion) {
+//!     c.bench_function("sum", |b| b.iter(|| (0..1000u64).sum::<u64>()));
+//!
+//!     let mut group = c.benchmark_group("sum_bytes");
+//!     for size in [16, 1024] {
+//!         let data = vec![1u8; size];
+//!         group.throughput(Throughput::Bytes(size as u64));
+//!         group.bench_with_input(BenchmarkId::from_parameter(size), &data, |b, data| {
+//!             b.iter(|| data.iter().map(|&x| x as u64).sum::<u64>())
+//!         });
+//!     }
+//!     group.finish();
+//! }
+//!
+//! criterion_group!(benches, sum);
+//! criterion_main!(benches);
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+
+pub use crate::black_box;
+pub use crate::Bencher;
+use crate::{Bench, BenchResult, Environment, Options, Report};
+
+/// The amount of data processed by a single iteration.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Throughput {
+    /// A number of bytes.
+    Bytes(u64),
+    /// A number of elements.
+    Elements(u64),
+}
+
+/// The identifier of a benchmark within a group.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BenchmarkId {
+    id: String,
+}
+
+impl BenchmarkId {
+    /// An identifier made of a function name and a parameter.
+    pub fn new(function_name: impl Into<String>, parameter: impl Display) -> Self {
+        BenchmarkId {
+            id: format!("{}/{}", function_name.into(), parameter),
+        }
+    }
+
+    /// An identifier made of a parameter only.
+    pub fn from_parameter(parameter: impl Display) -> Self {
+        BenchmarkId {
+            id: parameter.to_string(),
+        }
+    }
+}
+
+impl Display for BenchmarkId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+impl From<&str> for BenchmarkId {
+    fn from(id: &str) -> Self {
+        BenchmarkId { id: id.to_string() }
+    }
+}
+
+impl From<String> for BenchmarkId {
+    fn from(id: String) -> Self {
+        BenchmarkId { id }
+    }
+}
+
+/// The benchmark manager, running benchmarks as they are defined.
+pub struct Criterion {
+    bench: Bench,
+    options: Options,
+    report: Report,
+}
+
+impl Default for Criterion {
+    fn default() -> Self {
+        Criterion {
+            bench: Bench::new(),
+            options: Options::default(),
+            report: Report::new(Environment::detect()),
+        }
+    }
+}
+
+impl Criterion {
+    /// Use these options instead of the default ones.
+    pub fn with_options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Run a benchmark.
+    #[track_caller]
+    pub fn bench_function<F>(&mut self, id: impl Into<BenchmarkId>, f: F) -> &mut Self
+    where
+        F: FnMut(&mut Bencher<'_>),
+    {
+        let options = self.options.clone();
+        self.run(id.into().to_string(), &options, None, f);
+        self
+    }
+
+    /// Create a group of related benchmarks.
+    pub fn benchmark_group(&mut self, name: impl Into<String>) -> BenchmarkGroup<'_> {
+        BenchmarkGroup {
+            options: self.options.clone(),
+            criterion: self,
+            name: name.into(),
+            throughput: None,
+        }
+    }
+
+    /// Returns the results collected so far.
+    pub fn report(&self) -> &Report {
+        &self.report
+    }
+
+    /// Print a summary of all the results.
+    pub fn final_summary(&self) {
+        self.report.print();
+    }
+
+    #[track_caller]
+    fn run<F>(&mut self, name: String, options: &Options, throughput: Option<Throughput>, mut f: F)
+    where
+        F: FnMut(&mut Bencher<'_>),
+    {
+        let result = match self.bench.run_bencher(options, |b| f(b)) {
+            Some(result) => result,
+            None => return,
+        };
+        match throughput {
+            Some(throughput) => println!("{}: {:#} ({})", name, result, rate(&result, throughput)),
+            None => println!("{}: {:#}", name, result),
+        }
+        self.report.push(name, result);
+    }
+}
+
+fn rate(result: &BenchResult, throughput: Throughput) -> crate::Throughput {
+    match throughput {
+        Throughput::Bytes(bytes) => result.clone().throughput_bytes(bytes as u128),
+        Throughput::Elements(elements) => result.clone().throughput(elements as u128),
+    }
+}
+
+/// A group of related benchmarks, named `group/id`.
+pub struct BenchmarkGroup<'a> {
+    criterion: &'a mut Criterion,
+    options: Options,
+    name: String,
+    throughput: Option<Throughput>,
+}
+
+impl BenchmarkGroup<'_> {
+    /// Set the amount of data processed by the following benchmarks.
+    pub fn throughput(&mut self, throughput: Throughput) -> &mut Self {
+        self.throughput = Some(throughput);
+        self
+    }
+
+    /// Set the number of samples to collect for the benchmarks of this group.
+    pub fn sample_size(&mut self, samples: usize) -> &mut Self {
+        self.options.max_samples = samples;
+        self.options.min_samples = self.options.min_samples.min(samples);
+        self
+    }
+
+    /// Run a benchmark.
+    #[track_caller]
+    pub fn bench_function<F>(&mut self, id: impl Into<BenchmarkId>, f: F) -> &mut Self
+    where
+        F: FnMut(&mut Bencher<'_>),
+    {
+        let name = format!("{}/{}", self.name, id.into());
+        self.criterion.run(name, &self.options, self.throughput, f);
+        self
+    }
+
+    /// Run a benchmark with an input.
+    #[track_caller]
+    pub fn bench_with_input<F, I>(
+        &mut self,
+        id: impl Into<BenchmarkId>,
+        input: &I,
+        mut f: F,
+    ) -> &mut Self
+    where
+        F: FnMut(&mut Bencher<'_>, &I),
+        I: ?Sized,
+    {
+        let name = format!("{}/{}", self.name, id.into());
+        self.criterion
+            .run(name, &self.options, self.throughput, |b| f(b, input));
+        self
+    }
+
+    /// Finish the group.
+    pub fn finish(self) {}
+}
+
+/// Define a function running a group of criterion-style benchmarks.
+#[macro_export]
+macro_rules! criterion_group {
+    ($name:ident, $($target:path),+ $(,)?) => {
+        pub fn $name() {
+            let mut criterion = $crate::criterion_compat::Criterion::default();
+            $($target(&mut criterion);)+
+            criterion.final_summary();
+        }
+    };
+}
+
+/// Define a `main()` function running groups defined with `criterion_group!`.
+#[macro_export]
+macro_rules! criterion_main {
+    ($($group:path),+ $(,)?) => {
+        fn main() {
+            $($group();)+
+        }
+    };
+}
@@ -0,0 +1,101 @@
+//! Deterministic, seeded input generators.
+//!
+//! The same seed produces the same data on every machine and in every run,
+//! so that results can be compared without input variation adding noise.
+//! The generator is SplitMix64: fast, but not suitable for cryptography.
+//!
+//! ```rust
+//! use benchmark_simple::data;
+//!
+//! let input = data::random_bytes(42, 16384);
+//! assert_eq!(input, data::random_bytes(42, 16384));
+//!
+//! let text = data::random_words(42, 1000);
+//! assert_eq!(text.len(), 1000);
+//! ```
+
+/// A seeded pseudo-random number generator (SplitMix64).
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// Create a generator from a seed. Any seed is fine, including 0.
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    /// Returns the next random number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`. `n` must not be 0.
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+
+    /// Fill `out` with random bytes.
+    pub fn fill(&mut self, out: &mut [u8]) {
+        for chunk in out.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+/// Returns `len` random bytes.
+pub fn random_bytes(seed: u64, len: usize) -> Vec<u8> {
+    let mut out = vec![0; len];
+    Rng::new(seed).fill(&mut out);
+    out
+}
+
+/// Returns `len` random printable ASCII characters.
+pub fn random_ascii(seed: u64, len: usize) -> String {
+    let mut rng = Rng::new(seed);
+    (0..len)
+        .map(|_| (b' ' + rng.below(95) as u8) as char)
+        .collect()
+}
+
+/// Returns `len` bytes of text: random lowercase words of 1 to 10 letters,
+/// separated by spaces. This resembles the input of tokenizers and parsers.
+pub fn random_words(seed: u64, len: usize) -> String {
+    let mut rng = Rng::new(seed);
+    let mut out = String::with_capacity(len);
+    while out.len() < len {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        for _ in 0..1 + rng.below(10) {
+            out.push((b'a' + rng.below(26) as u8) as char);
+        }
+    }
+    out.truncate(len);
+    out
+}
+
+/// Returns `len` random 64-bit integers.
+pub fn random_u64s(seed: u64, len: usize) -> Vec<u64> {
+    let mut rng = Rng::new(seed);
+    (0..len).map(|_| rng.next_u64()).collect()
+}
+
+/// Returns `len` random 64-bit integers, in ascending order.
+pub fn sorted_u64s(seed: u64, len: usize) -> Vec<u64> {
+    let mut out = random_u64s(seed, len);
+    out.sort_unstable();
+    out
+}
+
+/// Returns `len` bytes repeating `pattern`, such as highly compressible data.
+///
+/// Panics if `pattern` is empty.
+pub fn repeating(pattern: &[u8], len: usize) -> Vec<u8> {
+    assert!(!pattern.is_empty(), "empty pattern");
+    pattern.iter().copied().cycle().take(len).collect()
+}
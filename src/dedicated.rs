@@ -0,0 +1,125 @@
+//! Running benchmarks on a dedicated, freshly spawned thread.
+//!
+//! The thread gets a large stack whose pages are touched before measuring,
+//! so that stack growth never causes page faults in the measured loop, and
+//! the layout of the stack doesn't depend on what the caller was doing.
+
+use std::io;
+use std::panic::{self, Location};
+use std::thread;
+
+use crate::{black_box, Bench, BenchResult, Options};
+
+/// Settings of the thread used by [`Bench::run_on_thread()`].
+#[derive(Clone, Debug)]
+pub struct ThreadOptions {
+    /// Size of the stack, in bytes. Most of it is pre-faulted.
+    pub stack_size: usize,
+    /// Pin the thread to this CPU. Only supported on Linux.
+    pub cpu: Option<usize>,
+}
+
+impl Default for ThreadOptions {
+    fn default() -> Self {
+        ThreadOptions {
+            stack_size: 16 * 1024 * 1024,
+            cpu: None,
+        }
+    }
+}
+
+impl ThreadOptions {
+    /// Set the stack size, in bytes.
+    pub fn stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = stack_size;
+        self
+    }
+
+    /// Pin the thread to a CPU.
+    pub fn cpu(mut self, cpu: usize) -> Self {
+        self.cpu = Some(cpu);
+        self
+    }
+}
+
+impl Bench {
+    /// Run a single test on a new thread, created for this benchmark only.
+    ///
+    /// The thread doesn't share any state the calling thread accumulated,
+    /// such as thread-local allocator caches, and its stack is pre-faulted.
+    /// `options.priority` applies to that thread. The result is the same as
+    /// with [`Bench::run()`], and a panic in `f` is propagated to the caller.
+    ///
+    /// If the thread can't be spawned, a warning is printed and the
+    /// benchmark runs on the calling thread.
+    #[track_caller]
+    pub fn run_on_thread<F, G>(
+        &self,
+        options: &Options,
+        thread_options: &ThreadOptions,
+        mut f: F,
+    ) -> BenchResult
+    where
+        F: FnMut() -> G + Send,
+    {
+        let location = Location::caller();
+        let spawned = thread::scope(|scope| {
+            let handle = thread::Builder::new()
+                .name("benchmark".to_string())
+                .stack_size(thread_options.stack_size)
+                .spawn_scoped(scope, || {
+                    if let Some(cpu) = thread_options.cpu {
+                        if let Err(e) = pin_to_cpu(cpu) {
+                            eprintln!("Unable to pin the benchmark thread to CPU {}: {}", cpu, e);
+                        }
+                    }
+                    // Leave room for the frames of the benchmark itself.
+                    prefault_stack(thread_options.stack_size / 4 * 3);
+                    self.run_at(options, location, &mut f)
+                })?;
+            Ok::<_, io::Error>(handle.join())
+        });
+        match spawned {
+            Ok(Ok(result)) => result,
+            Ok(Err(payload)) => panic::resume_unwind(payload),
+            Err(e) => {
+                eprintln!("Unable to spawn a benchmark thread: {}", e);
+                self.run_at(options, location, f)
+            }
+        }
+    }
+}
+
+/// Touch `size` bytes of the stack, so that the pages are mapped before the
+/// benchmark runs.
+#[inline(never)]
+fn prefault_stack(size: usize) {
+    const FRAME_SIZE: usize = 16 * 1024;
+    let mut frame = [0u8; FRAME_SIZE];
+    black_box(&mut frame);
+    if size > FRAME_SIZE {
+        prefault_stack(size - FRAME_SIZE);
+    }
+    // Keep the frame alive across the recursive call.
+    black_box(&frame);
+}
+
+#[cfg(target_os = "linux")]
+fn pin_to_cpu(cpu: usize) -> io::Result<()> {
+    if cpu >= libc::CPU_SETSIZE as usize {
+        return Err(io::ErrorKind::InvalidInput.into());
+    }
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(cpu, &mut set);
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_to_cpu(_cpu: usize) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
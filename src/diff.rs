@@ -0,0 +1,265 @@
+//! Comparisons of two saved sets of results.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::color::{self, paint, Color};
+use crate::{format_secs, Baseline};
+
+/// The comparison of two sets of results, returned by [`Baseline::diff()`].
+///
+/// It is displayed as a table with the time of an iteration before and
+/// after, the relative change, and whether the change is a significant
+/// regression or improvement.
+#[derive(Clone, Copy, Debug)]
+pub struct BaselineDiff<'a> {
+    old: &'a Baseline,
+    new: &'a Baseline,
+    threshold: f64,
+    normalized: bool,
+    colors: bool,
+}
+
+/// A benchmark of a [`BaselineDiff`].
+struct Row<'a> {
+    name: &'a str,
+    old: Option<f64>,
+    new: Option<f64>,
+    /// Relative change (in %), and whether it is significant.
+    change: Option<(f64, bool)>,
+}
+
+impl Baseline {
+    /// Compare `new` results with these ones, such as the results of a pull
+    /// request with those of the main branch.
+    ///
+    /// A change is significant if it is larger than `threshold` (in %) and
+    /// than the sum of the margins of error of both results, if known.
+    ///
+    /// ```rust
+    /// use benchmark_simple::Baseline;
+    ///
+    /// let old = Baseline::from_json(r#"{"results": [{"name": "sum", "ns": 1000}]}"#).unwrap();
+    /// let new = Baseline::from_json(r#"{"results": [{"name": "sum", "ns": 1500}]}"#).unwrap();
+    /// let diff = old.diff(&new, 5.0);
+    /// assert_eq!(diff.regressions().collect::<Vec<_>>(), ["sum"]);
+    /// println!("{}", diff);
+    /// ```
+    pub fn diff<'a>(&'a self, new: &'a Baseline, threshold: f64) -> BaselineDiff<'a> {
+        BaselineDiff {
+            old: self,
+            new,
+            threshold,
+            normalized: false,
+            colors: false,
+        }
+    }
+}
+
+impl<'a> BaselineDiff<'a> {
+    /// Compare results from different machines, by scaling the new times
+    /// by the ratio of the [calibration scores](crate::Bench::calibration_score)
+    /// of the machines, as if the new results came from the old machine.
+    ///
+    /// This only applies if both sets have a calibration score, which
+    /// [`BaselineDiff::is_normalized()`] tells.
+    ///
+    /// The score is a single workload: it approximates the difference
+    /// between machines, not the one for every benchmark.
+    ///
+    /// ```rust
+    /// use benchmark_simple::Baseline;
+    ///
+    /// let old = r#"{"environment": {"calibration_score": 100}, "results": [{"name": "sum", "ns": 1000}]}"#;
+    /// // Twice as fast a machine, but not twice as fast a result.
+    /// let new = r#"{"environment": {"calibration_score": 200}, "results": [{"name": "sum", "ns": 600}]}"#;
+    /// let (old, new) = (Baseline::from_json(old).unwrap(), Baseline::from_json(new).unwrap());
+    /// assert_eq!(old.diff(&new, 5.0).regressions().count(), 0);
+    /// let diff = old.diff(&new, 5.0).normalized();
+    /// assert!(diff.is_normalized());
+    /// assert_eq!(diff.regressions().collect::<Vec<_>>(), ["sum"]);
+    /// ```
+    pub fn normalized(mut self) -> Self {
+        self.normalized = true;
+        self
+    }
+
+    /// Returns `true` if the new times are normalized by the calibration
+    /// scores of the machines.
+    pub fn is_normalized(&self) -> bool {
+        self.normalization().is_some()
+    }
+
+    /// Returns the microarchitectures of the old and new machines, if both
+    /// are known and they differ.
+    ///
+    /// Changes between such results may come from the hardware rather than
+    /// from the code, and the comparison shows a warning. Normalizing the
+    /// times only partly accounts for this.
+    ///
+    /// ```rust
+    /// use benchmark_simple::Baseline;
+    ///
+    /// let old = r#"{"environment": {"microarchitecture": "Zen 4"}, "results": [{"name": "sum", "ns": 1000}]}"#;
+    /// let new = r#"{"environment": {"microarchitecture": "Golden Cove"}, "results": [{"name": "sum", "ns": 900}]}"#;
+    /// let (old, new) = (Baseline::from_json(old).unwrap(), Baseline::from_json(new).unwrap());
+    /// assert_eq!(old.diff(&new, 5.0).microarchitecture_mismatch(), Some(("Zen 4", "Golden Cove")));
+    /// assert_eq!(old.diff(&old, 5.0).microarchitecture_mismatch(), None);
+    /// ```
+    pub fn microarchitecture_mismatch(&self) -> Option<(&'a str, &'a str)> {
+        let old = self.old.microarchitecture()?;
+        let new = self.new.microarchitecture()?;
+        (old != new).then_some((old, new))
+    }
+
+    /// Returns the calibration scores of the old and new machines, if the
+    /// new times are normalized.
+    fn normalization(&self) -> Option<(f64, f64)> {
+        if !self.normalized {
+            return None;
+        }
+        self.old
+            .calibration_score()
+            .zip(self.new.calibration_score())
+    }
+
+    /// Returns the benchmarks of either set, sorted by name.
+    fn rows(&self) -> Vec<Row<'a>> {
+        let scale = self
+            .normalization()
+            .map_or(1.0, |(old_score, new_score)| new_score / old_score);
+        let mut names: Vec<&str> = self.old.names().chain(self.new.names()).collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+            .into_iter()
+            .map(|name| {
+                let old = self.old.secs_per_iteration(name);
+                let new = self.new.secs_per_iteration(name).map(|new| new * scale);
+                let change = old
+                    .zip(new)
+                    .filter(|&(old, _)| old > 0.0)
+                    .map(|(old, new)| {
+                        let change = (new / old - 1.0) * 100.0;
+                        let margins = self.old.margin_of_error(name).unwrap_or(0.0)
+                            + self.new.margin_of_error(name).unwrap_or(0.0);
+                        (change, change.abs() > self.threshold.max(margins))
+                    });
+                Row {
+                    name,
+                    old,
+                    new,
+                    change,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the names of the benchmarks that are significantly slower.
+    pub fn regressions(&self) -> impl Iterator<Item = &'a str> {
+        self.rows()
+            .into_iter()
+            .filter(|row| matches!(row.change, Some((change, true)) if change > 0.0))
+            .map(|row| row.name)
+    }
+
+    /// Returns the names of the benchmarks that are significantly faster.
+    pub fn improvements(&self) -> impl Iterator<Item = &'a str> {
+        self.rows()
+            .into_iter()
+            .filter(|row| matches!(row.change, Some((change, true)) if change < 0.0))
+            .map(|row| row.name)
+    }
+
+    /// Print the comparison to the standard output, with regressions in
+    /// red and improvements in green if it is a terminal and `NO_COLOR` is
+    /// not set.
+    pub fn print(&self) {
+        print!(
+            "{}",
+            BaselineDiff {
+                colors: color::stdout_supports_color(),
+                ..*self
+            }
+        );
+    }
+}
+
+impl Display for BaselineDiff<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let rows = self.rows();
+        let time = |secs: Option<f64>| secs.map_or_else(|| "-".to_string(), format_secs);
+        let width = rows
+            .iter()
+            .map(|row| row.name.len())
+            .chain(Some("Benchmark".len()))
+            .max()
+            .unwrap_or(0);
+        writeln!(
+            f,
+            "{:width$}  {:>10}  {:>10}  {:>9}",
+            "Benchmark",
+            "Old",
+            "New",
+            "Change",
+            width = width
+        )?;
+        let (mut regressions, mut improvements) = (0, 0);
+        for row in &rows {
+            let (change, flag, color) = match row.change {
+                Some((change, true)) if change > 0.0 => {
+                    regressions += 1;
+                    (format!("{:+.2}%", change), "  regression", Some(Color::Red))
+                }
+                Some((change, true)) => {
+                    improvements += 1;
+                    (
+                        format!("{:+.2}%", change),
+                        "  improvement",
+                        Some(Color::Green),
+                    )
+                }
+                Some((change, false)) => (format!("{:+.2}%", change), "", None),
+                None if row.old.is_none() => ("new".to_string(), "", None),
+                None if row.new.is_none() => ("removed".to_string(), "", None),
+                None => ("n/a".to_string(), "", None),
+            };
+            writeln!(
+                f,
+                "{:width$}  {:>10}  {:>10}  {}",
+                row.name,
+                time(row.old),
+                time(row.new),
+                paint(
+                    format!("{:>9}{}", change, flag),
+                    color.filter(|_| self.colors)
+                ),
+                width = width
+            )?;
+        }
+        if let Some((old, new)) = self.microarchitecture_mismatch() {
+            writeln!(
+                f,
+                "{}",
+                paint(
+                    format!(
+                        "Warning: the results come from different microarchitectures ({} vs {}), changes may come from the hardware",
+                        old, new
+                    ),
+                    Some(Color::Yellow).filter(|_| self.colors)
+                )
+            )?;
+        }
+        if let Some((old_score, new_score)) = self.normalization() {
+            writeln!(
+                f,
+                "New times normalized by the calibration scores ({:.0} vs {:.0})",
+                old_score, new_score
+            )?;
+        }
+        writeln!(
+            f,
+            "{} regressions, {} improvements (threshold: {}%)",
+            regressions, improvements, self.threshold
+        )
+    }
+}
@@ -0,0 +1,272 @@
+//! Periodic digests of the notable changes in a performance history.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::report::html_escape;
+use crate::{format_secs, json};
+
+/// A notable change of a benchmark over the period of a [`Digest`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DigestChange {
+    /// Name of the benchmark.
+    pub name: String,
+    /// Time of an iteration at the start of the period, in seconds.
+    pub before: f64,
+    /// Time of an iteration at the end of the period, in seconds.
+    pub after: f64,
+    /// Relative change (in %), positive if the benchmark got slower.
+    pub change: f64,
+    /// Git commit of the latest result, if known.
+    pub git_commit: Option<String>,
+}
+
+/// The top regressions and improvements over a period, such as the last
+/// week, computed from a history file written by
+/// [`Report::append_history()`](crate::Report::append_history).
+///
+/// The digest is rendered as Markdown or HTML, ready to be mailed or
+/// published.
+///
+/// ```rust
+/// use benchmark_simple::Digest;
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let history = r#"{"timestamp": 1000, "git_commit": "a1", "name": "sum", "iterations": 1, "ns": 1000, "rsd": 1.0, "samples": 5, "interrupted": false}
+/// {"timestamp": 90000, "git_commit": "b2", "name": "sum", "iterations": 1, "ns": 1300, "rsd": 1.0, "samples": 5, "interrupted": false}
+/// "#;
+/// let now = UNIX_EPOCH + Duration::from_secs(100_000);
+/// let digest = Digest::parse(history, now, Duration::from_secs(86_400)).unwrap();
+/// assert_eq!(digest.regressions()[0].name, "sum");
+/// println!("{}", digest.to_markdown());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Digest {
+    since: u64,
+    until: u64,
+    threshold: f64,
+    top: usize,
+    regressions: Vec<DigestChange>,
+    improvements: Vec<DigestChange>,
+}
+
+/// Changes smaller than this (in %) are not notable, by default.
+const DEFAULT_THRESHOLD: f64 = 5.0;
+
+/// Number of regressions and improvements listed, by default.
+const DEFAULT_TOP: usize = 10;
+
+/// A result of a history file.
+struct Entry {
+    timestamp: u64,
+    git_commit: Option<String>,
+    secs_per_iteration: f64,
+}
+
+impl Digest {
+    /// Build a digest of the `period` ending now, from the history file at
+    /// `path`.
+    pub fn from_history(path: impl AsRef<Path>, period: Duration) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?, SystemTime::now(), period)
+    }
+
+    /// Build a digest of the `period` ending at `until`, from the content of
+    /// a history file.
+    ///
+    /// Every benchmark is compared between its latest result before the
+    /// period, or its first one during the period if it is new, and its
+    /// latest result. Interrupted results are ignored.
+    pub fn parse(history: &str, until: SystemTime, period: Duration) -> io::Result<Self> {
+        let until = until.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let since = until.saturating_sub(period.as_secs());
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid history line: {}", line),
+            )
+        };
+        let mut entries: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
+        for line in history.lines().filter(|line| !line.trim().is_empty()) {
+            let value = json::parse(line).map_err(|_| invalid(line))?;
+            let number = |key: &str| value.get(key).and_then(|value| value.as_f64());
+            if value.get("interrupted").and_then(|v| v.as_bool()) == Some(true) {
+                continue;
+            }
+            let (name, timestamp, ns) = match (
+                value.get("name").and_then(|name| name.as_str()),
+                number("timestamp"),
+                number("ns"),
+            ) {
+                (Some(name), Some(timestamp), Some(ns)) => (name, timestamp as u64, ns),
+                _ => return Err(invalid(line)),
+            };
+            if timestamp > until {
+                continue;
+            }
+            let iterations = number("iterations").unwrap_or(1.0).max(1.0);
+            entries.entry(name.to_string()).or_default().push(Entry {
+                timestamp,
+                git_commit: value
+                    .get("git_commit")
+                    .and_then(|commit| commit.as_str())
+                    .map(str::to_string),
+                secs_per_iteration: ns / 1e9 / iterations,
+            });
+        }
+        let mut changes = Vec::new();
+        for (name, mut entries) in entries {
+            entries.sort_by_key(|entry| entry.timestamp);
+            let latest = match entries.last() {
+                Some(latest) if latest.timestamp >= since => latest,
+                // Not run during the period.
+                _ => continue,
+            };
+            let before = entries
+                .iter()
+                .rev()
+                .find(|entry| entry.timestamp < since)
+                .unwrap_or(&entries[0]);
+            if std::ptr::eq(before, latest) || before.secs_per_iteration <= 0.0 {
+                continue;
+            }
+            changes.push(DigestChange {
+                change: (latest.secs_per_iteration / before.secs_per_iteration - 1.0) * 100.0,
+                before: before.secs_per_iteration,
+                after: latest.secs_per_iteration,
+                git_commit: latest.git_commit.clone(),
+                name,
+            });
+        }
+        // Largest changes first.
+        changes.sort_by(|a, b| b.change.abs().total_cmp(&a.change.abs()));
+        let (regressions, improvements) = changes.into_iter().partition(|c| c.change > 0.0);
+        Ok(Digest {
+            since,
+            until,
+            threshold: DEFAULT_THRESHOLD,
+            top: DEFAULT_TOP,
+            regressions,
+            improvements,
+        })
+    }
+
+    /// Only consider changes larger than `threshold` (in %) as notable.
+    /// The default is 5%.
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// List at most `top` regressions and `top` improvements. The default
+    /// is 10.
+    pub fn top(mut self, top: usize) -> Self {
+        self.top = top;
+        self
+    }
+
+    /// Returns the notable regressions, largest first.
+    pub fn regressions(&self) -> &[DigestChange] {
+        self.notable(&self.regressions)
+    }
+
+    /// Returns the notable improvements, largest first.
+    pub fn improvements(&self) -> &[DigestChange] {
+        self.notable(&self.improvements)
+    }
+
+    fn notable<'a>(&self, changes: &'a [DigestChange]) -> &'a [DigestChange] {
+        let notable = changes
+            .iter()
+            .take_while(|c| c.change.abs() > self.threshold)
+            .count();
+        &changes[..notable.min(self.top)]
+    }
+
+    fn title(&self) -> String {
+        format!(
+            "Performance digest: {} to {}",
+            date(self.since),
+            date(self.until)
+        )
+    }
+
+    /// Returns the digest as a Markdown document.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("## {}\n", self.title());
+        for (title, changes) in [
+            ("Regressions", self.regressions()),
+            ("Improvements", self.improvements()),
+        ] {
+            out.push_str(&format!("\n### {}\n\n", title));
+            if changes.is_empty() {
+                out.push_str("None.\n");
+                continue;
+            }
+            out.push_str(
+                "| Benchmark | Before | After | Change | Commit |\n|---|---:|---:|---:|---|\n",
+            );
+            for c in changes {
+                out.push_str(&format!(
+                    "| `{}` | {} | {} | {:+.2}% | {} |\n",
+                    c.name.replace('|', "\\|").replace('`', "'"),
+                    format_secs(c.before),
+                    format_secs(c.after),
+                    c.change,
+                    c.git_commit.as_deref().unwrap_or("-"),
+                ));
+            }
+        }
+        out
+    }
+
+    /// Returns the digest as an HTML page.
+    pub fn to_html(&self) -> String {
+        let title = self.title();
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{}</title></head>\n<body>\n<h2>{}</h2>\n",
+            title, title
+        );
+        for (title, changes) in [
+            ("Regressions", self.regressions()),
+            ("Improvements", self.improvements()),
+        ] {
+            out.push_str(&format!("<h3>{}</h3>\n", title));
+            if changes.is_empty() {
+                out.push_str("<p>None.</p>\n");
+                continue;
+            }
+            out.push_str("<table>\n<tr><th>Benchmark</th><th>Before</th><th>After</th><th>Change</th><th>Commit</th></tr>\n");
+            for c in changes {
+                out.push_str(&format!(
+                    "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{:+.2}%</td><td>{}</td></tr>\n",
+                    html_escape(&c.name),
+                    format_secs(c.before),
+                    format_secs(c.after),
+                    c.change,
+                    html_escape(c.git_commit.as_deref().unwrap_or("-")),
+                ));
+            }
+            out.push_str("</table>\n");
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+}
+
+/// Returns the UTC date of a Unix timestamp, as `YYYY-MM-DD`.
+fn date(timestamp: u64) -> String {
+    // Days to civil date, from Howard Hinnant's algorithm.
+    let z = (timestamp / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
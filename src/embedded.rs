@@ -0,0 +1,120 @@
+//! Cycle counters of Cortex-M cores, to run benchmarks on the target.
+//!
+//! Only available with the `embedded` feature, on ARM targets. The counters
+//! are read and configured through their memory-mapped registers, which
+//! requires privileged mode.
+//!
+//! ```rust,ignore
+//! use benchmark_simple::embedded::CycleCounter;
+//! use benchmark_simple::{TickBench, TickOptions};
+//!
+//! const CORE_CLOCK_HZ: u64 = 168_000_000;
+//!
+//! let mut bench = TickBench::new(CycleCounter::enable().ticks(), CORE_CLOCK_HZ);
+//! let res = bench.run(&TickOptions::default(), || kernel(&input));
+//! // Ticks are CPU cycles.
+//! let cycles = res.ticks_per_iteration();
+//! ```
+
+use core::ptr;
+
+const DEMCR: *mut u32 = 0xe000_edfc as *mut u32;
+const DEMCR_TRCENA: u32 = 1 << 24;
+const DWT_CTRL: *mut u32 = 0xe000_1000 as *mut u32;
+const DWT_CTRL_CYCCNTENA: u32 = 1;
+const DWT_CTRL_NOCYCCNT: u32 = 1 << 25;
+const DWT_CYCCNT: *mut u32 = 0xe000_1004 as *mut u32;
+const DWT_LAR: *mut u32 = 0xe000_1fb0 as *mut u32;
+const DWT_LAR_KEY: u32 = 0xc5ac_ce55;
+const SYST_CSR: *mut u32 = 0xe000_e010 as *mut u32;
+const SYST_CSR_ENABLE: u32 = 1;
+const SYST_CSR_CLKSOURCE: u32 = 1 << 2;
+const SYST_RVR: *mut u32 = 0xe000_e014 as *mut u32;
+const SYST_CVR: *mut u32 = 0xe000_e018 as *mut u32;
+const SYST_MAX: u32 = 0x00ff_ffff;
+
+/// A counter of CPU cycles.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CycleCounter {
+    /// The 32-bit `CYCCNT` counter of the Data Watchpoint and Trace unit,
+    /// available on Cortex-M3 and later cores.
+    Dwt,
+    /// The 24-bit SysTick timer, clocked by the processor.
+    SysTick,
+}
+
+impl CycleCounter {
+    /// Enable the DWT cycle counter, or SysTick if the core doesn't have one.
+    pub fn enable() -> Self {
+        Self::dwt().unwrap_or_else(Self::systick)
+    }
+
+    /// Enable the DWT cycle counter, or return `None` if the core doesn't
+    /// have one, such as a Cortex-M0.
+    pub fn dwt() -> Option<Self> {
+        // SAFETY: these are the architecturally defined debug registers.
+        unsafe {
+            ptr::write_volatile(DEMCR, ptr::read_volatile(DEMCR) | DEMCR_TRCENA);
+            // Cortex-M7 cores ignore writes until the unit is unlocked.
+            ptr::write_volatile(DWT_LAR, DWT_LAR_KEY);
+            let ctrl = ptr::read_volatile(DWT_CTRL);
+            if ctrl & DWT_CTRL_NOCYCCNT != 0 {
+                return None;
+            }
+            ptr::write_volatile(DWT_CYCCNT, 0);
+            ptr::write_volatile(DWT_CTRL, ctrl | DWT_CTRL_CYCCNTENA);
+        }
+        Some(CycleCounter::Dwt)
+    }
+
+    /// Configure SysTick to count processor cycles, with the largest reload
+    /// value and without interrupts.
+    ///
+    /// This takes SysTick over: don't use it if an RTOS relies on it.
+    pub fn systick() -> Self {
+        // SAFETY: these are the architecturally defined SysTick registers.
+        unsafe {
+            ptr::write_volatile(SYST_CSR, 0);
+            ptr::write_volatile(SYST_RVR, SYST_MAX);
+            ptr::write_volatile(SYST_CVR, 0);
+            ptr::write_volatile(SYST_CSR, SYST_CSR_CLKSOURCE | SYST_CSR_ENABLE);
+        }
+        CycleCounter::SysTick
+    }
+
+    /// Returns the raw value of the counter. SysTick counts down.
+    pub fn read(&self) -> u32 {
+        // SAFETY: the counter was enabled by the constructor.
+        unsafe {
+            match self {
+                CycleCounter::Dwt => ptr::read_volatile(DWT_CYCCNT),
+                CycleCounter::SysTick => ptr::read_volatile(SYST_CVR),
+            }
+        }
+    }
+
+    /// Returns a tick source for a [`TickBench`](crate::TickBench), counting
+    /// up the cycles elapsed since its first call.
+    ///
+    /// The counter wraps around, so it has to be read at least once per
+    /// period: 2^32 cycles with the DWT, and 2^24 cycles with SysTick (about
+    /// 100 ms at 168 MHz). Samples have to be shorter than that.
+    pub fn ticks(self) -> impl FnMut() -> u64 {
+        let mask = match self {
+            CycleCounter::Dwt => u32::MAX,
+            CycleCounter::SysTick => SYST_MAX,
+        };
+        let mut last = self.read();
+        let mut total = 0u64;
+        move || {
+            let now = self.read();
+            let delta = match self {
+                CycleCounter::Dwt => now.wrapping_sub(last),
+                CycleCounter::SysTick => last.wrapping_sub(now),
+            } & mask;
+            last = now;
+            total += delta as u64;
+            total
+        }
+    }
+}
@@ -0,0 +1,520 @@
+//! The sampling loop, as a building block for specialized harnesses.
+
+use std::fmt::{self, Display, Formatter};
+use std::ops::ControlFlow;
+use std::panic::Location;
+use std::time::{Duration, SystemTime};
+
+use precision::Timestamp;
+
+use crate::data::Rng;
+use crate::timer::Timer;
+use crate::{black_box_ref, is_interrupted, shared_timer, stats, Options, Reporter};
+
+/// With `extend_samples` and no `max_duration`, the number of samples is
+/// bounded by `max_samples` times this.
+const EXTENDED_SAMPLES_FACTOR: usize = 10;
+
+/// Measures a single sample.
+///
+/// The default measurement, [`WallClock`], measures the elapsed time. Other
+/// implementations can measure something else, such as the time spent on a
+/// GPU or by a database server, as long as it can be expressed as a duration.
+pub trait Measurement {
+    /// Run `sample`, which runs all the iterations of a sample, and
+    /// return its cost.
+    fn measure(&mut self, sample: &mut dyn FnMut()) -> Duration;
+}
+
+impl<M: Measurement + ?Sized> Measurement for &mut M {
+    fn measure(&mut self, sample: &mut dyn FnMut()) -> Duration {
+        (**self).measure(sample)
+    }
+}
+
+/// Measures the elapsed time, using the calibrated timer of the process.
+#[derive(Clone)]
+pub struct WallClock {
+    precision: Timer,
+}
+
+impl Default for WallClock {
+    fn default() -> Self {
+        WallClock {
+            precision: shared_timer(),
+        }
+    }
+}
+
+impl Measurement for WallClock {
+    fn measure(&mut self, sample: &mut dyn FnMut()) -> Duration {
+        let start = self.precision.now();
+        sample();
+        let elapsed = self.precision.now() - start;
+        Duration::from_nanos(self.precision.as_ns(elapsed))
+    }
+}
+
+/// The state of a sampling run, passed to stopping rules after every sample.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct SamplingState<'a> {
+    /// The samples collected so far.
+    pub samples: &'a [Duration],
+    /// The RSD of the samples collected so far (in 0...100), from the second sample on.
+    pub rsd: Option<f64>,
+    /// The time elapsed since the first sample started.
+    pub elapsed: Duration,
+}
+
+/// Decides when to stop collecting samples.
+///
+/// Rules are checked after every sample, in addition to the limits set by
+/// the `Options`. Closures taking a [`SamplingState`] and returning `true`
+/// to stop are rules.
+pub trait StoppingRule {
+    /// Returns `true` if no more samples should be collected.
+    fn should_stop(&mut self, state: &SamplingState<'_>) -> bool;
+}
+
+impl<F: FnMut(&SamplingState<'_>) -> bool> StoppingRule for F {
+    fn should_stop(&mut self, state: &SamplingState<'_>) -> bool {
+        self(state)
+    }
+}
+
+/// Why a sampling run stopped.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum StopReason {
+    /// `max_samples` samples were collected.
+    MaxSamples,
+    /// The RSD went below `max_rsd` (and the confidence interval below
+    /// `max_ci_width`, if set) after at least `min_samples` samples.
+    Converged,
+    /// The `max_duration` budget was used, or the next sample would exceed it.
+    MaxDuration,
+    /// A sample took longer than `sample_timeout`.
+    SampleTimeout,
+    /// The process received an interruption request.
+    Interrupted,
+    /// The reporter asked to stop.
+    Reporter,
+    /// A custom stopping rule asked to stop, or, in a race, the benchmark
+    /// was found to be faster or slower than all the others.
+    Rule,
+}
+
+impl Display for StopReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            StopReason::MaxSamples => "maximum number of samples reached",
+            StopReason::Converged => "converged",
+            StopReason::MaxDuration => "maximum duration reached",
+            StopReason::SampleTimeout => "sample timeout",
+            StopReason::Interrupted => "interrupted",
+            StopReason::Reporter => "stopped by the reporter",
+            StopReason::Rule => "stopped by a rule",
+        })
+    }
+}
+
+impl StopReason {
+    /// Returns the identifier of the reason, in serialized results and
+    /// reports.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            StopReason::MaxSamples => "max_samples",
+            StopReason::Converged => "converged",
+            StopReason::MaxDuration => "max_duration",
+            StopReason::SampleTimeout => "sample_timeout",
+            StopReason::Interrupted => "interrupted",
+            StopReason::Reporter => "reporter",
+            StopReason::Rule => "rule",
+        }
+    }
+
+    /// Returns the reason with the identifier `name`.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "max_samples" => StopReason::MaxSamples,
+            "converged" => StopReason::Converged,
+            "max_duration" => StopReason::MaxDuration,
+            "sample_timeout" => StopReason::SampleTimeout,
+            "interrupted" => StopReason::Interrupted,
+            "reporter" => StopReason::Reporter,
+            "rule" => StopReason::Rule,
+            _ => return None,
+        })
+    }
+}
+
+/// The samples collected by a [`SamplingEngine`].
+#[derive(Clone, Debug)]
+pub struct Sampling {
+    samples: Vec<Duration>,
+    iterations: Vec<u64>,
+    timestamps: Vec<SystemTime>,
+    rsd: Option<f64>,
+    stop_reason: StopReason,
+    discarded: usize,
+}
+
+impl Sampling {
+    /// Returns the samples, in collection order.
+    pub fn samples(&self) -> &[Duration] {
+        &self.samples
+    }
+
+    /// Returns the number of iterations of every sample.
+    ///
+    /// These differ from `iterations` with the `iteration_jitter` option; the
+    /// samples are then normalized to `iterations` iterations.
+    pub fn iterations(&self) -> &[u64] {
+        &self.iterations
+    }
+
+    /// Returns the wall-clock time at which every sample started.
+    pub fn timestamps(&self) -> &[SystemTime] {
+        &self.timestamps
+    }
+
+    /// Returns the RSD of the samples (in 0...100), or `None` with a single sample.
+    pub fn rsd(&self) -> Option<f64> {
+        self.rsd
+    }
+
+    /// Returns the number of samples that were measured, and then ignored
+    /// because of the `discard_samples` option.
+    pub fn discarded(&self) -> usize {
+        self.discarded
+    }
+
+    /// Returns why no more samples were collected.
+    pub fn stop_reason(&self) -> StopReason {
+        self.stop_reason
+    }
+}
+
+/// Progress of the measured samples, passed to the sampling hook of a
+/// [`SamplingEngine`].
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(not(feature = "usdt"), allow(dead_code))]
+pub(crate) enum SamplingEvent {
+    /// Before the first sample.
+    Start,
+    /// Before the sample with the given number (from 1).
+    SampleStart(usize),
+    /// After the sample with the given number, with its duration.
+    SampleEnd(usize, Duration),
+    /// After the last sample.
+    Stop,
+}
+
+/// The sampling loop used by `Bench::run()`, with extension points for
+/// crates building specialized harnesses.
+///
+/// The engine runs the warm-up, then collects samples of `iterations`
+/// iterations each until a limit set by the `Options` is reached or a
+/// [`StoppingRule`] asks to stop. Each sample is measured by a
+/// [`Measurement`], and a [`Reporter`] is notified as samples are collected.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use benchmark_simple::{Options, SamplingEngine, SamplingState};
+///
+/// let options = Options::quick();
+/// let sampling = SamplingEngine::new(&options)
+///     .with_stopping_rule(|state: &SamplingState| state.elapsed > Duration::from_millis(10))
+///     .run(|| (0..1000u64).sum::<u64>());
+/// println!("{} samples ({})", sampling.samples().len(), sampling.stop_reason());
+/// ```
+pub struct SamplingEngine<'a> {
+    options: Options,
+    measurement: Box<dyn Measurement + 'a>,
+    rules: Vec<Box<dyn StoppingRule + 'a>>,
+    reporter: Option<&'a mut dyn Reporter>,
+    sampling_hook: Option<Box<dyn FnMut(SamplingEvent) + 'a>>,
+    precision: Timer,
+}
+
+impl<'a> SamplingEngine<'a> {
+    /// Create an engine collecting samples according to `options`, measured
+    /// with a [`WallClock`].
+    pub fn new(options: &Options) -> Self {
+        SamplingEngine {
+            options: options.clone(),
+            measurement: Box::new(WallClock::default()),
+            rules: Vec::new(),
+            reporter: None,
+            sampling_hook: None,
+            precision: shared_timer(),
+        }
+    }
+
+    /// Measure samples with `measurement`.
+    pub fn with_measurement(mut self, measurement: impl Measurement + 'a) -> Self {
+        self.measurement = Box::new(measurement);
+        self
+    }
+
+    /// Add a stopping rule.
+    pub fn with_stopping_rule(mut self, rule: impl StoppingRule + 'a) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Notify `reporter` when sampling starts and after every sample.
+    ///
+    /// `on_finish()` is not called, as the engine doesn't produce a result:
+    /// this is left to the harness.
+    pub fn with_reporter(mut self, reporter: &'a mut dyn Reporter) -> Self {
+        self.reporter = Some(reporter);
+        self
+    }
+
+    /// Call `hook` as the measured samples are collected, after the warm-up
+    /// and the discarded samples.
+    pub(crate) fn with_sampling_hook(mut self, hook: impl FnMut(SamplingEvent) + 'a) -> Self {
+        self.sampling_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Collect samples of `f`.
+    ///
+    /// The caller's source location is passed to the reporter.
+    #[track_caller]
+    pub fn run<F, G>(&mut self, f: F) -> Sampling
+    where
+        F: FnMut() -> G,
+    {
+        self.run_at(Location::caller(), f)
+    }
+
+    pub(crate) fn run_at<F, G>(
+        &mut self,
+        location: &'static Location<'static>,
+        mut f: F,
+    ) -> Sampling
+    where
+        F: FnMut() -> G,
+    {
+        let mut run = self.start_at(location, &mut f);
+        while run.sample(&mut f).is_none() {}
+        run.finish()
+    }
+
+    /// Run the warm-up of `f`, and return a run collecting its samples one
+    /// at a time, so that the samples of several functions can be
+    /// interleaved.
+    pub(crate) fn start_at<F, G>(
+        &mut self,
+        location: &'static Location<'static>,
+        f: &mut F,
+    ) -> SamplingRun<'_, 'a>
+    where
+        F: FnMut() -> G,
+    {
+        let options = &self.options;
+        let iterations = options.iterations;
+        let max_samples = std::cmp::max(1, options.max_samples);
+        if let Some(reporter) = &mut self.reporter {
+            reporter.on_start(location, options);
+        }
+        warm_up(options, &self.precision, &mut *f);
+        // At most `iterations - 1`, even if the options weren't validated.
+        let jitter = ((iterations as f64 * options.iteration_jitter / 100.0) as u64)
+            .min(iterations.saturating_sub(1));
+        let rng = Rng::new(
+            SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_nanos() as u64),
+        );
+        let mut discarded = 0;
+        while discarded < options.discard_samples && !is_interrupted() {
+            self.measurement.measure(&mut || {
+                for _ in 0..iterations {
+                    black_box_ref(&f());
+                }
+            });
+            discarded += 1;
+        }
+        if let Some(hook) = &mut self.sampling_hook {
+            hook(SamplingEvent::Start);
+        }
+        SamplingRun {
+            engine: self,
+            samples: Vec::with_capacity(max_samples),
+            iterations: Vec::with_capacity(max_samples),
+            timestamps: Vec::with_capacity(max_samples),
+            moments: stats::Welford::default(),
+            rsd: None,
+            jitter,
+            rng,
+            discarded,
+            start: None,
+            stop_reason: None,
+        }
+    }
+}
+
+/// The samples of a [`SamplingEngine`] being collected, after the warm-up.
+pub(crate) struct SamplingRun<'e, 'a> {
+    engine: &'e mut SamplingEngine<'a>,
+    samples: Vec<Duration>,
+    iterations: Vec<u64>,
+    timestamps: Vec<SystemTime>,
+    moments: stats::Welford,
+    rsd: Option<f64>,
+    jitter: u64,
+    rng: Rng,
+    discarded: usize,
+    start: Option<Timestamp>,
+    stop_reason: Option<StopReason>,
+}
+
+impl SamplingRun<'_, '_> {
+    /// Returns the samples collected so far.
+    pub(crate) fn samples(&self) -> &[Duration] {
+        &self.samples
+    }
+
+    /// Returns why no more samples are collected, if the run is over.
+    pub(crate) fn stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason
+    }
+
+    /// Stop collecting samples, for a reason decided by the caller, unless
+    /// the run is already over.
+    pub(crate) fn stop(&mut self, stop_reason: StopReason) {
+        self.stop_reason.get_or_insert(stop_reason);
+    }
+
+    /// Collect a sample of `f`, unless the run is over, and return why the
+    /// run is over, if it is.
+    pub(crate) fn sample<F, G>(&mut self, f: &mut F) -> Option<StopReason>
+    where
+        F: FnMut() -> G,
+    {
+        if self.stop_reason.is_none() {
+            self.stop_reason = self.next_sample(f);
+        }
+        self.stop_reason
+    }
+
+    fn next_sample<F, G>(&mut self, f: &mut F) -> Option<StopReason>
+    where
+        F: FnMut() -> G,
+    {
+        let engine = &mut *self.engine;
+        let options = &engine.options;
+        let iterations = options.iterations;
+        let max_samples = std::cmp::max(1, options.max_samples);
+        let extended_max_samples = match options.max_duration {
+            Some(_) => usize::MAX,
+            None => max_samples.saturating_mul(EXTENDED_SAMPLES_FACTOR),
+        };
+        let i = self.samples.len() + 1;
+        if i > 1 && is_interrupted() {
+            return Some(StopReason::Interrupted);
+        }
+        if i > 1 {
+            match options.cooldown {
+                Some(cooldown) if cooldown.is_zero() => std::thread::yield_now(),
+                Some(cooldown) => std::thread::sleep(cooldown),
+                None => {}
+            }
+        }
+        let jitter = self.jitter;
+        let n = if jitter > 0 {
+            std::cmp::max(1, iterations - jitter + self.rng.below(2 * jitter + 1))
+        } else {
+            iterations
+        };
+        let start = *self.start.get_or_insert_with(|| engine.precision.now());
+        if let Some(hook) = &mut engine.sampling_hook {
+            hook(SamplingEvent::SampleStart(i));
+        }
+        self.timestamps.push(SystemTime::now());
+        let raw = engine.measurement.measure(&mut || {
+            for _ in 0..n {
+                black_box_ref(&f());
+            }
+        });
+        if let Some(hook) = &mut engine.sampling_hook {
+            hook(SamplingEvent::SampleEnd(i, raw));
+        }
+        let duration = if n == iterations {
+            raw
+        } else {
+            raw.mul_f64(iterations as f64 / n as f64)
+        };
+        self.iterations.push(n);
+        self.samples.push(duration);
+        self.moments.push(duration.as_secs_f64());
+        if options.sample_timeout.is_some_and(|timeout| raw > timeout) {
+            return Some(StopReason::SampleTimeout);
+        }
+        let rsd = self.moments.rsd();
+        self.rsd = rsd;
+        if let Some(reporter) = &mut engine.reporter {
+            if reporter.on_sample(i, duration, rsd) == ControlFlow::Break(()) {
+                return Some(StopReason::Reporter);
+            }
+        }
+        if rsd.is_some_and(|rsd| i >= options.min_samples && options.is_converged(rsd, i)) {
+            return Some(StopReason::Converged);
+        }
+        let elapsed = Duration::from_nanos(engine.precision.as_ns(engine.precision.now() - start));
+        let state = SamplingState {
+            samples: &self.samples,
+            rsd,
+            elapsed,
+        };
+        if engine.rules.iter_mut().any(|rule| rule.should_stop(&state)) {
+            return Some(StopReason::Rule);
+        }
+        if i >= max_samples && !(options.extend_samples && i < extended_max_samples) {
+            return Some(StopReason::MaxSamples);
+        }
+        if let Some(max_duration) = options.max_duration {
+            // Don't start a sample that would obviously exceed the budget.
+            if elapsed >= max_duration || elapsed + raw > max_duration {
+                return Some(StopReason::MaxDuration);
+            }
+        }
+        None
+    }
+
+    /// End the run, and return its samples. A run that isn't over is
+    /// considered interrupted.
+    pub(crate) fn finish(self) -> Sampling {
+        if let Some(hook) = &mut self.engine.sampling_hook {
+            hook(SamplingEvent::Stop);
+        }
+        Sampling {
+            samples: self.samples,
+            iterations: self.iterations,
+            timestamps: self.timestamps,
+            rsd: self.rsd,
+            stop_reason: self.stop_reason.unwrap_or(StopReason::Interrupted),
+            discarded: self.discarded,
+        }
+    }
+}
+
+/// Run `f` `warmup_iterations` times, and then until `warmup_time` has
+/// elapsed.
+pub(crate) fn warm_up<G>(options: &Options, precision: &Timer, mut f: impl FnMut() -> G) {
+    for _ in 0..options.warmup_iterations {
+        black_box_ref(&f());
+    }
+    if let Some(warmup_time) = options.warmup_time {
+        let start = precision.now();
+        while precision.as_ns(precision.now() - start) < warmup_time.as_nanos() as u64
+            && !is_interrupted()
+        {
+            black_box_ref(&f());
+        }
+    }
+}
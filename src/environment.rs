@@ -0,0 +1,612 @@
+//! Information about the machine running the benchmarks.
+
+use std::fmt::{self, Display, Formatter};
+
+/// A description of the environment benchmarks are running in.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Environment {
+    /// CPU model name, as reported by the CPU or the operating system.
+    pub cpu_model: Option<String>,
+    /// CPU microarchitecture family (e.g. "Zen 4", "Golden Cove", "Apple M2").
+    pub microarchitecture: Option<String>,
+    /// Number of CPUs the process is allowed to run on.
+    pub allowed_cpus: Option<usize>,
+    /// CPU bandwidth limit, in CPUs, if the process runs under a cgroup quota.
+    pub cpu_quota: Option<f64>,
+    /// Hypervisor the machine runs under, if any.
+    pub hypervisor: Option<String>,
+    /// Number of logical CPUs of the machine.
+    pub logical_cpus: Option<usize>,
+    /// Operating system and, if known, its version (e.g. "linux 6.1.0").
+    pub os: Option<String>,
+    /// Target triple the benchmarks were compiled for.
+    pub target: Option<String>,
+    /// Version of the compiler the benchmarks were compiled with.
+    pub rustc_version: Option<String>,
+    /// Cargo profile the benchmarks were compiled with (e.g. "release").
+    pub profile: Option<String>,
+    /// Commit of the git repository in the current directory, if any.
+    pub git_commit: Option<String>,
+    /// CPU frequency scaling governor (Linux only).
+    pub cpu_governor: Option<String>,
+    /// Whether turbo boost is enabled (Linux only).
+    pub turbo: Option<bool>,
+    /// Calibration score of the machine, if measured (see
+    /// [`Bench::calibration_score()`](crate::Bench::calibration_score)).
+    pub calibration_score: Option<f64>,
+}
+
+impl Environment {
+    /// Detect the current environment.
+    pub fn detect() -> Self {
+        let cpu_model = cpu_model();
+        let microarchitecture = microarchitecture(cpu_model.as_deref());
+        Environment {
+            cpu_model,
+            microarchitecture,
+            allowed_cpus: allowed_cpus(),
+            cpu_quota: cgroup::cpu_quota(),
+            hypervisor: hypervisor(),
+            logical_cpus: logical_cpus(),
+            os: Some(os()),
+            target: non_empty(env!("BENCHMARK_SIMPLE_TARGET")),
+            rustc_version: non_empty(env!("BENCHMARK_SIMPLE_RUSTC_VERSION")),
+            profile: non_empty(env!("BENCHMARK_SIMPLE_PROFILE")),
+            git_commit: git_commit(),
+            cpu_governor: cpu_governor(),
+            turbo: turbo_enabled(),
+            calibration_score: None,
+        }
+    }
+
+    /// Returns `true` if the machine is a virtual machine.
+    ///
+    /// Timers are often virtualized, so results shouldn't be compared with
+    /// results from bare-metal machines.
+    pub fn is_virtualized(&self) -> bool {
+        self.hypervisor.is_some()
+    }
+
+    /// A key to group results from machines sharing the same microarchitecture.
+    pub fn microarchitecture_key(&self) -> &str {
+        self.microarchitecture.as_deref().unwrap_or("unknown")
+    }
+}
+
+impl Display for Environment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "CPU: {}", self.cpu_model.as_deref().unwrap_or("unknown"))?;
+        write!(f, "Microarchitecture: {}", self.microarchitecture_key())?;
+        if let Some(allowed_cpus) = self.allowed_cpus {
+            write!(f, "\nAllowed CPUs: {}", allowed_cpus)?;
+        }
+        if let Some(cpu_quota) = self.cpu_quota {
+            write!(f, "\nCPU quota: {:.2} CPUs", cpu_quota)?;
+        }
+        if let Some(hypervisor) = &self.hypervisor {
+            write!(
+                f,
+                "\nHypervisor: {} (timers may be virtualized)",
+                hypervisor
+            )?;
+        }
+        if let Some(logical_cpus) = self.logical_cpus {
+            write!(f, "\nLogical CPUs: {}", logical_cpus)?;
+        }
+        for (name, value) in [
+            ("OS", &self.os),
+            ("Target", &self.target),
+            ("Compiler", &self.rustc_version),
+            ("Profile", &self.profile),
+            ("Git commit", &self.git_commit),
+            ("CPU governor", &self.cpu_governor),
+        ] {
+            if let Some(value) = value {
+                write!(f, "\n{}: {}", name, value)?;
+            }
+        }
+        if let Some(turbo) = self.turbo {
+            write!(
+                f,
+                "\nTurbo boost: {}",
+                if turbo { "enabled" } else { "disabled" }
+            )?;
+        }
+        if let Some(calibration_score) = self.calibration_score {
+            write!(f, "\nCalibration score: {:.0}", calibration_score)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[allow(unused_unsafe)] // `__cpuid` is only safe to call on recent Rust versions
+mod cpuid {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::__cpuid;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::__cpuid;
+
+    pub fn vendor() -> String {
+        let r = unsafe { __cpuid(0) };
+        let mut vendor = Vec::with_capacity(12);
+        for reg in [r.ebx, r.edx, r.ecx] {
+            vendor.extend_from_slice(&reg.to_le_bytes());
+        }
+        String::from_utf8_lossy(&vendor).into_owned()
+    }
+
+    /// Returns the display family and model.
+    pub fn family_model() -> (u32, u32) {
+        let eax = unsafe { __cpuid(1) }.eax;
+        let base_family = (eax >> 8) & 0xf;
+        let base_model = (eax >> 4) & 0xf;
+        let family = if base_family == 0xf {
+            base_family + ((eax >> 20) & 0xff)
+        } else {
+            base_family
+        };
+        let model = if base_family == 0x6 || base_family == 0xf {
+            base_model | (((eax >> 16) & 0xf) << 4)
+        } else {
+            base_model
+        };
+        (family, model)
+    }
+
+    /// Returns the hypervisor vendor, if the hypervisor bit is set.
+    pub fn hypervisor() -> Option<String> {
+        if unsafe { __cpuid(1) }.ecx & (1 << 31) == 0 {
+            return None;
+        }
+        let r = unsafe { __cpuid(0x4000_0000) };
+        let mut vendor = Vec::with_capacity(12);
+        for reg in [r.ebx, r.ecx, r.edx] {
+            vendor.extend_from_slice(&reg.to_le_bytes());
+        }
+        let name = match &vendor[..] {
+            b"KVMKVMKVM\0\0\0" => "KVM",
+            b"Microsoft Hv" => "Hyper-V",
+            b"VMwareVMware" => "VMware",
+            b"XenVMMXenVMM" => "Xen",
+            b"TCGTCGTCGTCG" => "QEMU",
+            b"VBoxVBoxVBox" => "VirtualBox",
+            b" lrpepyh  vr" => "Parallels",
+            b"bhyve bhyve " => "bhyve",
+            b"ACRNACRNACRN" => "ACRN",
+            b"QNXQVMBSQG\0\0" => "QNX",
+            b"Apple VZ\0\0\0\0" => "Apple Virtualization",
+            _ => "unknown hypervisor",
+        };
+        Some(name.to_string())
+    }
+
+    pub fn brand() -> Option<String> {
+        if unsafe { __cpuid(0x8000_0000) }.eax < 0x8000_0004 {
+            return None;
+        }
+        let mut brand = Vec::with_capacity(48);
+        for leaf in 0x8000_0002..=0x8000_0004 {
+            let r = unsafe { __cpuid(leaf) };
+            for reg in [r.eax, r.ebx, r.ecx, r.edx] {
+                brand.extend_from_slice(&reg.to_le_bytes());
+            }
+        }
+        let brand = String::from_utf8_lossy(&brand);
+        let brand = brand.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+        if brand.is_empty() {
+            None
+        } else {
+            Some(brand.to_string())
+        }
+    }
+}
+
+fn cpu_model() -> Option<String> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if let Some(brand) = cpuid::brand() {
+            return Some(brand);
+        }
+    }
+    #[cfg(target_os = "linux")]
+    {
+        if let Some(model) = proc_cpuinfo_field("model name") {
+            return Some(model);
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        if let Some(model) = sysctl("machdep.cpu.brand_string") {
+            return Some(model);
+        }
+    }
+    None
+}
+
+#[allow(unused_variables)]
+fn microarchitecture(cpu_model: Option<&str>) -> Option<String> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        let (family, model) = cpuid::family_model();
+        let name = match cpuid::vendor().as_str() {
+            "AuthenticAMD" => amd_microarchitecture(family, model),
+            "GenuineIntel" => intel_microarchitecture(family, model),
+            _ => None,
+        };
+        return name.map(|name| name.to_string());
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if let Some(model) = cpu_model.filter(|model| model.starts_with("Apple ")) {
+            return Some(model.to_string());
+        }
+        #[cfg(target_os = "linux")]
+        {
+            let implementer = proc_cpuinfo_field("CPU implementer")?;
+            let part = proc_cpuinfo_field("CPU part")?;
+            let implementer = u32::from_str_radix(implementer.trim_start_matches("0x"), 16).ok()?;
+            let part = u32::from_str_radix(part.trim_start_matches("0x"), 16).ok()?;
+            return arm_microarchitecture(implementer, part).map(|name| name.to_string());
+        }
+    }
+    #[allow(unreachable_code)]
+    None
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn amd_microarchitecture(family: u32, model: u32) -> Option<&'static str> {
+    let name = match (family, model) {
+        (0x17, 0x00..=0x2f) => "Zen",
+        (0x17, _) => "Zen 2",
+        (0x19, 0x00..=0x0f) | (0x19, 0x20..=0x5f) => "Zen 3",
+        (0x19, _) => "Zen 4",
+        (0x1a, _) => "Zen 5",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn intel_microarchitecture(family: u32, model: u32) -> Option<&'static str> {
+    if family != 6 {
+        return None;
+    }
+    let name = match model {
+        0x3c | 0x3f | 0x45 | 0x46 => "Haswell",
+        0x3d | 0x47 | 0x4f | 0x56 => "Broadwell",
+        0x4e | 0x5e | 0x55 => "Skylake",
+        0x8e | 0x9e | 0xa5 | 0xa6 => "Kaby Lake",
+        0x66 => "Cannon Lake",
+        0x6a | 0x6c | 0x7d | 0x7e => "Sunny Cove",
+        0x8c | 0x8d => "Willow Cove",
+        0xa7 => "Cypress Cove",
+        0x97 | 0x9a | 0x8f => "Golden Cove",
+        0xb7 | 0xba | 0xbf | 0xcf => "Raptor Cove",
+        0xaa | 0xac | 0xad | 0xae => "Redwood Cove",
+        0xbd | 0xc5 | 0xc6 => "Lion Cove",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(target_arch = "aarch64")]
+#[allow(dead_code)]
+fn arm_microarchitecture(implementer: u32, part: u32) -> Option<&'static str> {
+    let name = match (implementer, part) {
+        (0x41, 0xd0c) => "Neoverse N1",
+        (0x41, 0xd40) => "Neoverse V1",
+        (0x41, 0xd49) => "Neoverse N2",
+        (0x41, 0xd4f) => "Neoverse V2",
+        (0x41, 0xd83) => "Neoverse V3",
+        (0x41, 0xd08) => "Cortex-A72",
+        (0x41, 0xd0b) => "Cortex-A76",
+        (0x41, 0xd41) => "Cortex-A78",
+        (0x41, 0xd44) => "Cortex-X1",
+        (0x61, _) => "Apple",
+        (0xc0, _) => "Ampere",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(target_os = "linux")]
+fn proc_cpuinfo_field(name: &str) -> Option<String> {
+    let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").ok()?;
+    cpuinfo.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        if key.trim() == name {
+            Some(value.trim().to_string())
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn sysctl(name: &str) -> Option<String> {
+    let output = std::process::Command::new("sysctl")
+        .args(["-n", name])
+        .output()
+        .ok()?;
+    let value = String::from_utf8(output.stdout).ok()?;
+    let value = value.trim();
+    if !output.status.success() || value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+fn hypervisor() -> Option<String> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if let Some(hypervisor) = cpuid::hypervisor() {
+            return Some(hypervisor);
+        }
+    }
+    #[cfg(target_os = "linux")]
+    {
+        if let Ok(kind) = std::fs::read_to_string("/sys/hypervisor/type") {
+            return Some(kind.trim().to_string());
+        }
+        let dmi = |name: &str| {
+            std::fs::read_to_string(format!("/sys/class/dmi/id/{}", name)).unwrap_or_default()
+        };
+        let dmi = format!("{} {}", dmi("sys_vendor"), dmi("product_name"));
+        for (pattern, name) in [
+            ("QEMU", "QEMU"),
+            ("KVM", "KVM"),
+            ("VMware", "VMware"),
+            ("VirtualBox", "VirtualBox"),
+            ("Virtual Machine", "Hyper-V"),
+            ("Amazon EC2", "Amazon EC2"),
+            ("Google Compute Engine", "Google Compute Engine"),
+        ] {
+            if dmi.contains(pattern) {
+                return Some(name.to_string());
+            }
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        if sysctl("kern.hv_vmm_present").as_deref() == Some("1") {
+            return Some("Apple Virtualization".to_string());
+        }
+    }
+    None
+}
+
+fn allowed_cpus() -> Option<usize> {
+    #[cfg(target_os = "linux")]
+    {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        let list = status
+            .lines()
+            .find_map(|line| line.strip_prefix("Cpus_allowed_list:"))?;
+        let mut count = 0;
+        for range in list.trim().split(',') {
+            count += match range.split_once('-') {
+                Some((first, last)) => {
+                    last.parse::<usize>().ok()? - first.parse::<usize>().ok()? + 1
+                }
+                None => 1,
+            };
+        }
+        return Some(count);
+    }
+    #[allow(unreachable_code)]
+    std::thread::available_parallelism()
+        .ok()
+        .map(|count| count.get())
+}
+
+fn logical_cpus() -> Option<usize> {
+    #[cfg(target_os = "linux")]
+    {
+        let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+        let count = cpuinfo
+            .lines()
+            .filter(|line| line.split(':').next().map(str::trim) == Some("processor"))
+            .count();
+        if count > 0 {
+            return Some(count);
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        if let Some(count) = sysctl("hw.logicalcpu").and_then(|count| count.parse().ok()) {
+            return Some(count);
+        }
+    }
+    std::thread::available_parallelism()
+        .ok()
+        .map(|count| count.get())
+}
+
+fn os() -> String {
+    let os = std::env::consts::OS;
+    #[cfg(target_os = "linux")]
+    {
+        if let Ok(release) = std::fs::read_to_string("/proc/sys/kernel/osrelease") {
+            return format!("{} {}", os, release.trim());
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        if let Some(release) = sysctl("kern.osrelease") {
+            return format!("{} {}", os, release);
+        }
+    }
+    os.to_string()
+}
+
+/// Returns a key identifying the host, its CPU and its system, so that data
+/// cached for a machine, such as the frequency of its timer, is invalidated
+/// when any of them changes.
+pub(crate) fn host_key() -> String {
+    format!(
+        "{} | {} | {} CPUs | {} | {}",
+        hostname().as_deref().unwrap_or("unknown"),
+        cpu_model().as_deref().unwrap_or("unknown"),
+        logical_cpus().unwrap_or(0),
+        os(),
+        env!("BENCHMARK_SIMPLE_TARGET")
+    )
+}
+
+fn hostname() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        if let Ok(hostname) = std::fs::read_to_string("/proc/sys/kernel/hostname") {
+            return non_empty(hostname.trim());
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        if let Some(hostname) = sysctl("kern.hostname") {
+            return Some(hostname);
+        }
+    }
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .find_map(|name| non_empty(&std::env::var(name).ok()?))
+}
+
+fn git_commit() -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    non_empty(String::from_utf8(output.stdout).ok()?.trim())
+}
+
+/// Returns the CPU frequency scaling governor.
+pub(crate) fn cpu_governor() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let governor =
+            std::fs::read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor")
+                .ok()?;
+        return Some(governor.trim().to_string());
+    }
+    #[allow(unreachable_code)]
+    None
+}
+
+/// Returns whether turbo boost is enabled.
+pub(crate) fn turbo_enabled() -> Option<bool> {
+    #[cfg(target_os = "linux")]
+    {
+        let read = |path: &str| {
+            std::fs::read_to_string(path)
+                .ok()
+                .map(|value| value.trim() == "1")
+        };
+        let no_turbo = read("/sys/devices/system/cpu/intel_pstate/no_turbo");
+        let boost = read("/sys/devices/system/cpu/cpufreq/boost");
+        return no_turbo.map(|no_turbo| !no_turbo).or(boost);
+    }
+    #[allow(unreachable_code)]
+    None
+}
+
+/// Returns warnings about frequency scaling settings that make results noisy.
+pub(crate) fn frequency_scaling_warnings() -> Vec<String> {
+    let mut warnings = Vec::new();
+    if let Some(governor) = cpu_governor().filter(|governor| governor != "performance") {
+        warnings.push(format!(
+            "the CPU frequency governor is '{}', results may be noisy; use 'performance'.",
+            governor
+        ));
+    }
+    if turbo_enabled() == Some(true) {
+        warnings.push("turbo boost is enabled, results may be noisy.".to_string());
+    }
+    warnings
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s.to_string())
+    }
+}
+
+/// CPU bandwidth control via Linux control groups.
+pub(crate) mod cgroup {
+    #[cfg(target_os = "linux")]
+    fn read(path: &std::path::Path) -> Option<String> {
+        std::fs::read_to_string(path).ok()
+    }
+
+    /// Returns the directory of the cgroup controlling the CPU bandwidth, and
+    /// whether it is a cgroup v2 hierarchy.
+    #[cfg(target_os = "linux")]
+    fn cpu_cgroup() -> Option<(std::path::PathBuf, bool)> {
+        let root = std::path::Path::new("/sys/fs/cgroup");
+        let cgroups = read(std::path::Path::new("/proc/self/cgroup"))?;
+        for line in cgroups.lines() {
+            let mut parts = line.splitn(3, ':');
+            let (_, controllers, path) = (parts.next()?, parts.next()?, parts.next()?);
+            let path = path.trim_start_matches('/');
+            if controllers.is_empty() {
+                let dir = root.join(path);
+                if dir.join("cpu.max").exists() {
+                    return Some((dir, true));
+                }
+            } else if controllers.split(',').any(|c| c == "cpu") {
+                for name in [controllers, "cpu", "cpu,cpuacct"] {
+                    let dir = root.join(name).join(path);
+                    if dir.join("cpu.cfs_quota_us").exists() {
+                        return Some((dir, false));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the CPU quota, in CPUs, if there is one.
+    pub fn cpu_quota() -> Option<f64> {
+        #[cfg(target_os = "linux")]
+        {
+            let (dir, v2) = cpu_cgroup()?;
+            let (quota, period) = if v2 {
+                let max = read(&dir.join("cpu.max"))?;
+                let mut parts = max.split_whitespace();
+                (parts.next()?.to_string(), parts.next()?.to_string())
+            } else {
+                (
+                    read(&dir.join("cpu.cfs_quota_us"))?,
+                    read(&dir.join("cpu.cfs_period_us"))?,
+                )
+            };
+            let quota = quota.trim().parse::<f64>().ok().filter(|&q| q > 0.0)?;
+            let period = period.trim().parse::<f64>().ok().filter(|&p| p > 0.0)?;
+            return Some(quota / period);
+        }
+        #[allow(unreachable_code)]
+        None
+    }
+
+    /// Returns the number of periods during which the cgroup was throttled.
+    pub fn throttled_periods() -> Option<u64> {
+        #[cfg(target_os = "linux")]
+        {
+            let (dir, _) = cpu_cgroup()?;
+            let stat = read(&dir.join("cpu.stat"))?;
+            return stat.lines().find_map(|line| {
+                line.strip_prefix("nr_throttled ")
+                    .and_then(|count| count.trim().parse().ok())
+            });
+        }
+        #[allow(unreachable_code)]
+        None
+    }
+}
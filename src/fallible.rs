@@ -0,0 +1,62 @@
+//! Benchmarks of functions that can fail.
+
+use std::cell::RefCell;
+use std::panic::Location;
+
+use crate::{Bench, BenchResult, Options};
+
+impl Bench {
+    /// Run a single test of a function returning a `Result`.
+    ///
+    /// The first error stops the benchmark and is returned, instead of
+    /// timing the error path as if it was a successful call: once `f` has
+    /// failed, it isn't called any more, and no more samples are collected.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// let bench = Bench::new();
+    /// let options = Options::quick()
+    ///     .into_builder()
+    ///     .iterations(10)
+    ///     .build()
+    ///     .unwrap();
+    /// let res = bench.try_run(&options, || "42".parse::<u64>());
+    /// assert!(res.is_ok());
+    ///
+    /// let mut calls = 0;
+    /// let res = bench.try_run(&options, || {
+    ///     calls += 1;
+    ///     if calls < 3 {
+    ///         Ok(calls)
+    ///     } else {
+    ///         Err("I/O error")
+    ///     }
+    /// });
+    /// assert_eq!(res.unwrap_err(), "I/O error");
+    /// ```
+    #[track_caller]
+    pub fn try_run<F, T, E>(&self, options: &Options, mut f: F) -> Result<BenchResult, E>
+    where
+        F: FnMut() -> Result<T, E>,
+    {
+        let error = RefCell::new(None);
+        let failed = || error.borrow().is_some();
+        let result = self.run_cancellable_at(options, None, Location::caller(), &failed, || {
+            if failed() {
+                return None;
+            }
+            match f() {
+                Ok(value) => Some(value),
+                Err(e) => {
+                    *error.borrow_mut() = Some(e);
+                    None
+                }
+            }
+        });
+        match error.into_inner() {
+            Some(e) => Err(e),
+            None => Ok(result),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Selection of the benchmarks to run.
+
+/// A pattern selecting benchmarks.
+///
+/// `tag:<tag>` selects the benchmarks with that tag, and `group:<group>` the
+/// benchmarks of a group and its subgroups, such as `group:aead/aes256gcm` for
+/// `aead/aes256gcm/encrypt/16384`. Other patterns select
+/// the benchmarks whose name contains them or, with the `regex` feature,
+/// whose name matches them as a regular expression.
+#[derive(Clone, Debug)]
+pub(crate) enum Filter {
+    Tag(String),
+    Group(String),
+    Name(String),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl Filter {
+    /// Create a filter, matching invalid regular expressions as substrings.
+    pub(crate) fn new(pattern: &str) -> Self {
+        match Self::try_new(pattern) {
+            Ok(filter) => filter,
+            Err(e) => {
+                eprintln!("{}, matching it as a substring", e);
+                Filter::Name(pattern.to_string())
+            }
+        }
+    }
+
+    /// Create a filter, returning an error for invalid regular expressions.
+    pub(crate) fn try_new(pattern: &str) -> Result<Self, String> {
+        if let Some(tag) = pattern.strip_prefix("tag:") {
+            return Ok(Filter::Tag(tag.to_string()));
+        }
+        if let Some(group) = pattern.strip_prefix("group:") {
+            return Ok(Filter::Group(group.trim_end_matches('/').to_string()));
+        }
+        #[cfg(feature = "regex")]
+        return regex::Regex::new(pattern)
+            .map(Filter::Regex)
+            .map_err(|e| format!("Invalid filter [{}]: {}", pattern, e));
+        #[allow(unreachable_code)]
+        Ok(Filter::Name(pattern.to_string()))
+    }
+
+    pub(crate) fn matches(&self, name: &str, tags: &[String]) -> bool {
+        match self {
+            Filter::Tag(tag) => tags.iter().any(|t| t == tag),
+            Filter::Group(group) => name
+                .strip_prefix(group.as_str())
+                .is_some_and(|rest| rest.starts_with('/')),
+            Filter::Name(pattern) => name.contains(pattern.as_str()),
+            #[cfg(feature = "regex")]
+            Filter::Regex(regex) => regex.is_match(name),
+        }
+    }
+
+    /// Returns a description of the filter, for error messages.
+    fn describe(&self) -> String {
+        match self {
+            Filter::Tag(tag) => format!("tag:{}", tag),
+            Filter::Group(group) => format!("group:{}", group),
+            Filter::Name(pattern) => pattern.clone(),
+            #[cfg(feature = "regex")]
+            Filter::Regex(regex) => regex.as_str().to_string(),
+        }
+    }
+
+    /// Returns a message explaining that no benchmarks matched, suggesting
+    /// the closest names, tags or groups.
+    pub(crate) fn no_match_message<'a>(
+        &self,
+        benchmarks: impl Iterator<Item = (&'a str, &'a [String])> + Clone,
+    ) -> String {
+        let (pattern, candidates): (&str, Vec<&str>) = match self {
+            Filter::Tag(tag) => (
+                tag,
+                benchmarks
+                    .flat_map(|(_, tags)| tags.iter().map(String::as_str))
+                    .collect(),
+            ),
+            Filter::Group(group) => (
+                group,
+                benchmarks
+                    .filter_map(|(name, _)| name.rsplit_once('/').map(|(group, _)| group))
+                    .collect(),
+            ),
+            Filter::Name(pattern) => (pattern, benchmarks.map(|(name, _)| name).collect()),
+            #[cfg(feature = "regex")]
+            Filter::Regex(regex) => (regex.as_str(), benchmarks.map(|(name, _)| name).collect()),
+        };
+        let mut message = format!("No benchmarks match [{}]", self.describe());
+        let suggestions = suggestions(pattern, candidates);
+        if !suggestions.is_empty() {
+            message.push_str(&format!(
+                "; did you mean {}?",
+                suggestions
+                    .iter()
+                    .map(|s| format!("[{}]", s))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        message
+    }
+}
+
+/// Returns up to 3 candidates close to `pattern`, closest first.
+fn suggestions<'a>(pattern: &str, mut candidates: Vec<&'a str>) -> Vec<&'a str> {
+    candidates.sort_unstable();
+    candidates.dedup();
+    let max_distance = std::cmp::max(2, pattern.chars().count() / 3);
+    let mut scored: Vec<_> = candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(pattern, candidate), candidate))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .collect();
+    scored.sort_unstable();
+    scored.into_iter().take(3).map(|(_, s)| s).collect()
+}
+
+/// Returns the Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
@@ -0,0 +1,335 @@
+//! A command-line entry point for `harness = false` bench targets.
+
+use std::path::{Path, PathBuf};
+use std::process;
+
+#[cfg(feature = "toml")]
+use crate::config::OptionsConfig;
+use crate::filter::Filter;
+use crate::{Baseline, Options, Suite};
+
+const USAGE: &str = "Usage: [--filter <pattern>] [--json <path>] [--baseline <path>] [--max-regression <percent>] [--config <path>] [--shuffle] [--shuffle-seed <seed>] [--flamegraphs] [--calibrate] [--quick] [--verbose] [--diagnostics] [pattern]";
+
+/// Command-line options of a benchmark executable.
+///
+/// `--filter <pattern>` (or a bare pattern, as passed by `cargo bench <pattern>`)
+/// only runs matching benchmarks (see [`Suite::filter`]), `--json <path>` saves the report,
+/// `--baseline <path>` compares the results with a previously saved report (or a hyperfine
+/// export, or a criterion directory, see [`Baseline::load`]),
+/// `--max-regression <percent>` exits with an error if a result is slower than the
+/// baseline by more than that (see [`Report::assert_no_regression`](crate::Report::assert_no_regression)),
+/// `--config <path>` loads options from a TOML file (`toml` feature, see
+/// `OptionsConfig`), `--shuffle` runs the benchmarks in a random order, and
+/// `--shuffle-seed <seed>` in the order printed by a previous run (see
+/// [`Suite::shuffle`]), `--flamegraphs` saves a flamegraph of every benchmark next to
+/// the report (`profiling` feature, see `Suite::set_flamegraphs`), `--calibrate` stores the
+/// calibration score of the machine in the report (see [`Suite::calibrate`]),
+/// `--quick` collects a single sample per benchmark, `--verbose`
+/// enables verbose output, and `--diagnostics` also prints a `file:line:col` line per
+/// result, for editors to jump to (see [`Report::to_diagnostics`](crate::Report::to_diagnostics)).
+#[derive(Clone, Debug, Default)]
+pub struct Harness {
+    /// Only run benchmarks matching this pattern, instead of `BENCHMARK_FILTER`.
+    pub filter: Option<String>,
+    /// Where to save the report, as JSON.
+    pub json: Option<PathBuf>,
+    /// A previously saved report to compare the results with.
+    pub baseline: Option<PathBuf>,
+    /// Fail if a result is slower than the baseline by more than this percentage.
+    pub max_regression: Option<f64>,
+    /// A TOML file with the options of the benchmarks.
+    pub config: Option<PathBuf>,
+    /// Run the benchmarks in a random order.
+    pub shuffle: bool,
+    /// Run the benchmarks in the order derived from this seed.
+    pub shuffle_seed: Option<u64>,
+    /// Save a flamegraph of every benchmark.
+    pub flamegraphs: bool,
+    /// Measure the calibration score of the machine.
+    pub calibrate: bool,
+    /// Use `Options::quick()` instead of the default options.
+    pub quick: bool,
+    /// Enable verbose output.
+    pub verbose: bool,
+    /// Also print the results as compiler-style diagnostics.
+    pub diagnostics: bool,
+}
+
+impl Harness {
+    /// Parse the command-line arguments of the current process.
+    ///
+    /// On invalid arguments, the usage is printed and the process exits.
+    pub fn from_args() -> Self {
+        match Self::parse(std::env::args().skip(1)) {
+            Ok(harness) => harness,
+            Err(e) => {
+                eprintln!("{}\n{}", e, USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    /// Parse command-line arguments, excluding the executable name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut harness = Harness::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for {}", name))
+            };
+            match arg.as_str() {
+                "--filter" => harness.filter = Some(value("--filter")?),
+                "--json" => harness.json = Some(value("--json")?.into()),
+                "--baseline" => harness.baseline = Some(value("--baseline")?.into()),
+                "--max-regression" => {
+                    let value = value("--max-regression")?;
+                    harness.max_regression = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|pct: &f64| *pct >= 0.0)
+                            .ok_or_else(|| {
+                                format!("Invalid value for --max-regression: {}", value)
+                            })?,
+                    )
+                }
+                "--config" => harness.config = Some(value("--config")?.into()),
+                "--shuffle" => harness.shuffle = true,
+                "--shuffle-seed" => {
+                    let value = value("--shuffle-seed")?;
+                    harness.shuffle_seed = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid value for --shuffle-seed: {}", value))?,
+                    )
+                }
+                "--flamegraphs" => harness.flamegraphs = true,
+                "--calibrate" => harness.calibrate = true,
+                "--quick" => harness.quick = true,
+                "--verbose" => harness.verbose = true,
+                "--diagnostics" => harness.diagnostics = true,
+                // Passed by `cargo bench`.
+                "--bench" => {}
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                arg if arg.starts_with('-') => return Err(format!("Unknown option: {}", arg)),
+                pattern => harness.filter = Some(pattern.to_string()),
+            }
+        }
+        Ok(harness)
+    }
+
+    /// Returns the options to run the benchmarks with.
+    pub fn options(&self) -> Options {
+        let mut options = if self.quick {
+            Options::quick()
+        } else {
+            Options::default()
+        };
+        options.verbose |= self.verbose;
+        options
+    }
+
+    /// Load and validate everything the run needs: the filter, the options,
+    /// the configuration file, the baseline, and the output path.
+    ///
+    /// Errors are reported before any benchmark runs, with a message
+    /// explaining what is wrong.
+    pub fn config(&self) -> Result<RunConfig, String> {
+        let filter = self.filter.as_deref().map(Filter::try_new).transpose()?;
+        let options = self.options();
+        options
+            .validate()
+            .map_err(|e| format!("Invalid options: {}", e))?;
+        #[cfg(feature = "toml")]
+        let config = match &self.config {
+            Some(_) if self.quick => {
+                return Err("--quick can't be combined with --config".to_string())
+            }
+            Some(path) => Some(OptionsConfig::from_file(path).map_err(|e| {
+                format!(
+                    "Unable to load the configuration [{}]: {}",
+                    path.display(),
+                    e
+                )
+            })?),
+            None => None,
+        };
+        #[cfg(not(feature = "toml"))]
+        if self.config.is_some() {
+            return Err("--config requires the `toml` feature".to_string());
+        }
+        let baseline = self
+            .baseline
+            .as_ref()
+            .map(|path| {
+                Baseline::load(path)
+                    .map_err(|e| format!("Unable to load the baseline [{}]: {}", path.display(), e))
+            })
+            .transpose()?;
+        if self.max_regression.is_some() && baseline.is_none() {
+            return Err("--max-regression requires --baseline".to_string());
+        }
+        if let Some(path) = &self.json {
+            let parent = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty());
+            if parent.is_some_and(|parent| !parent.is_dir()) {
+                return Err(format!(
+                    "Unable to save the report [{}]: the directory doesn't exist",
+                    path.display()
+                ));
+            }
+        }
+        #[cfg(not(all(feature = "profiling", unix)))]
+        if self.flamegraphs {
+            return Err("--flamegraphs requires the `profiling` feature, on Unix".to_string());
+        }
+        Ok(RunConfig {
+            options,
+            filter,
+            baseline,
+            max_regression: self.max_regression,
+            shuffle_seed: match self.shuffle_seed {
+                Some(seed) => Some(seed),
+                None => self.shuffle.then(crate::suite::random_seed),
+            },
+            json: self.json.clone(),
+            calibrate: self.calibrate,
+            #[cfg(all(feature = "profiling", unix))]
+            flamegraphs: self.flamegraphs.then(|| {
+                // Next to the report, if it is saved.
+                self.json
+                    .as_deref()
+                    .and_then(Path::parent)
+                    .filter(|parent| !parent.as_os_str().is_empty())
+                    .map_or_else(|| PathBuf::from("."), Path::to_path_buf)
+            }),
+            #[cfg(feature = "toml")]
+            config,
+        })
+    }
+
+    /// Register benchmarks with `register`, run them, and print the report,
+    /// followed by its summary (see [`Report::summary`](crate::Report::summary)).
+    ///
+    /// On GitHub Actions, the report is also added to the job summary (see
+    /// [`Report::write_github_summary`](crate::Report::write_github_summary)).
+    ///
+    /// The process exits with an error if the configuration is invalid (see
+    /// [`Harness::config`]), the report can't be saved, a benchmark failed,
+    /// or a result regressed by more than `--max-regression`.
+    pub fn run<'a>(&self, register: impl FnOnce(&mut Suite<'a>)) {
+        let config = self.config().unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        });
+        let mut suite = Suite::from_run_config(&config);
+        register(&mut suite);
+        let report = suite.run();
+        match config.baseline() {
+            Some(baseline) => report.print_compared(baseline),
+            None => report.print(),
+        }
+        println!("\n{}", report.summary());
+        if self.diagnostics {
+            print!("\n{}", report.to_diagnostics(config.baseline()));
+        }
+        if let Err(e) = report.write_github_summary(config.baseline()) {
+            eprintln!("Unable to write the job summary: {}", e);
+        }
+        if let Some(path) = config.json() {
+            if let Err(e) = std::fs::write(path, report.to_json()) {
+                eprintln!("Unable to save the report [{}]: {}", path.display(), e);
+                process::exit(1);
+            }
+        }
+        if let (Some(baseline), Some(max_regression)) = (config.baseline(), config.max_regression())
+        {
+            if let Err(e) = report.assert_no_regression(baseline, max_regression) {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        if report.failures().next().is_some() {
+            process::exit(1);
+        }
+    }
+}
+
+/// The validated settings of a benchmark run, loaded once by [`Harness::config`].
+#[derive(Clone, Debug)]
+pub struct RunConfig {
+    pub(crate) options: Options,
+    pub(crate) filter: Option<Filter>,
+    baseline: Option<Baseline>,
+    max_regression: Option<f64>,
+    pub(crate) shuffle_seed: Option<u64>,
+    json: Option<PathBuf>,
+    pub(crate) calibrate: bool,
+    #[cfg(all(feature = "profiling", unix))]
+    pub(crate) flamegraphs: Option<PathBuf>,
+    #[cfg(feature = "toml")]
+    pub(crate) config: Option<OptionsConfig>,
+}
+
+impl RunConfig {
+    /// Returns the options of the benchmarks, unless a configuration file
+    /// provides them.
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// Returns the baseline to compare the results with, if any.
+    pub fn baseline(&self) -> Option<&Baseline> {
+        self.baseline.as_ref()
+    }
+
+    /// Returns the largest tolerated regression compared to the baseline
+    /// (in %), if any.
+    pub fn max_regression(&self) -> Option<f64> {
+        self.max_regression
+    }
+
+    /// Returns where to save the report, if anywhere.
+    pub fn json(&self) -> Option<&Path> {
+        self.json.as_deref()
+    }
+}
+
+/// Define a `main()` function for a `harness = false` bench target.
+///
+/// Every argument is a function taking a `&mut Suite` and registering
+/// benchmarks. With the `macros` feature and no arguments, the functions
+/// annotated with `#[benchmark]` are registered instead.
+/// Command-line arguments are parsed by [`Harness`].
+///
+/// ```rust,no_run
+/// use benchmark_simple::Suite;
+///
+/// fn hashing(suite: &mut Suite) {
+///     suite.register("sum", || (0..1000u64).sum::<u64>());
+/// }
+///
+/// benchmark_simple::main!(hashing);
+/// ```
+#[macro_export]
+macro_rules! main {
+    () => {
+        fn main() {
+            $crate::Harness::from_args().run(|suite| {
+                suite.register_annotated();
+            });
+        }
+    };
+    ($($group:path),+ $(,)?) => {
+        fn main() {
+            $crate::Harness::from_args().run(|suite| {
+                $($group(suite);)+
+            });
+        }
+    };
+}
@@ -0,0 +1,291 @@
+//! A minimal HTTP server exposing the latest report, and a client pushing
+//! metrics to a Prometheus pushgateway.
+//!
+//! Only available with the `http` feature.
+
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::report::split_group;
+use crate::{Report, Unit, Volume};
+
+#[derive(Default)]
+struct Published {
+    json: String,
+    html: String,
+}
+
+/// Serves the latest published report as JSON (`/report.json`) and HTML (`/`).
+///
+/// Clones share the same published report, so a server can run on a
+/// background thread while the benchmarks publish new reports.
+#[derive(Clone, Default)]
+pub struct ReportServer {
+    published: Arc<Mutex<Published>>,
+}
+
+impl ReportServer {
+    /// Create a server with no published report yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Publish a report, replacing the previous one.
+    pub fn publish(&self, report: &Report) {
+        let mut published = self.published.lock().unwrap();
+        published.json = report.to_json();
+        published.html = report.to_html();
+    }
+
+    /// Serve requests from a listener, on the current thread.
+    pub fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            // A misbehaving client shouldn't stop the server.
+            let _ = self.handle(stream?);
+        }
+        Ok(())
+    }
+
+    /// Serve requests from a listener, on a new thread.
+    pub fn spawn(&self, listener: TcpListener) -> JoinHandle<io::Result<()>> {
+        let server = self.clone();
+        thread::spawn(move || server.serve(listener))
+    }
+
+    fn handle(&self, mut stream: TcpStream) -> io::Result<()> {
+        let mut request_line = String::new();
+        BufReader::new(&stream).read_line(&mut request_line)?;
+        let mut parts = request_line.split_whitespace();
+        let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        let (status, content_type, body) = {
+            let published = self.published.lock().unwrap();
+            match (method, path) {
+                ("GET", "/report.json") => (
+                    "200 OK",
+                    "application/json",
+                    if published.json.is_empty() {
+                        "null\n".to_string()
+                    } else {
+                        published.json.clone()
+                    },
+                ),
+                ("GET", "/") | ("GET", "/index.html") => {
+                    ("200 OK", "text/html; charset=utf-8", published.html.clone())
+                }
+                ("GET", _) => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+                _ => (
+                    "405 Method Not Allowed",
+                    "text/plain",
+                    "Method not allowed\n".to_string(),
+                ),
+            }
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        )?;
+        stream.flush()
+    }
+}
+
+/// Pushes the metrics of a report to a Prometheus pushgateway, so that
+/// Grafana dashboards can track them.
+///
+/// Every benchmark gets the following metrics, labeled with its `name`, its
+/// `group` and `param` (the parent and the last component of hierarchical
+/// names) and the git `commit`, if known:
+///
+/// - `benchmark_duration_seconds`: the duration of an iteration
+/// - `benchmark_rsd_percent`: the relative standard deviation of the samples
+/// - `benchmark_throughput_per_second`: the throughput, with a `unit` label,
+///   with [`Pushgateway::push_with_volumes()`]
+///
+/// Only `http://` URLs are supported.
+#[derive(Clone, Debug)]
+pub struct Pushgateway {
+    host: String,
+    path: String,
+}
+
+impl Pushgateway {
+    /// Push to a gateway such as `http://localhost:9091`, under a job name.
+    pub fn new(url: &str, job: &str) -> io::Result<Self> {
+        let host = url
+            .strip_prefix("http://")
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "unsupported pushgateway URL, expected http://",
+                )
+            })?
+            .trim_end_matches('/');
+        Ok(Pushgateway {
+            host: host.to_string(),
+            path: format!("/metrics/{}", path_label("job", job)),
+        })
+    }
+
+    /// Add a grouping label, such as the name of the machine. Metrics
+    /// pushed with the same grouping labels replace each other.
+    pub fn label(mut self, name: &str, value: &str) -> Self {
+        self.path.push('/');
+        self.path.push_str(&path_label(name, value));
+        self
+    }
+
+    /// Push the durations and RSDs of the results of a report.
+    pub fn push(&self, report: &Report) -> io::Result<()> {
+        self.push_with_volumes(report, |_| None)
+    }
+
+    /// Push the metrics of a report, including the throughputs of the
+    /// benchmarks for which `volume` returns the volume processed in an
+    /// iteration.
+    pub fn push_with_volumes(
+        &self,
+        report: &Report,
+        volume: impl Fn(&str) -> Option<Volume>,
+    ) -> io::Result<()> {
+        let body = metrics(report, volume);
+        let mut stream = TcpStream::connect(&self.host)?;
+        write!(
+            stream,
+            "PUT {} HTTP/1.1\r\nHost: {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.host,
+            body.len(),
+            body
+        )?;
+        stream.flush()?;
+        let mut status_line = String::new();
+        BufReader::new(&stream).read_line(&mut status_line)?;
+        match status_line.split_whitespace().nth(1) {
+            Some(status) if status.starts_with('2') => Ok(()),
+            _ => Err(io::Error::other(format!(
+                "the pushgateway rejected the metrics: {}",
+                status_line.trim()
+            ))),
+        }
+    }
+}
+
+/// Returns the metrics of a report, in the Prometheus text format.
+fn metrics(report: &Report, volume: impl Fn(&str) -> Option<Volume>) -> String {
+    let commit = report.environment().git_commit.as_deref();
+    let (mut durations, mut rsds, mut throughputs) = (String::new(), String::new(), String::new());
+    for (name, result) in report.sorted() {
+        let (group, param) = split_group(name);
+        let mut labels = format!("name=\"{}\"", escape(name));
+        if let Some(group) = group {
+            let _ = write!(
+                labels,
+                ",group=\"{}\",param=\"{}\"",
+                escape(group),
+                escape(param)
+            );
+        }
+        if let Some(commit) = commit {
+            let _ = write!(labels, ",commit=\"{}\"", escape(commit));
+        }
+        let _ = writeln!(
+            durations,
+            "benchmark_duration_seconds{{{}}} {:e}",
+            labels,
+            result.per_iteration_duration().as_secs_f64()
+        );
+        if let Some(rsd) = result.rsd() {
+            let _ = writeln!(rsds, "benchmark_rsd_percent{{{}}} {}", labels, rsd);
+        }
+        if let Some(volume) = volume(name) {
+            let unit = match volume.unit() {
+                Unit::None => "none",
+                Unit::Bytes => "bytes",
+                Unit::Bits => "bits",
+                Unit::Elements => "elements",
+            };
+            let throughput = result.clone().throughput_volume(volume);
+            if !throughput.is_below_resolution() {
+                let _ = writeln!(
+                    throughputs,
+                    "benchmark_throughput_per_second{{{},unit=\"{}\"}} {:e}",
+                    labels,
+                    unit,
+                    throughput.as_f64()
+                );
+            }
+        }
+    }
+    let mut out = String::new();
+    for (metric, help, lines) in [
+        (
+            "benchmark_duration_seconds",
+            "Duration of an iteration.",
+            durations,
+        ),
+        (
+            "benchmark_rsd_percent",
+            "Relative standard deviation of the samples.",
+            rsds,
+        ),
+        (
+            "benchmark_throughput_per_second",
+            "Throughput, in units per second.",
+            throughputs,
+        ),
+    ] {
+        if !lines.is_empty() {
+            let _ = write!(
+                out,
+                "# HELP {} {}\n# TYPE {} gauge\n{}",
+                metric, help, metric, lines
+            );
+        }
+    }
+    out
+}
+
+/// Escape a label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Returns a grouping label as a path component, base64-encoding values
+/// that can't appear in a path.
+fn path_label(name: &str, value: &str) -> String {
+    if !value.is_empty()
+        && value
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || b"-_.".contains(&c))
+    {
+        return format!("{}/{}", name, value);
+    }
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    let mut encoded = String::new();
+    for chunk in value.as_bytes().chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..chunk.len() + 1 {
+            encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+        }
+        for _ in chunk.len()..3 {
+            encoded.push('=');
+        }
+    }
+    if encoded.is_empty() {
+        // An empty value is encoded as a single padding character.
+        encoded.push('=');
+    }
+    format!("{}@base64/{}", name, encoded)
+}
@@ -0,0 +1,258 @@
+//! Running benchmarks in child processes.
+//!
+//! The current executable is spawned again with environment variables
+//! naming the benchmark to run and the `Suite::run()` call it belongs to. In
+//! the child, that call only runs the benchmark, writes its result to the
+//! standard output, and exits. The rest of the output of the child is
+//! forwarded to the output of the parent.
+
+use std::io::{BufRead, BufReader};
+use std::panic::Location;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use precision::Elapsed;
+
+use crate::{json, stats, Bench, BenchResult, Options, ResourceUsage, StopReason};
+
+/// Name of the benchmark a child process has to run.
+const BENCHMARK_ENV: &str = "BENCHMARK_SIMPLE_ISOLATED";
+/// Index of the `Suite::run()` call the benchmark belongs to.
+const SUITE_ENV: &str = "BENCHMARK_SIMPLE_ISOLATED_SUITE";
+/// Maximum duration of the benchmark, in nanoseconds, if the parent has a budget.
+const MAX_DURATION_ENV: &str = "BENCHMARK_SIMPLE_ISOLATED_MAX_DURATION";
+/// Prefix of the line carrying the result.
+const RESULT_PREFIX: &str = "BENCHMARK_SIMPLE_RESULT ";
+
+/// Number of `Suite::run()` calls so far.
+static SUITE_RUNS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the index of a new `Suite::run()` call, which identifies the
+/// suite in child processes, as they make the same calls.
+pub(crate) fn next_suite() -> usize {
+    SUITE_RUNS.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Returns the benchmark to run, if this is a child process, along with
+/// the index of its suite and the maximum duration set by the parent.
+pub(crate) fn child_benchmark() -> Option<(String, Option<usize>, Option<Duration>)> {
+    let name = std::env::var(BENCHMARK_ENV).ok()?;
+    let suite = std::env::var(SUITE_ENV)
+        .ok()
+        .and_then(|index| index.parse().ok());
+    let max_duration = std::env::var(MAX_DURATION_ENV)
+        .ok()
+        .and_then(|ns| ns.parse().ok())
+        .map(Duration::from_nanos);
+    Some((name, suite, max_duration))
+}
+
+/// Send a result to the parent process.
+///
+/// Along with the measurements, the result carries what was decided in the
+/// child, such as the calibrated number of iterations. Timestamps are sent
+/// as strings, as nanoseconds since the epoch don't fit in a JSON number.
+pub(crate) fn send(result: &BenchResult) {
+    let samples: Vec<_> = result
+        .samples
+        .iter()
+        .map(|sample| sample.as_nanos().to_string())
+        .collect();
+    let timestamps: Vec<_> = result
+        .timestamps
+        .iter()
+        .map(|timestamp| {
+            let ns = timestamp
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos();
+            json::string(&ns.to_string())
+        })
+        .collect();
+    let resource_usage: Vec<_> = result
+        .resource_usage
+        .iter()
+        .map(|usage| {
+            format!(
+                "{{\"minor_faults\": {}, \"major_faults\": {}, \"voluntary_switches\": {}, \"involuntary_switches\": {}}}",
+                usage.minor_faults,
+                usage.major_faults,
+                usage.voluntary_switches,
+                usage.involuntary_switches
+            )
+        })
+        .collect();
+    println!(
+        "{}{{\"ns\": {}, \"iterations\": {}, \"rsd\": {}, \"samples\": [{}], \"timestamps\": [{}], \"resource_usage\": [{}], \"interrupted\": {}, \"cpu_throttled\": {}, \"throttled_samples\": {}, \"near_resolution\": {}, \"sample_timed_out\": {}, \"stop_reason\": {}}}",
+        RESULT_PREFIX,
+        result.as_ns(),
+        result.options.iterations,
+        result.rsd.map_or_else(|| "null".to_string(), json::number),
+        samples.join(", "),
+        timestamps.join(", "),
+        resource_usage.join(", "),
+        result.interrupted,
+        result.cpu_throttled,
+        result.throttled_samples,
+        result.near_resolution,
+        result.sample_timed_out,
+        result
+            .stop_reason
+            .map_or_else(|| "null".to_string(), |reason| json::string(reason.name())),
+    );
+}
+
+/// Combine the results of the same benchmark, run in several processes.
+///
+/// The median result is kept, as the fastest one may only come from a lucky
+/// code layout. The samples of all the processes are merged, and scaled to
+/// the number of iterations of that result, as every process calibrates its
+/// own.
+pub(crate) fn aggregate(mut results: Vec<BenchResult>) -> BenchResult {
+    let secs: Vec<f64> = results.iter().map(|r| r.secs_per_iteration()).collect();
+    results.sort_by(|a, b| a.secs_per_iteration().total_cmp(&b.secs_per_iteration()));
+    let iterations = results[results.len() / 2].options.iterations;
+    let samples: Arc<[Duration]> = results
+        .iter()
+        .flat_map(|r| {
+            let scale = iterations as f64 / r.options.iterations.max(1) as f64;
+            r.samples.iter().map(move |sample| sample.mul_f64(scale))
+        })
+        .collect();
+    let timestamps: Arc<[SystemTime]> = results
+        .iter()
+        .flat_map(|r| r.timestamps.iter().copied())
+        .collect();
+    let resource_usage: Arc<[ResourceUsage]> = results
+        .iter()
+        .flat_map(|r| r.resource_usage.iter().copied())
+        .collect();
+    let interrupted = results.iter().any(|r| r.interrupted);
+    let cpu_throttled = results.iter().any(|r| r.cpu_throttled);
+    let near_resolution = results.iter().any(|r| r.near_resolution);
+    let sample_timed_out = results.iter().any(|r| r.sample_timed_out);
+    let throttled_samples = results.iter().map(|r| r.throttled_samples).sum();
+    let mut result = results.swap_remove(results.len() / 2);
+    result.rsd = stats::rsd(&samples.iter().map(|d| d.as_secs_f64()).collect::<Vec<_>>());
+    result.process_rsd = stats::rsd(&secs);
+    result.samples = samples;
+    result.timestamps = timestamps;
+    result.resource_usage = resource_usage;
+    result.interrupted = interrupted;
+    result.cpu_throttled = cpu_throttled;
+    result.near_resolution = near_resolution;
+    result.sample_timed_out = sample_timed_out;
+    result.throttled_samples = throttled_samples;
+    result
+}
+
+impl Bench {
+    /// Run the benchmark named `name`, of the suite with the index `suite`,
+    /// in a child process.
+    pub(crate) fn run_isolated(
+        &self,
+        name: &str,
+        suite: usize,
+        options: &Options,
+        location: &'static Location<'static>,
+    ) -> Result<BenchResult, String> {
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        let mut command = Command::new(exe);
+        command
+            .args(std::env::args_os().skip(1))
+            .env(BENCHMARK_ENV, name)
+            .env(SUITE_ENV, suite.to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped());
+        match options.max_duration {
+            Some(max_duration) => {
+                command.env(MAX_DURATION_ENV, max_duration.as_nanos().to_string())
+            }
+            None => command.env_remove(MAX_DURATION_ENV),
+        };
+        let mut child = command.spawn().map_err(|e| e.to_string())?;
+        let stdout = child.stdout.take().ok_or("no standard output")?;
+        let mut line = None;
+        for output in BufReader::new(stdout).lines() {
+            let output = output.map_err(|e| e.to_string())?;
+            match output.strip_prefix(RESULT_PREFIX) {
+                Some(result) => line = Some(result.to_string()),
+                None => println!("{}", output),
+            }
+        }
+        let status = child.wait().map_err(|e| e.to_string())?;
+        let line = match line {
+            Some(line) => line,
+            None if status.success() => {
+                return Err("the child process exited without running the benchmark".to_string())
+            }
+            None => return Err(format!("the child process failed ({})", status)),
+        };
+        let value = json::parse(&line)?;
+        let number = |key: &str| value.get(key).and_then(json::Value::as_f64);
+        let flag = |key: &str| {
+            value
+                .get(key)
+                .and_then(json::Value::as_bool)
+                .unwrap_or(false)
+        };
+        let ns = number("ns").ok_or("missing duration")? as u128;
+        // The child calibrated its own timer: convert back to ticks of ours.
+        let ticks = ns * self.precision.frequency() as u128 / 1_000_000_000;
+        // Samples, and thus `ns`, are for the iterations calibrated by the child.
+        let mut options = options.clone();
+        if let Some(iterations) = number("iterations") {
+            options.iterations = iterations as u64;
+        }
+        let mut result = self.result(
+            Elapsed::from_ticks(ticks as u64),
+            Arc::new(options),
+            location,
+        );
+        result.rsd = number("rsd");
+        result.samples = value
+            .get("samples")
+            .and_then(json::Value::as_array)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|sample| sample.as_f64())
+            .map(|ns| Duration::from_nanos(ns as u64))
+            .collect();
+        result.timestamps = value
+            .get("timestamps")
+            .and_then(json::Value::as_array)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|timestamp| timestamp.as_str()?.parse().ok())
+            .map(|ns| UNIX_EPOCH + Duration::from_nanos(ns))
+            .collect();
+        result.resource_usage = value
+            .get("resource_usage")
+            .and_then(json::Value::as_array)
+            .unwrap_or_default()
+            .iter()
+            .map(|usage| {
+                let counter =
+                    |key: &str| usage.get(key).and_then(json::Value::as_f64).unwrap_or(0.0) as u64;
+                ResourceUsage {
+                    minor_faults: counter("minor_faults"),
+                    major_faults: counter("major_faults"),
+                    voluntary_switches: counter("voluntary_switches"),
+                    involuntary_switches: counter("involuntary_switches"),
+                }
+            })
+            .collect();
+        result.interrupted = flag("interrupted");
+        result.cpu_throttled = flag("cpu_throttled");
+        result.near_resolution = flag("near_resolution");
+        result.throttled_samples = number("throttled_samples").unwrap_or(0.0) as usize;
+        result.stop_reason = value
+            .get("stop_reason")
+            .and_then(json::Value::as_str)
+            .and_then(StopReason::from_name);
+        result.sample_timed_out = flag("sample_timed_out");
+        Ok(result)
+    }
+}
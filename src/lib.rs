@@ -1,61 +1,276 @@
-#![doc = include_str!("../README.md")]
+#![cfg_attr(feature = "std", doc = include_str!("../README.md"))]
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "libtest", feature(test))]
 
-use std::cmp::max;
-use std::fmt::{self, Debug, Display, Formatter};
-use std::mem;
-use std::ops::Add;
-use std::ptr;
-use std::rc::Rc;
-use std::time::Duration;
+#[cfg(feature = "libtest")]
+extern crate test;
 
+#[cfg(feature = "std")]
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    io::{self, Write},
+    iter::Sum,
+    ops::{Add, ControlFlow, Div, Sub},
+    panic::Location,
+    sync::atomic::{AtomicBool, Ordering},
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant, SystemTime},
+};
+
+#[cfg(feature = "std")]
+use engine::SamplingEvent;
+#[cfg(feature = "std")]
 use precision::*;
+#[cfg(feature = "std")]
+use timer::Timer;
 
-/// Options.
-#[derive(Clone, Debug)]
-pub struct Options {
-    /// Number of iterations to perform.
-    pub iterations: u64,
-    /// Number of warm-up iterations to perform.
-    pub warmup_iterations: u64,
-    /// Minimum number of samples to collect.
-    pub min_samples: usize,
-    /// Maximum number of samples to collect.
-    pub max_samples: usize,
-    /// Maximum RSD to tolerate (in 0...100).
-    pub max_rsd: f64,
-    /// Maximum benchmark duration time.
-    pub max_duration: Option<Duration>,
-    /// Verbose output
-    pub verbose: bool,
-}
-
-impl Default for Options {
-    fn default() -> Self {
-        let mut verbose = false;
-        std::env::var("BENCHMARK_VERBOSE")
-            .map(|_| verbose = true)
-            .ok();
+/// Write a line to the verbose output of a `Bench`.
+#[cfg(feature = "std")]
+macro_rules! log {
+    ($bench:expr, $($arg:tt)*) => {
+        $bench.log(false, format_args!($($arg)*))
+    };
+}
 
-        Self {
-            iterations: 1,
-            warmup_iterations: 0,
-            min_samples: 3,
-            max_samples: 5,
-            max_rsd: 5.0,
-            verbose,
-            max_duration: None,
-        }
+/// Write an important line, such as a result, to the verbose output of a `Bench`.
+#[cfg(feature = "std")]
+macro_rules! log_info {
+    ($bench:expr, $($arg:tt)*) => {
+        $bench.log(true, format_args!($($arg)*))
+    };
+}
+
+#[cfg(feature = "std")]
+mod artifacts;
+#[cfg(feature = "std")]
+mod assertions;
+#[cfg(feature = "std")]
+mod bandwidth;
+#[cfg(feature = "std")]
+mod baseline;
+#[cfg(feature = "std")]
+mod bencher;
+#[cfg(feature = "std")]
+pub mod buffers;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "std")]
+mod calibration;
+#[cfg(feature = "std")]
+mod checks;
+#[cfg(feature = "std")]
+mod color;
+#[cfg(feature = "std")]
+mod command;
+#[cfg(feature = "std")]
+mod compare;
+#[cfg(feature = "std")]
+mod concurrent;
+#[cfg(feature = "toml")]
+mod config;
+#[cfg(feature = "std")]
+mod counters;
+#[cfg(feature = "std")]
+pub mod criterion_compat;
+#[cfg(feature = "std")]
+pub mod data;
+#[cfg(feature = "std")]
+mod dedicated;
+#[cfg(feature = "std")]
+mod diff;
+#[cfg(feature = "std")]
+mod digest;
+#[cfg(all(feature = "embedded", target_arch = "arm"))]
+pub mod embedded;
+#[cfg(feature = "std")]
+mod engine;
+#[cfg(feature = "std")]
+mod environment;
+#[cfg(feature = "std")]
+mod fallible;
+#[cfg(feature = "std")]
+mod filter;
+#[cfg(feature = "std")]
+mod harness;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "std")]
+mod isolation;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "std")]
+mod noise;
+#[cfg(feature = "std")]
+mod open_loop;
+#[cfg(feature = "std")]
+mod options;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "plot")]
+mod plot;
+#[cfg(feature = "std")]
+mod priority;
+#[cfg(feature = "std")]
+mod profiler;
+#[cfg(all(feature = "profiling", unix))]
+mod profiling;
+#[cfg(feature = "std")]
+mod progress;
+#[cfg(feature = "std")]
+mod regression;
+#[cfg(feature = "std")]
+mod report;
+#[cfg(feature = "std")]
+mod reporter;
+#[cfg(feature = "std")]
+mod rusage;
+#[cfg(feature = "std")]
+mod scope;
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "std")]
+mod stopwatch;
+#[cfg(feature = "std")]
+mod suite;
+#[cfg(feature = "std")]
+mod thermal;
+mod ticks;
+#[cfg(feature = "std")]
+mod timer;
+#[cfg(feature = "usdt")]
+mod usdt;
+#[cfg(feature = "std")]
+mod watchdog;
+#[cfg(feature = "std")]
+mod worst_case;
+
+#[cfg(feature = "object-store")]
+pub use artifacts::ObjectStoreSink;
+#[cfg(feature = "std")]
+pub use artifacts::{ArtifactSink, DirectorySink};
+#[cfg(feature = "std")]
+pub use assertions::PerformanceError;
+#[cfg(feature = "std")]
+pub use bandwidth::MemoryBandwidth;
+#[cfg(feature = "std")]
+pub use baseline::Baseline;
+#[cfg(feature = "std")]
+#[cfg(feature = "libtest")]
+pub use bencher::libtest;
+#[cfg(feature = "std")]
+pub use bencher::Bencher;
+#[cfg(feature = "std")]
+pub use cache::flush_cache;
+#[cfg(feature = "std")]
+pub use checks::{system_check, CheckResult, CheckStatus};
+#[cfg(feature = "std")]
+pub use compare::{Comparison, Ranking};
+#[cfg(feature = "toml")]
+pub use config::{ConfigError, OptionsConfig};
+#[cfg(feature = "std")]
+pub use counters::Counters;
+#[cfg(feature = "std")]
+pub use dedicated::ThreadOptions;
+#[cfg(feature = "std")]
+pub use diff::BaselineDiff;
+#[cfg(feature = "std")]
+pub use digest::{Digest, DigestChange};
+#[cfg(feature = "std")]
+pub use engine::{
+    Measurement, Sampling, SamplingEngine, SamplingState, StopReason, StoppingRule, WallClock,
+};
+#[cfg(feature = "std")]
+pub use environment::Environment;
+#[cfg(feature = "std")]
+pub use harness::{Harness, RunConfig};
+#[cfg(feature = "std")]
+pub use noise::NoiseFloor;
+#[cfg(feature = "std")]
+pub use open_loop::Latencies;
+#[cfg(feature = "std")]
+pub use options::{Options, OptionsBuilder, OptionsError};
+#[cfg(feature = "std")]
+pub use priority::Priority;
+#[cfg(feature = "std")]
+pub use profiler::Profiler;
+#[cfg(all(feature = "profiling", unix))]
+pub use profiling::Flamegraphs;
+#[cfg(feature = "std")]
+pub use regression::{Regression, RegressionError};
+#[cfg(feature = "std")]
+pub use report::{Report, ReportFormat, Summary};
+#[cfg(feature = "std")]
+pub use reporter::{PrintReporter, Reporter};
+#[cfg(feature = "std")]
+pub use rusage::ResourceUsage;
+#[cfg(feature = "std")]
+pub use scope::ScopeTimer;
+#[cfg(feature = "sqlite")]
+pub use sqlite::{ResultsStore, StoredResult};
+#[cfg(feature = "std")]
+pub use stopwatch::Stopwatch;
+#[cfg(feature = "std")]
+pub use suite::{DuplicateNames, Suite};
+pub use ticks::{TickBench, TickOptions, TickResult};
+#[cfg(feature = "std")]
+pub use worst_case::WorstCase;
+
+#[cfg(feature = "macros")]
+pub use benchmark_simple_macros::benchmark;
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    pub use inventory;
+
+    /// A benchmark registered with the `#[benchmark]` attribute.
+    pub struct Registration {
+        pub name: &'static str,
+        pub register: fn(&mut crate::Suite<'_>),
     }
+
+    inventory::collect!(Registration);
 }
 
+#[cfg(feature = "std")]
 /// A benchmark result.
+///
+/// Results are displayed in nanoseconds, microseconds, milliseconds or
+/// seconds, according to their magnitude, unless a unit is forced with
+/// [`BenchResult::with_time_unit()`]. In evcxr notebooks, they are rendered
+/// as HTML tables.
+///
+/// Results can be combined: `full - setup` subtracts the time of a setup
+/// measured separately, and `(a + b) / 2` averages two results.
 #[derive(Clone)]
 pub struct BenchResult {
     elapsed: Elapsed,
-    precision: Precision,
-    options: Rc<Options>,
+    precision: Timer,
+    options: Arc<Options>,
+    location: &'static Location<'static>,
+    sample_timed_out: bool,
+    interrupted: bool,
+    cpu_throttled: bool,
+    throttled_samples: usize,
+    near_resolution: bool,
+    rsd: Option<f64>,
+    process_rsd: Option<f64>,
+    samples: Arc<[Duration]>,
+    time_unit: TimeUnit,
+    threads: Option<usize>,
+    counters: Arc<[(String, f64)]>,
+    name: Option<Arc<str>>,
+    parameters: Arc<[(String, String)]>,
+    timestamps: Arc<[SystemTime]>,
+    resource_usage: Arc<[ResourceUsage]>,
+    stop_reason: Option<StopReason>,
 }
 
+#[cfg(feature = "std")]
 impl Add for BenchResult {
     type Output = BenchResult;
 
@@ -64,11 +279,101 @@ impl Add for BenchResult {
             elapsed: self.elapsed + other.elapsed,
             precision: self.precision,
             options: self.options,
+            location: self.location,
+            sample_timed_out: self.sample_timed_out || other.sample_timed_out,
+            interrupted: self.interrupted || other.interrupted,
+            cpu_throttled: self.cpu_throttled || other.cpu_throttled,
+            throttled_samples: self.throttled_samples + other.throttled_samples,
+            near_resolution: self.near_resolution || other.near_resolution,
+            rsd: None,
+            process_rsd: None,
+            samples: Arc::new([]),
+            time_unit: self.time_unit,
+            threads: self.threads,
+            counters: self.counters.clone(),
+            name: self.name.clone(),
+            parameters: self.parameters.clone(),
+            timestamps: Arc::new([]),
+            resource_usage: Arc::new([]),
+            stop_reason: None,
         }
     }
 }
 
+#[cfg(feature = "std")]
+/// Subtract a baseline, such as the time of the setup alone, from a result.
+/// The elapsed time saturates at zero.
+impl Sub for BenchResult {
+    type Output = BenchResult;
+
+    fn sub(self, other: BenchResult) -> Self::Output {
+        BenchResult {
+            elapsed: Elapsed::from_ticks(self.ticks().saturating_sub(other.ticks())),
+            sample_timed_out: self.sample_timed_out || other.sample_timed_out,
+            interrupted: self.interrupted || other.interrupted,
+            cpu_throttled: self.cpu_throttled || other.cpu_throttled,
+            throttled_samples: self.throttled_samples + other.throttled_samples,
+            near_resolution: self.near_resolution || other.near_resolution,
+            rsd: None,
+            process_rsd: None,
+            samples: Arc::new([]),
+            timestamps: Arc::new([]),
+            resource_usage: Arc::new([]),
+            stop_reason: None,
+            ..self
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+/// Divide a result, such as the sum of several results, by an integer.
+///
+/// # Panics
+///
+/// Panics if the divisor is zero, as integer division does. See
+/// [`BenchResult::checked_div()`] for a division that doesn't.
+impl Div<u64> for BenchResult {
+    type Output = BenchResult;
+
+    fn div(self, divisor: u64) -> Self::Output {
+        self.checked_div(divisor)
+            .expect("attempt to divide a benchmark result by zero")
+    }
+}
+
+#[cfg(feature = "std")]
 impl BenchResult {
+    /// Divide the result by an integer, returning `None` if the divisor is
+    /// zero.
+    ///
+    /// As with the `/` operator, the result has no samples.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// let bench = Bench::new();
+    /// let options = Options::builder()
+    ///     .dry_run(true)
+    ///     .build()
+    ///     .unwrap();
+    /// let res = bench.run(&options, || ());
+    /// assert!(res.clone().checked_div(2).is_some());
+    /// assert!(res.checked_div(0).is_none());
+    /// ```
+    pub fn checked_div(self, divisor: u64) -> Option<BenchResult> {
+        let ticks = self.ticks().checked_div(divisor)?;
+        Some(BenchResult {
+            elapsed: Elapsed::from_ticks(ticks),
+            rsd: None,
+            process_rsd: None,
+            samples: Arc::new([]),
+            timestamps: Arc::new([]),
+            resource_usage: Arc::new([]),
+            stop_reason: None,
+            ..self
+        })
+    }
+
     /// Returns the number of ticks.
     pub fn ticks(&self) -> u64 {
         self.elapsed.ticks()
@@ -76,75 +381,638 @@ impl BenchResult {
 
     /// Returns the elapsed time in seconds.
     pub fn as_secs(&self) -> u64 {
-        self.elapsed.as_secs(&self.precision)
+        self.precision.as_secs(self.elapsed)
     }
 
     /// Returns the elapsed time in seconds (floating point).
     pub fn as_secs_f64(&self) -> f64 {
-        self.elapsed.as_secs_f64(&self.precision)
+        self.precision.as_secs_f64(self.elapsed)
     }
 
     /// Returns the elapsed time in milliseconds.
     pub fn as_millis(&self) -> u64 {
-        self.elapsed.as_millis(&self.precision)
+        self.precision.as_millis(self.elapsed)
     }
 
     /// Returns the elapsed time in nanoseconds.
     pub fn as_ns(&self) -> u64 {
-        self.elapsed.as_ns(&self.precision)
+        self.precision.as_ns(self.elapsed)
+    }
+
+    /// Returns the elapsed time, for all the iterations of a sample.
+    pub fn as_duration(&self) -> Duration {
+        Duration::from_nanos(self.as_ns())
+    }
+
+    /// Returns the number of iterations of a sample, which differs from the
+    /// requested one with the `auto_iterations` and `sample_time` options.
+    pub fn iterations(&self) -> u64 {
+        self.options.iterations
+    }
+
+    /// Returns the elapsed time of a single iteration.
+    pub fn per_iteration_duration(&self) -> Duration {
+        Duration::from_nanos(self.as_ns() / self.options.iterations.max(1))
+    }
+
+    /// Returns the time of an iteration relative to the time of an iteration
+    /// of `other` (e.g. `2.0` if this result is twice as slow).
+    pub fn ratio(&self, other: &BenchResult) -> f64 {
+        self.secs_per_iteration() / other.secs_per_iteration()
+    }
+
+    /// Returns how many times faster this result is than `other` (e.g. `2.0`
+    /// if an iteration takes half the time). Values below 1 mean that it is
+    /// slower.
+    pub fn speedup_over(&self, other: &BenchResult) -> f64 {
+        other.secs_per_iteration() / self.secs_per_iteration()
+    }
+
+    /// Returns the probability of observing such different samples if both
+    /// results came from equally fast code (Welch's t-test on the durations
+    /// of an iteration), or `None` if either has less than two samples.
+    pub fn p_value(&self, other: &BenchResult) -> Option<f64> {
+        stats::welch_t_test(
+            &self.iteration_samples_secs(),
+            &other.iteration_samples_secs(),
+        )
+    }
+
+    /// Returns `true` if the difference with `other` is statistically
+    /// significant at the `alpha` level (e.g. `0.05`), using Welch's t-test.
+    /// Results with less than two samples are never significantly different.
+    pub fn significantly_different(&self, other: &BenchResult, alpha: f64) -> bool {
+        self.p_value(other).is_some_and(|p| p < alpha)
+    }
+
+    fn iteration_samples_secs(&self) -> Vec<f64> {
+        let iterations = self.options.iterations.max(1) as f64;
+        self.samples
+            .iter()
+            .map(|sample| sample.as_secs_f64() / iterations)
+            .collect()
+    }
+
+    fn secs_per_iteration(&self) -> f64 {
+        self.as_secs_f64() / self.options.iterations.max(1) as f64
+    }
+
+    /// Returns `true` if the measured time is too short to be distinguished
+    /// from zero by the timer. Increasing `iterations` is recommended then.
+    pub fn is_below_resolution(&self) -> bool {
+        self.ticks() == 0 || self.as_ns() == 0
+    }
+
+    /// Returns `true` if the fastest sample took less than a hundred times
+    /// the timer resolution, so that the rounding of timer readings alone
+    /// can change the result by more than 1%.
+    ///
+    /// Such results, typical of functions running in a few nanoseconds with
+    /// `iterations: 1`, are unreliable: increasing `iterations`, or enabling
+    /// `auto_iterations`, is recommended.
+    pub fn is_near_resolution(&self) -> bool {
+        self.near_resolution || self.is_below_resolution()
+    }
+
+    /// Returns `true` if a sample exceeded the `sample_timeout` option.
+    pub fn sample_timed_out(&self) -> bool {
+        self.sample_timed_out
+    }
+
+    /// Returns `true` if the benchmark was interrupted before completion.
+    pub fn interrupted(&self) -> bool {
+        self.interrupted
+    }
+
+    /// Returns `true` if a cgroup CPU quota throttled the process while the
+    /// benchmark was running.
+    pub fn cpu_throttled(&self) -> bool {
+        self.cpu_throttled
+    }
+
+    /// Returns the number of samples taken while the CPU was throttling,
+    /// with the `detect_throttling` option.
+    pub fn throttled_samples(&self) -> usize {
+        self.throttled_samples
+    }
+
+    /// Returns the page faults and context switches of every sample, in
+    /// the order of [`BenchResult::samples()`], recorded with the
+    /// `resource_usage` option.
+    ///
+    /// Empty if they were not recorded, such as without the option or on
+    /// systems other than Unix.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// let options = Options::quick()
+    ///     .into_builder()
+    ///     .resource_usage(true)
+    ///     .build()
+    ///     .unwrap();
+    /// let res = Bench::new().run(&options, || vec![0u8; 1 << 20]);
+    /// for (sample, usage) in res.samples().iter().zip(res.sample_resource_usage()) {
+    ///     println!("{:?}: {} minor faults", sample, usage.minor_faults);
+    /// }
+    /// ```
+    pub fn sample_resource_usage(&self) -> &[ResourceUsage] {
+        &self.resource_usage
+    }
+
+    /// Returns the page faults and context switches of all the samples,
+    /// if they were recorded.
+    pub fn resource_usage(&self) -> Option<ResourceUsage> {
+        self.resource_usage.iter().copied().reduce(Add::add)
+    }
+
+    /// Returns the number of samples the result was computed from, excluding
+    /// the warm-up and the discarded samples.
+    ///
+    /// Results combined with arithmetic operators have no samples.
+    pub fn samples_collected(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns why no more samples were collected, or `None` if the result
+    /// doesn't come from a sampling run, such as the result of a
+    /// [`Stopwatch`], or the sum of two results.
+    ///
+    /// Results that stopped before converging, for example because of
+    /// `max_duration`, can be flagged or run again.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// let options = Options::quick()
+    ///     .into_builder()
+    ///     .max_rsd(0.0)
+    ///     .build()
+    ///     .unwrap();
+    /// let res = Bench::new().run(&options, || (0..1000u64).sum::<u64>());
+    /// assert_eq!(res.samples_collected(), 1);
+    /// assert_eq!(res.stop_reason(), Some(StopReason::MaxSamples));
+    /// ```
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason
+    }
+
+    /// Returns the relative standard deviation of the samples (in 0...100),
+    /// if more than one sample was collected.
+    pub fn rsd(&self) -> Option<f64> {
+        self.rsd
+    }
+
+    /// Returns the relative standard deviation of the results of the child
+    /// processes (in 0...100), if the benchmark ran in several processes.
+    ///
+    /// See [`Suite::processes()`].
+    pub fn process_rsd(&self) -> Option<f64> {
+        self.process_rsd
+    }
+
+    /// Returns the number of threads of the pool the benchmark ran in, if it
+    /// ran with [`Bench::run_parallel()`].
+    pub fn threads(&self) -> Option<usize> {
+        self.threads
+    }
+
+    /// Returns the value per iteration of the counter `name`, if it was
+    /// recorded with [`Bench::run_with_counters()`].
+    pub fn counter(&self, name: &str) -> Option<f64> {
+        self.counters
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|&(_, value)| value)
+    }
+
+    /// Returns the names and values per iteration of all the counters.
+    pub fn counters(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.counters
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+    }
+
+    /// Returns the margin of error of the mean sample duration, at a 95%
+    /// confidence level and relative to the mean (in 0...100), if more than
+    /// one sample was collected.
+    ///
+    /// This tells how much a result can be trusted, for example when a
+    /// deadline didn't leave enough time to collect many samples.
+    pub fn margin_of_error(&self) -> Option<f64> {
+        self.rsd
+            .map(|rsd| 1.96 * rsd / (self.samples.len() as f64).sqrt())
+    }
+
+    /// Returns the smallest relative change (in %) that a comparison with a
+    /// result collecting as many samples, with the same variance, would tell
+    /// apart from noise at the `confidence` level (e.g. `0.95`), 80% of the
+    /// time. Returns `None` if less than two samples were collected.
+    ///
+    /// If it is 3%, chasing a 1% optimization on this machine is pointless
+    /// without collecting more samples or reducing the noise.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// let bench = Bench::new();
+    /// let options = Options::quick()
+    ///     .into_builder()
+    ///     .iterations(100)
+    ///     .min_samples(10)
+    ///     .max_samples(10)
+    ///     .build()
+    ///     .unwrap();
+    /// let res = bench.run(&options, || (0..100u64).sum::<u64>());
+    /// if let Some(mde) = res.min_detectable_difference(0.95) {
+    ///     assert!(mde >= 0.0);
+    /// }
+    /// ```
+    pub fn min_detectable_difference(&self, confidence: f64) -> Option<f64> {
+        const POWER: f64 = 0.8;
+        if self.samples.len() < 2 {
+            return None;
+        }
+        let z = stats::normal_quantile((1.0 + confidence) / 2.0) + stats::normal_quantile(POWER);
+        self.rsd
+            .map(|rsd| z * rsd * (2.0 / self.samples.len() as f64).sqrt())
+    }
+
+    /// Returns the name of the benchmark, if it was set with
+    /// [`BenchResult::with_name()`] or [`Bench::run_named()`], or if the
+    /// result was added to a [`Report`].
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Set the name of the benchmark, so that the result identifies itself
+    /// wherever it is passed to, or serialized.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into().into());
+        self
+    }
+
+    /// Returns the value of the parameter `key`, if it was set with
+    /// [`BenchResult::with_parameter()`].
+    pub fn parameter(&self, key: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns all the parameters, in the order they were set.
+    pub fn parameters(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.parameters
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Set a parameter the benchmark ran with, such as the input size,
+    /// replacing its previous value.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// let bench = Bench::new();
+    /// let data = vec![0u8; 4096];
+    /// let res = bench
+    ///     .run_named(&Options::quick(), "checksum", || data.iter().map(|&x| x as u64).sum::<u64>())
+    ///     .with_parameter("size", data.len());
+    /// assert_eq!(res.name(), Some("checksum"));
+    /// assert_eq!(res.parameter("size"), Some("4096"));
+    /// ```
+    pub fn with_parameter(mut self, key: impl Into<String>, value: impl Display) -> Self {
+        let key = key.into();
+        let mut parameters: Vec<_> = self
+            .parameters
+            .iter()
+            .filter(|(k, _)| *k != key)
+            .cloned()
+            .collect();
+        parameters.push((key, value.to_string()));
+        self.parameters = parameters.into();
+        self
+    }
+
+    /// Display the result in a specific time unit, instead of one picked
+    /// according to its magnitude.
+    pub fn with_time_unit(mut self, time_unit: TimeUnit) -> Self {
+        self.time_unit = time_unit;
+        self
+    }
+
+    /// Returns the durations of all the collected samples, in collection order.
+    pub fn samples(&self) -> &[Duration] {
+        &self.samples
+    }
+
+    /// Returns the wall-clock time at which every sample started, in the
+    /// same order as [`BenchResult::samples()`].
+    ///
+    /// Plotted against the sample durations, they reveal external
+    /// interference during long runs, such as cron jobs or thermal events.
+    /// Empty if the timestamps were not recorded, such as for the samples
+    /// of [`Bench::compare()`].
+    pub fn sample_timestamps(&self) -> &[SystemTime] {
+        &self.timestamps
+    }
+
+    /// Render the distribution of the sample durations as a text histogram,
+    /// with up to `buckets` lines.
+    ///
+    /// A multimodal distribution is a sign of an unstable environment, which
+    /// the minimum alone doesn't reveal.
+    pub fn histogram(&self, buckets: usize) -> String {
+        const WIDTH: usize = 40;
+        let samples: Vec<f64> = self.samples.iter().map(|d| d.as_secs_f64()).collect();
+        let (min, max) = match (
+            samples.iter().cloned().reduce(f64::min),
+            samples.iter().cloned().reduce(f64::max),
+        ) {
+            (Some(min), Some(max)) => (min, max),
+            _ => return String::new(),
+        };
+        let buckets = if max > min { buckets.max(1) } else { 1 };
+        let step = (max - min) / buckets as f64;
+        let mut counts = vec![0usize; buckets];
+        for &sample in &samples {
+            let bucket = if step > 0.0 {
+                ((sample - min) / step) as usize
+            } else {
+                0
+            };
+            counts[bucket.min(buckets - 1)] += 1;
+        }
+        let max_count = counts.iter().cloned().max().unwrap_or(1);
+        let mut out = String::new();
+        for (i, &count) in counts.iter().enumerate() {
+            let bar = (count * WIDTH).div_ceil(max_count);
+            out.push_str(&format!(
+                "{:>10.6}s | {:<width$} {}\n",
+                min + step * i as f64,
+                "#".repeat(bar),
+                count,
+                width = WIDTH
+            ));
+        }
+        out
+    }
+
+    /// Returns the result as an HTML table.
+    pub fn to_html_table(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<table>\n");
+        out.push_str(&format!(
+            "<tr><th>Time</th><td>{}</td></tr>\n",
+            format_secs(self.as_secs_f64())
+        ));
+        if let Some(rsd) = self.rsd {
+            out.push_str(&format!("<tr><th>RSD</th><td>{:.2}%</td></tr>\n", rsd));
+        }
+        out.push_str(&format!(
+            "<tr><th>Samples</th><td>{}</td></tr>\n<tr><th>Iterations</th><td>{}</td></tr>\n",
+            self.samples.len(),
+            self.options.iterations
+        ));
+        out.push_str(&format!(
+            "<tr><th>Source</th><td><a href=\"{}\"><code>{}</code></a></td></tr>\n</table>\n",
+            report::html_escape(&report::source_url(self.location, None)),
+            report::html_escape(&self.location.to_string())
+        ));
+        out
+    }
+
+    /// Display the result as an HTML table in an evcxr notebook.
+    pub fn evcxr_display(&self) {
+        evcxr_html(&self.to_html_table());
+    }
+
+    /// Returns the source location the benchmark was run from.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
     }
 
     /// Compute the throughput for a given volume of data.
     /// The volume is the amount of bytes processed in a single iteration.
-    pub fn throughput(self, mut volume: u128) -> Throughput {
-        volume *= self.options.iterations as u128;
-        Throughput {
-            volume: volume as f64,
-            result: self,
-            unit: Unit::None,
-        }
+    pub fn throughput(self, volume: u128) -> Throughput {
+        self.throughput_volume(Volume::items(volume))
+    }
+
+    /// Compute the throughput in elements or operations, such as lookups,
+    /// for a number of them processed in a single iteration.
+    pub fn throughput_items(self, count: u128) -> Throughput {
+        self.throughput_volume(Volume::elements(count))
     }
 
     /// Compute the throughput in bits for a given volume of data.
     /// The volume is the amount of bytes processed in a single iteration.
-    pub fn throughput_bits(self, mut volume: u128) -> Throughput {
-        volume *= self.options.iterations as u128;
-        volume *= 8;
-        Throughput {
-            volume: volume as f64,
-            result: self,
-            unit: Unit::Bits,
-        }
+    pub fn throughput_bits(self, volume: u128) -> Throughput {
+        self.throughput_volume(Volume::bits(volume.saturating_mul(8)))
     }
 
     /// Compute the throughput in bytes for a given volume of data.
     /// The volume is the amount of bytes processed in a single iteration.
-    pub fn throughput_bytes(self, mut volume: u128) -> Throughput {
-        volume *= self.options.iterations as u128;
-        volume *= 8;
+    ///
+    /// Up to version 0.1.10, every byte was counted eight times, so that
+    /// the throughputs were eight times higher than the actual ones.
+    pub fn throughput_bytes(self, volume: u128) -> Throughput {
+        self.throughput_volume(Volume::bytes(volume))
+    }
+
+    /// Compute the throughput for a volume processed in a single iteration.
+    /// The unit of the throughput is the unit of the volume.
+    pub fn throughput_volume(self, volume: Volume) -> Throughput {
+        let volume_total = volume
+            .amount
+            .saturating_mul(self.options.iterations as u128);
         Throughput {
-            volume: volume as f64,
+            volume: volume_total,
             result: self,
-            unit: Unit::Bytes,
+            unit: volume.unit,
+            binary_prefixes: false,
+            others: Vec::new(),
         }
     }
 }
 
+#[cfg(feature = "std")]
+/// Send HTML content to an evcxr notebook.
+pub(crate) fn evcxr_html(html: &str) {
+    println!("EVCXR_BEGIN_CONTENT text/html\n{}\nEVCXR_END_CONTENT", html);
+}
+
+#[cfg(feature = "std")]
+/// Format a duration with an adaptive unit and three significant digits.
+pub(crate) fn format_secs(secs: f64) -> String {
+    format_time(secs, TimeUnit::Auto, None)
+}
+
+#[cfg(feature = "std")]
+/// How the durations of results are displayed.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct TimeFormat {
+    /// Unit overriding the one of the results.
+    pub(crate) unit: Option<TimeUnit>,
+    /// Fixed number of decimals, instead of three significant digits.
+    pub(crate) decimals: Option<usize>,
+    /// Minimum width durations are right-aligned to.
+    pub(crate) width: usize,
+}
+
+#[cfg(feature = "std")]
+/// Format a duration in seconds in the given unit, with three significant
+/// digits unless a number of decimals is given.
+fn format_time(secs: f64, time_unit: TimeUnit, decimals: Option<usize>) -> String {
+    let time_unit = match time_unit {
+        TimeUnit::Auto if secs >= 1.0 => TimeUnit::Seconds,
+        TimeUnit::Auto if secs >= 1e-3 => TimeUnit::Milliseconds,
+        TimeUnit::Auto if secs >= 1e-6 => TimeUnit::Microseconds,
+        TimeUnit::Auto => TimeUnit::Nanoseconds,
+        time_unit => time_unit,
+    };
+    let (value, unit) = match time_unit {
+        TimeUnit::Seconds => (secs, "s"),
+        TimeUnit::Milliseconds => (secs * 1e3, "ms"),
+        TimeUnit::Microseconds => (secs * 1e6, "µs"),
+        _ => (secs * 1e9, "ns"),
+    };
+    let decimals = decimals.unwrap_or(if value >= 100.0 {
+        0
+    } else if value >= 10.0 {
+        1
+    } else {
+        2
+    });
+    format!("{:.*} {}", decimals, value, unit)
+}
+
+#[cfg(feature = "std")]
+impl BenchResult {
+    /// Returns the duration of the result, as displayed with `format`,
+    /// without padding. Results below the timer resolution show zero.
+    pub(crate) fn formatted_time(&self, format: TimeFormat) -> String {
+        let secs = if self.is_below_resolution() {
+            0.0
+        } else {
+            self.as_secs_f64()
+        };
+        format_time(secs, format.unit.unwrap_or(self.time_unit), format.decimals)
+    }
+
+    /// Returns the result, displayed with `format`.
+    pub(crate) fn display_with(&self, format: TimeFormat) -> impl Display + '_ {
+        FormattedResult {
+            result: self,
+            format,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+struct FormattedResult<'a> {
+    result: &'a BenchResult,
+    format: TimeFormat,
+}
+
+#[cfg(feature = "std")]
+/// The alternate form (`{:#}`) shows a compact summary with an adaptive
+/// unit, the RSD and the number of samples, such as `13.2 µs ± 1.8% (n=7)`.
 impl Display for BenchResult {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{:.2}s", self.as_secs_f64())
+        self.display_with(TimeFormat::default()).fmt(f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Display for FormattedResult<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let format = self.format;
+        let result = self.result;
+        if result.options.dry_run {
+            return write!(f, "n/a (dry run)");
+        }
+        if f.alternate() {
+            let time = format_time(
+                result.as_secs_f64(),
+                format.unit.unwrap_or(result.time_unit),
+                format.decimals,
+            );
+            write!(f, "{:>width$}", time, width = format.width)?;
+            if let Some(rsd) = result.rsd {
+                write!(f, " ± {:.1}%", rsd)?;
+            }
+            return write!(f, " (n={})", result.samples.len());
+        }
+        let time = result.formatted_time(format);
+        write!(f, "{:>width$}", time, width = format.width)?;
+        if result.is_below_resolution() {
+            return write!(f, " (below timer resolution)");
+        }
+        if result.near_resolution {
+            write!(f, " (near timer resolution)")?;
+        }
+        if result.cpu_throttled {
+            write!(f, " (CPU throttled)")?;
+        }
+        if result.throttled_samples > 0 {
+            write!(f, " ({} throttled samples)", result.throttled_samples)?;
+        }
+        if let Some(process_rsd) = result.process_rsd {
+            write!(f, " (± {:.1}% across processes)", process_rsd)?;
+        }
+        if let Some(threads) = result.threads {
+            write!(f, " ({} threads)", threads)?;
+        }
+        Ok(())
     }
 }
 
+#[cfg(feature = "std")]
+/// Results are compared by the time of a single iteration, so that results
+/// with different numbers of iterations can be compared.
+impl PartialEq for BenchResult {
+    fn eq(&self, other: &Self) -> bool {
+        self.secs_per_iteration() == other.secs_per_iteration()
+    }
+}
+
+#[cfg(feature = "std")]
+impl PartialOrd for BenchResult {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.secs_per_iteration()
+            .partial_cmp(&other.secs_per_iteration())
+    }
+}
+
+#[cfg(feature = "std")]
 impl Debug for BenchResult {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self)
     }
 }
 
+#[cfg(feature = "std")]
+/// The unit used to display a duration.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub enum TimeUnit {
+    /// Nanoseconds, microseconds, milliseconds or seconds, according to the
+    /// magnitude of the duration
+    #[default]
+    Auto,
+    /// Nanoseconds
+    Nanoseconds,
+    /// Microseconds
+    Microseconds,
+    /// Milliseconds
+    Milliseconds,
+    /// Seconds
+    Seconds,
+}
+
+#[cfg(feature = "std")]
 /// Unit
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-#[derive(Default)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
 pub enum Unit {
     /// None
     #[default]
@@ -153,230 +1021,1468 @@ pub enum Unit {
     Bytes,
     /// Bits
     Bits,
+    /// Elements or operations, such as lookups or signature verifications
+    Elements,
 }
 
+#[cfg(feature = "std")]
 impl Display for Unit {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Unit::None => write!(f, ""),
             Unit::Bytes => write!(f, "B"),
             Unit::Bits => write!(f, "b"),
+            Unit::Elements => write!(f, "ops"),
         }
     }
 }
 
+#[cfg(feature = "std")]
+/// A volume of data, with its unit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Volume {
+    amount: u128,
+    unit: Unit,
+}
+
+#[cfg(feature = "std")]
+impl Volume {
+    /// A number of items, with no specific unit.
+    pub fn items(count: u128) -> Self {
+        Volume {
+            amount: count,
+            unit: Unit::None,
+        }
+    }
+
+    /// A number of elements or operations.
+    pub fn elements(count: u128) -> Self {
+        Volume {
+            amount: count,
+            unit: Unit::Elements,
+        }
+    }
+
+    /// A number of bits.
+    pub fn bits(count: u128) -> Self {
+        Volume {
+            amount: count,
+            unit: Unit::Bits,
+        }
+    }
+
+    /// A number of bytes.
+    pub fn bytes(count: u128) -> Self {
+        Volume {
+            amount: count,
+            unit: Unit::Bytes,
+        }
+    }
+
+    /// A number of kilobytes (1000 bytes).
+    ///
+    /// As with the other multiples, the number of bytes saturates at
+    /// `u128::MAX`.
+    pub fn kb(count: u128) -> Self {
+        Self::bytes(count.saturating_mul(1000))
+    }
+
+    /// A number of megabytes (1000^2 bytes).
+    pub fn mb(count: u128) -> Self {
+        Self::bytes(count.saturating_mul(1000 * 1000))
+    }
+
+    /// A number of gigabytes (1000^3 bytes).
+    pub fn gb(count: u128) -> Self {
+        Self::bytes(count.saturating_mul(1000 * 1000 * 1000))
+    }
+
+    /// A number of kibibytes (1024 bytes).
+    pub fn kib(count: u128) -> Self {
+        Self::bytes(count.saturating_mul(1024))
+    }
+
+    /// A number of mebibytes (1024^2 bytes).
+    pub fn mib(count: u128) -> Self {
+        Self::bytes(count.saturating_mul(1024 * 1024))
+    }
+
+    /// A number of gibibytes (1024^3 bytes).
+    pub fn gib(count: u128) -> Self {
+        Self::bytes(count.saturating_mul(1024 * 1024 * 1024))
+    }
 
+    /// The amount, in the volume's unit.
+    pub fn amount(&self) -> u128 {
+        self.amount
+    }
+
+    /// The unit of the volume.
+    pub fn unit(&self) -> Unit {
+        self.unit
+    }
+}
+
+#[cfg(feature = "std")]
 /// The result of a benchmark, as a throughput.
+///
+/// Byte and bit throughputs are displayed with decimal prefixes (`MB/s`),
+/// unless [`Throughput::use_binary_prefixes()`] is set. Throughputs with the
+/// same unit can be added, or summed from an iterator, to get the aggregate
+/// throughput of several threads or streams.
 #[derive(Clone)]
 pub struct Throughput {
-    volume: f64,
+    volume: u128,
     result: BenchResult,
     unit: Unit,
+    binary_prefixes: bool,
+    /// Throughputs added to this one, such as those of other threads.
+    others: Vec<Throughput>,
+}
+
+#[cfg(feature = "std")]
+/// The sum of two throughputs, such as the throughputs of two threads.
+///
+/// Panics if the units differ.
+impl Add for Throughput {
+    type Output = Throughput;
+
+    fn add(mut self, other: Throughput) -> Self::Output {
+        assert_eq!(
+            self.unit, other.unit,
+            "throughputs with different units can't be added"
+        );
+        self.others.push(other);
+        self
+    }
 }
 
+#[cfg(feature = "std")]
+/// The sum of throughputs, such as the throughputs of several threads.
+///
+/// Panics if the iterator is empty, or if the units differ.
+impl Sum for Throughput {
+    fn sum<I: Iterator<Item = Throughput>>(iter: I) -> Self {
+        iter.reduce(|a, b| a + b)
+            .expect("the sum of no throughputs has no unit")
+    }
+}
+
+#[cfg(feature = "std")]
 impl Throughput {
+    /// Returns `true` if the underlying result is below the timer resolution.
+    /// In that case, floating point throughput values are infinite.
+    pub fn is_below_resolution(&self) -> bool {
+        self.result.is_below_resolution() || self.others.iter().any(|t| t.is_below_resolution())
+    }
+
+    /// Compute `volume * 10^9 * multiplier / (ns * divisor)`.
+    ///
+    /// The integer part and the remainder are computed separately so that
+    /// large volumes and long durations don't lose precision, falling back
+    /// to floating point arithmetic only if the numerator overflows.
+    fn rate(&self, multiplier: u128, divisor: u128) -> f64 {
+        let others: f64 = self
+            .others
+            .iter()
+            .map(|t| t.rate(multiplier, divisor))
+            .sum();
+        let ns = self.result.as_ns() as u128;
+        if ns == 0 {
+            return f64::INFINITY;
+        }
+        let den = ns * divisor;
+        others
+            + match self.volume.checked_mul(1_000_000_000 * multiplier) {
+                Some(num) => (num / den) as f64 + (num % den) as f64 / den as f64,
+                None => self.volume as f64 * (1_000_000_000 * multiplier) as f64 / den as f64,
+            }
+    }
+
+    /// Display byte and bit throughputs with binary prefixes (KiB/s, MiB/s,
+    /// GiB/s) instead of decimal ones (KB/s, MB/s, GB/s).
+    pub fn use_binary_prefixes(mut self, binary_prefixes: bool) -> Self {
+        self.binary_prefixes = binary_prefixes;
+        self
+    }
+
     /// The throughput as a floating point number.
     pub fn as_f64(&self) -> f64 {
-        self.volume * 1_000_000_000f64 / (max(1, self.result.as_ns()) as f64)
+        self.rate(1, 1)
     }
 
     /// The throughput as an integer.
+    /// Saturates if the underlying result is below the timer resolution.
     pub fn as_u128(&self) -> u128 {
-        self.volume as u128 * 1_000_000_000 / (max(1, self.result.as_ns()) as u128)
+        let others = self
+            .others
+            .iter()
+            .fold(0u128, |acc, t| acc.saturating_add(t.as_u128()));
+        let ns = self.result.as_ns() as u128;
+        if ns == 0 {
+            return u128::MAX;
+        }
+        others.saturating_add(match self.volume.checked_mul(1_000_000_000) {
+            Some(num) => num / ns,
+            None => (self.volume / ns)
+                .saturating_mul(1_000_000_000)
+                .saturating_add(self.volume % ns * 1_000_000_000 / ns),
+        })
     }
 
     /// The throughput in kibibytes.
     pub fn as_kib(&self) -> f64 {
-        self.volume * 1_000_000_000f64 / (max(1, self.result.as_ns()) as f64) / 1024.0
+        self.rate(1, 1024)
     }
 
     /// The throughput in mebibytes.
     pub fn as_mib(&self) -> f64 {
-        self.volume * 1_000_000_000f64 / (max(1, self.result.as_ns()) as f64) / (1024.0 * 1024.0)
+        self.rate(1, 1024 * 1024)
     }
 
     /// The throughput in gibibytes.
     pub fn as_gib(&self) -> f64 {
-        self.volume * 1_000_000_000f64
-            / (max(1, self.result.as_ns()) as f64)
-            / (1024.0 * 1024.0 * 1024.0)
+        self.rate(1, 1024 * 1024 * 1024)
     }
 
     /// The throughput in kilobytes.
     pub fn as_kb(&self) -> f64 {
-        self.volume * 1_000_000_000f64 / (max(1, self.result.as_ns()) as f64) / 1000.0
+        self.rate(1, 1000)
     }
 
     /// The throughput in megabytes.
     pub fn as_mb(&self) -> f64 {
-        self.volume * 1_000_000_000f64 / (max(1, self.result.as_ns()) as f64) / (1000.0 * 1000.0)
+        self.rate(1, 1000 * 1000)
     }
 
     /// The throughput in gigabytes.
     pub fn as_gb(&self) -> f64 {
-        self.volume * 1_000_000_000f64
-            / (max(1, self.result.as_ns()) as f64)
-            / (1000.0 * 1000.0 * 1000.0)
+        self.rate(1, 1000 * 1000 * 1000)
     }
 
     /// The throughput in kilobits.
     pub fn as_kb8(&self) -> f64 {
-        self.volume * 8_000_000_000f64 / (max(1, self.result.as_ns()) as f64) / 1000.0
+        self.rate(8, 1000)
     }
 
     /// The throughput in megabits.
     pub fn as_mb8(&self) -> f64 {
-        self.volume * 8_000_000_000f64 / (max(1, self.result.as_ns()) as f64) / (1000.0 * 1000.0)
+        self.rate(8, 1000 * 1000)
     }
 
     /// The throughput in gigabits.
     pub fn as_gb8(&self) -> f64 {
-        self.volume * 8_000_000_000f64
-            / (max(1, self.result.as_ns()) as f64)
-            / (1000.0 * 1000.0 * 1000.0)
+        self.rate(8, 1000 * 1000 * 1000)
     }
 }
 
+#[cfg(feature = "std")]
 impl Display for Throughput {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        match self.unit {
-            Unit::None => match self.as_u128() {
-                0..=999 => write!(f, "{:.2} /s", self.as_f64()),
-                1_000..=999_999 => write!(f, "{:.2} K/s", self.as_kb()),
-                1_000_000..=999_999_999 => write!(f, "{:.2} M/s", self.as_mb()),
-                _ => write!(f, "{:.2} G/s", self.as_gb()),
-            },
-            Unit::Bytes => match self.as_u128() {
-                0..=999 => write!(f, "{:.2} B/s", self.as_f64()),
-                1_000..=999_999 => write!(f, "{:.2} KB/s", self.as_kb()),
-                1_000_000..=999_999_999 => write!(f, "{:.2} MB/s", self.as_mb()),
-                _ => write!(f, "{:.2} GB/s", self.as_gb()),
-            },
-            Unit::Bits => match self.as_u128() {
-                0..=999 => write!(f, "{:.2} b/s", self.as_f64()),
-                1_000..=999_999 => write!(f, "{:.2} Kb/s", self.as_kb()),
-                1_000_000..=999_999_999 => write!(f, "{:.2} Mb/s", self.as_mb()),
-                _ => write!(f, "{:.2} Gb/s", self.as_gb()),
-            },
+        if self.result.options.dry_run {
+            return write!(f, "n/a (dry run)");
+        }
+        if self.is_below_resolution() {
+            return write!(f, "n/a (below timer resolution)");
         }
+        f.write_str(&format_rate(self.as_f64(), self.unit, self.binary_prefixes))
+    }
+}
+
+#[cfg(feature = "std")]
+/// Format a rate per second with an adaptive prefix.
+pub(crate) fn format_rate(rate: f64, unit: Unit, binary_prefixes: bool) -> String {
+    const KI: f64 = 1024.0;
+    const MI: f64 = 1024.0 * 1024.0;
+    const GI: f64 = 1024.0 * 1024.0 * 1024.0;
+    let (divisor, prefix) = match unit {
+        Unit::Bytes | Unit::Bits if binary_prefixes => match rate {
+            r if r < KI => (1.0, ""),
+            r if r < MI => (KI, "Ki"),
+            r if r < GI => (MI, "Mi"),
+            _ => (GI, "Gi"),
+        },
+        _ => match rate {
+            r if r < 1e3 => (1.0, ""),
+            r if r < 1e6 => (1e3, "K"),
+            r if r < 1e9 => (1e6, "M"),
+            _ => (1e9, "G"),
+        },
+    };
+    let value = rate / divisor;
+    match unit {
+        Unit::None => format!("{:.2} {}/s", value, prefix),
+        Unit::Bytes => format!("{:.2} {}B/s", value, prefix),
+        Unit::Bits => format!("{:.2} {}b/s", value, prefix),
+        Unit::Elements if prefix.is_empty() => format!("{:.2} ops/s", value),
+        Unit::Elements => format!("{:.2} {} ops/s", value, prefix),
     }
 }
 
+#[cfg(feature = "std")]
 impl Debug for Throughput {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self)
     }
 }
 
+#[cfg(feature = "std")]
+/// A writer that can be shared between threads.
+type SharedWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+
+#[cfg(feature = "std")]
+fn shared_writer(writer: impl Write + Send + 'static) -> SharedWriter {
+    Arc::new(Mutex::new(Box::new(writer)))
+}
+
+#[cfg(feature = "std")]
+/// Write a line to a shared writer, ignoring errors.
+fn write_line(writer: &SharedWriter, args: fmt::Arguments<'_>) {
+    let mut writer = match writer.lock() {
+        Ok(writer) => writer,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let _ = writer.write_fmt(args);
+    let _ = writer.write_all(b"\n");
+    let _ = writer.flush();
+}
+
+#[cfg(feature = "std")]
 /// A benchmarking environment.
 #[derive(Clone)]
 pub struct Bench {
-    precision: Precision,
+    precision: Timer,
+    #[cfg_attr(feature = "log", allow(dead_code))]
+    output: SharedWriter,
+    events: Option<SharedWriter>,
+    reporter: Option<Arc<Mutex<Box<dyn Reporter + Send>>>>,
+    scopes: scope::Scopes,
+    profilers: Vec<Arc<Mutex<Box<dyn Profiler + Send>>>>,
+    monitor: Option<Arc<watchdog::Monitor>>,
 }
 
+#[cfg(feature = "std")]
 impl Bench {
     /// Create a new benchmarking environment.
+    ///
+    /// With the `ctrlc` feature, this also installs a Ctrl-C handler: the first
+    /// interruption stops the current benchmark after its current sample, so
+    /// that partial results can be reported, the second one exits immediately.
+    ///
+    /// The timer is calibrated once per process, and shared by all environments.
+    /// Its frequency is cached in `benchmark-simple/calibration`, in the Cargo
+    /// target directory, so that later runs on the same host start right away.
+    /// The cache is invalidated when the host name, the CPU or the system
+    /// changes. The `BENCHMARK_SIMPLE_CALIBRATION_CACHE` environment variable
+    /// overrides its location, and disables it if it is empty.
+    ///
+    /// If the benchmarks were compiled without optimizations, or if CPU
+    /// frequency scaling is likely to make results noisy (a governor other than
+    /// `performance`, or turbo boost, on Linux), a warning is printed to the
+    /// standard error, once per process. Use [`Bench::try_new`] to refuse to
+    /// run unoptimized benchmarks instead.
     pub fn new() -> Self {
-        let precision = Precision::new(Default::default()).unwrap();
-        Bench { precision }
+        #[cfg(feature = "ctrlc")]
+        install_interrupt_handler();
+        static WARN: std::sync::Once = std::sync::Once::new();
+        WARN.call_once(|| {
+            if !is_optimized_build() {
+                eprintln!(
+                    "WARNING: benchmarks were compiled without optimizations, results are meaningless.\n\
+                     WARNING: use `cargo bench` or `cargo run --release`."
+                );
+            }
+            for warning in environment::frequency_scaling_warnings() {
+                eprintln!("WARNING: {}", warning);
+            }
+        });
+        Bench {
+            precision: shared_timer(),
+            output: shared_writer(io::stdout()),
+            events: None,
+            reporter: None,
+            scopes: Default::default(),
+            profilers: Vec::new(),
+            monitor: None,
+        }
     }
 
-    fn run_once<F, G>(&self, options: Rc<Options>, f: &mut F) -> BenchResult
+    /// Create a new benchmarking environment, or return an error if the
+    /// benchmarks were compiled without optimizations.
+    pub fn try_new() -> Result<Self, UnoptimizedBuild> {
+        if !is_optimized_build() {
+            return Err(UnoptimizedBuild);
+        }
+        Ok(Self::new())
+    }
+
+    /// Returns a shared benchmarking environment, created on first use.
+    ///
+    /// This is convenient for libraries running quick measurements, as it
+    /// can be used from any thread. Options are passed to each `run()` call,
+    /// so users of the shared environment don't interfere with each other.
+    pub fn global() -> &'static Bench {
+        static GLOBAL: OnceLock<Bench> = OnceLock::new();
+        GLOBAL.get_or_init(Bench::new)
+    }
+
+    /// Set where verbose output is written to. The default is the standard output.
+    ///
+    /// This has no effect with the `log` feature.
+    ///
+    /// In order to capture the output, pass a writer sharing its buffer,
+    /// such as a wrapper around an `Arc<Mutex<Vec<u8>>>`.
+    pub fn set_output(&mut self, output: impl Write + Send + 'static) {
+        self.output = shared_writer(output);
+    }
+
+    /// Emit progress events to `events`, as newline-delimited JSON objects.
+    ///
+    /// Every object has an `event` property, that can be `started`,
+    /// `sample` or `finished`, so that external tools can track progress
+    /// without parsing the verbose output.
+    pub fn set_events(&mut self, events: impl Write + Send + 'static) {
+        self.events = Some(shared_writer(events));
+    }
+
+    /// Set a reporter, called when a benchmark starts, after every sample,
+    /// and when a benchmark finishes.
+    pub fn set_reporter(&mut self, reporter: impl Reporter + Send + 'static) {
+        self.reporter = Some(Arc::new(Mutex::new(Box::new(reporter))));
+    }
+
+    /// Add a profiler, started before the first measured sample of every
+    /// benchmark and stopped after the last one.
+    ///
+    /// Profilers are started in the order they were added.
+    pub fn with_profiler(mut self, profiler: impl Profiler + Send + 'static) -> Self {
+        self.add_profiler(profiler);
+        self
+    }
+
+    pub(crate) fn add_profiler(&mut self, profiler: impl Profiler + Send + 'static) {
+        self.profilers
+            .push(Arc::new(Mutex::new(Box::new(profiler))));
+    }
+
+    /// Record a flamegraph of the measured samples of every benchmark, and
+    /// save it as `<dir>/<name>.svg`, `name` being the name of the benchmark
+    /// or, if it has none, its source location (see [`Flamegraphs`]).
+    ///
+    /// Warm-up iterations are not profiled. The profiler interrupts the
+    /// benchmark about a thousand times per second, so results are slightly
+    /// slower while it runs. Benchmarks too short to be sampled have no
+    /// flamegraph.
+    ///
+    /// Only available with the `profiling` feature, on Unix systems.
+    #[cfg(all(feature = "profiling", unix))]
+    pub fn set_flamegraphs(&mut self, dir: impl Into<std::path::PathBuf>) {
+        self.add_profiler(Flamegraphs::new(dir));
+    }
+
+    fn report<T>(&self, f: impl FnOnce(&mut dyn Reporter) -> T) -> Option<T> {
+        let reporter = self.reporter.as_ref()?;
+        let mut reporter = match reporter.lock() {
+            Ok(reporter) => reporter,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        Some(f(reporter.as_mut()))
+    }
+
+    fn event(&self, args: fmt::Arguments<'_>) {
+        if let Some(events) = &self.events {
+            write_line(events, args);
+        }
+    }
+
+    /// Write a verbose message.
+    ///
+    /// With the `log` feature, messages are sent to the `log` facade instead,
+    /// at the `info` level for results and at the `debug` level for progress.
+    #[cfg(feature = "log")]
+    pub(crate) fn log(&self, info: bool, args: fmt::Arguments<'_>) {
+        let level = if info {
+            log::Level::Info
+        } else {
+            log::Level::Debug
+        };
+        log::log!(target: "benchmark_simple", level, "{}", args);
+    }
+
+    #[cfg(not(feature = "log"))]
+    pub(crate) fn log(&self, _info: bool, args: fmt::Arguments<'_>) {
+        write_line(&self.output, args)
+    }
+
+    fn run_once<F, G>(
+        &self,
+        options: Arc<Options>,
+        location: &'static Location<'static>,
+        f: &mut F,
+    ) -> BenchResult
     where
         F: FnMut() -> G,
     {
         let iterations = options.iterations;
         let start = self.precision.now();
         for _ in 0..iterations {
-            black_box(f());
+            black_box_ref(&f());
         }
         let elapsed = self.precision.now() - start;
+        self.result(elapsed, options, location)
+    }
+
+    fn result(
+        &self,
+        elapsed: Elapsed,
+        options: Arc<Options>,
+        location: &'static Location<'static>,
+    ) -> BenchResult {
         BenchResult {
             elapsed,
             precision: self.precision.clone(),
             options,
+            location,
+            sample_timed_out: false,
+            interrupted: false,
+            cpu_throttled: false,
+            throttled_samples: 0,
+            near_resolution: false,
+            rsd: None,
+            process_rsd: None,
+            samples: Arc::new([]),
+            time_unit: TimeUnit::Auto,
+            threads: None,
+            counters: Arc::new([]),
+            name: None,
+            parameters: Arc::new([]),
+            timestamps: Arc::new([]),
+            resource_usage: Arc::new([]),
+            stop_reason: None,
         }
     }
 
+    /// Returns the time elapsed since `start`.
+    pub(crate) fn elapsed_since(&self, start: Timestamp) -> Duration {
+        Duration::from_nanos(self.precision.as_ns(self.precision.now() - start))
+    }
+
     /// Run a single test.
-    pub fn run<F, G>(&self, options: &Options, mut f: F) -> BenchResult
+    ///
+    /// The caller's source location is recorded in the result.
+    ///
+    /// Samples are also timed with the monotonic clock of the operating
+    /// system. When both disagree, because the clock jumped, such as when a
+    /// VM was suspended, the sample is re-run a few times, and then measured
+    /// with the monotonic clock, so that a clock step never ends up as an
+    /// outlier.
+    #[track_caller]
+    pub fn run<F, G>(&self, options: &Options, f: F) -> BenchResult
     where
         F: FnMut() -> G,
     {
-        let options = Rc::new(options.clone());
-        let max_samples = std::cmp::max(1, options.max_samples);
+        self.run_at(options, Location::caller(), f)
+    }
+
+    /// Run a single test, and set the name of the result.
+    #[track_caller]
+    pub fn run_named<F, G>(&self, options: &Options, name: impl Into<String>, f: F) -> BenchResult
+    where
+        F: FnMut() -> G,
+    {
+        let name = name.into();
+        self.run_named_at(options, Some(&name), Location::caller(), f)
+            .with_name(name)
+    }
+
+    /// Run a set of named tests, one after the other, and return their
+    /// results along with their names, in the same order.
+    ///
+    /// This drives benchmarks only known at runtime, such as one per
+    /// registered algorithm or one per input file.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// let bench = Bench::new();
+    /// let inputs = vec![vec![1u8; 16], vec![1u8; 1024]];
+    /// let results = bench.run_many(
+    ///     &Options::quick(),
+    ///     inputs.iter().map(|input| {
+    ///         let name = format!("sum/{}", input.len());
+    ///         (name, move || input.iter().map(|&x| x as u64).sum::<u64>())
+    ///     }),
+    /// );
+    /// assert_eq!(results[1].0, "sum/1024");
+    /// assert_eq!(results[1].1.name(), Some("sum/1024"));
+    /// ```
+    #[track_caller]
+    pub fn run_many<I, F, G>(&self, options: &Options, benches: I) -> Vec<(String, BenchResult)>
+    where
+        I: IntoIterator<Item = (String, F)>,
+        F: FnMut() -> G,
+    {
+        let location = Location::caller();
+        benches
+            .into_iter()
+            .map(|(name, f)| {
+                let result = self
+                    .run_named_at(options, Some(&name), location, f)
+                    .with_name(name.clone());
+                (name, result)
+            })
+            .collect()
+    }
+
+    /// Run a single test, passing the index of every iteration to `f`.
+    ///
+    /// A closure that only depends on captured constants can be hoisted out
+    /// of the loop by the compiler, and then only runs once per sample. The
+    /// index goes through [`black_box()`], so a computation derived from it,
+    /// such as picking one of several inputs, has to run on every iteration.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    /// use std::collections::HashSet;
+    ///
+    /// let bench = Bench::new();
+    /// let options = Options::builder()
+    ///     .iterations(100)
+    ///     .warmup_iterations(0)
+    ///     .min_samples(3)
+    ///     .max_samples(3)
+    ///     .dry_run(false)
+    ///     .build()
+    ///     .unwrap();
+    /// let inputs = [3u64, 5, 7, 11];
+    /// let mut seen = HashSet::new();
+    /// bench.run_with_varied_input(&options, |i| {
+    ///     assert!(seen.insert(i));
+    ///     inputs[i as usize % inputs.len()].pow(3)
+    /// });
+    /// // Every iteration ran the closure, with a different index.
+    /// assert_eq!(seen.len(), 300);
+    /// ```
+    #[track_caller]
+    pub fn run_with_varied_input<F, G>(&self, options: &Options, mut f: F) -> BenchResult
+    where
+        F: FnMut(u64) -> G,
+    {
+        let mut i = 0u64;
+        self.run_at(options, Location::caller(), || {
+            let index = black_box(i);
+            i = i.wrapping_add(1);
+            f(index)
+        })
+    }
+
+    pub(crate) fn run_at<F, G>(
+        &self,
+        options: &Options,
+        location: &'static Location<'static>,
+        f: F,
+    ) -> BenchResult
+    where
+        F: FnMut() -> G,
+    {
+        self.run_named_at(options, None, location, f)
+    }
+
+    /// Run a single test, `name` identifying the benchmark in profiles.
+    pub(crate) fn run_named_at<F, G>(
+        &self,
+        options: &Options,
+        name: Option<&str>,
+        location: &'static Location<'static>,
+        f: F,
+    ) -> BenchResult
+    where
+        F: FnMut() -> G,
+    {
+        self.run_cancellable_at(options, name, location, &|| false, f)
+    }
+
+    /// Run a single test, stopping as soon as possible once `cancelled()`
+    /// returns `true`.
+    pub(crate) fn run_cancellable_at<F, G>(
+        &self,
+        options: &Options,
+        name: Option<&str>,
+        location: &'static Location<'static>,
+        cancelled: &dyn Fn() -> bool,
+        mut f: F,
+    ) -> BenchResult
+    where
+        F: FnMut() -> G,
+    {
+        if options.dry_run {
+            black_box_ref(&f());
+            return self.result(Elapsed::from_ticks(0), Arc::new(options.clone()), location);
+        }
+        let mut run = Run::new(self, options, name, location, false, cancelled, &mut f);
+        let _priority = self.elevate_priority(run.options.priority);
+        let sampling = run.engine(cancelled).run_at(location, &mut f);
+        run.finish(sampling)
+    }
+
+    /// Raise the priority of the current thread until the returned guard is
+    /// dropped, warning once per process if it can't be raised.
+    fn elevate_priority(&self, priority: Priority) -> Option<priority::PriorityGuard> {
+        match priority::elevate(priority) {
+            Ok(guard) => guard,
+            Err(e) => {
+                static WARN: std::sync::Once = std::sync::Once::new();
+                WARN.call_once(|| {
+                    eprintln!("Unable to raise the scheduling priority: {}", e);
+                });
+                None
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+/// A benchmark being measured, from the calibration of its options to its
+/// result.
+///
+/// `Bench::run()` collects all the samples at once, while suites running
+/// benchmarks in rounds collect a sample of every benchmark at a time.
+struct Run<'a> {
+    bench: &'a Bench,
+    options: Arc<Options>,
+    location: &'static Location<'static>,
+    name: String,
+    json_location: String,
+    measurement: RunMeasurement<'a>,
+    hooks: RunHooks<'a>,
+    throttled_periods: Option<u64>,
+}
+
+#[cfg(feature = "std")]
+impl<'a> Run<'a> {
+    /// Calibrate the options of a benchmark of `f`. The progress bar is
+    /// hidden with `interleaved`, as other benchmarks run at the same time.
+    fn new<F, G>(
+        bench: &'a Bench,
+        options: &Options,
+        name: Option<&str>,
+        location: &'static Location<'static>,
+        interleaved: bool,
+        cancelled: &dyn Fn() -> bool,
+        f: &mut F,
+    ) -> Self
+    where
+        F: FnMut() -> G,
+    {
+        let mut options = options.clone();
+        let sample_time = match options.sample_time {
+            Some(sample_time) => Some(sample_time),
+            None if options.auto_iterations => Some(bench.min_sample_time()),
+            None => None,
+        };
+        if let Some(sample_time) = sample_time {
+            options.iterations =
+                bench.calibrate_iterations(options.iterations, sample_time, cancelled, &mut *f);
+            if options.verbose {
+                log!(bench, "Calibrated iterations: {}", options.iterations);
+            }
+        }
+        let options = Arc::new(options);
+        if let Some(monitor) = &bench.monitor {
+            monitor.set_options(&options);
+        }
         let verbose = options.verbose;
+        let json_location = json::string(&location.to_string());
+        let measurement = RunMeasurement {
+            bench,
+            json_location: json_location.clone(),
+            watchdog: match options.sample_timeout {
+                Some(timeout) if options.abort_on_sample_timeout && bench.monitor.is_none() => {
+                    Some(watchdog::Watchdog::spawn(timeout, location))
+                }
+                _ => None,
+            },
+            elapsed: Vec::with_capacity(options.max_samples),
+            verbose,
+            throttling: options
+                .detect_throttling
+                .then(|| thermal::ThrottlingDetector::new(bench.precision.clone())),
+            throttling_retries: options.throttling_retries,
+            throttled_samples: 0,
+            cache_evictor: options.cold_cache.then(cache::CacheEvictor::new),
+            record_resource_usage: options.resource_usage,
+            resource_usage: Vec::new(),
+        };
+        let hooks = RunHooks {
+            bench,
+            json_location: json_location.clone(),
+            progress: progress::Progress::new(
+                std::cmp::max(1, options.max_samples),
+                verbose || interleaved,
+            ),
+            total: 0.0,
+            verbose,
+        };
+        Run {
+            bench,
+            options,
+            location,
+            name: name.map_or_else(|| location.to_string(), str::to_string),
+            json_location,
+            measurement,
+            hooks,
+            throttled_periods: environment::cgroup::throttled_periods(),
+        }
+    }
 
+    /// Returns an engine collecting the samples of the benchmark, and
+    /// notifying the profilers and the probes.
+    fn engine<'r>(&'r mut self, cancelled: &'r dyn Fn() -> bool) -> SamplingEngine<'r> {
+        let bench = self.bench;
+        let name = &self.name;
+        #[cfg(feature = "usdt")]
+        let probe_name = std::ffi::CString::new(name.replace('\0', "")).unwrap_or_default();
+        let sampling_hook = move |event: SamplingEvent| match event {
+            SamplingEvent::Start | SamplingEvent::Stop => {
+                for profiler in &bench.profilers {
+                    let mut profiler = match profiler.lock() {
+                        Ok(profiler) => profiler,
+                        Err(poisoned) => poisoned.into_inner(),
+                    };
+                    match event {
+                        SamplingEvent::Start => profiler.start(name),
+                        _ => profiler.stop(name),
+                    }
+                }
+            }
+            #[cfg(feature = "usdt")]
+            SamplingEvent::SampleStart(sample) => usdt::sample_start(&probe_name, sample),
+            #[cfg(feature = "usdt")]
+            SamplingEvent::SampleEnd(sample, duration) => {
+                usdt::sample_end(&probe_name, sample, duration.as_nanos() as u64)
+            }
+            #[cfg(not(feature = "usdt"))]
+            SamplingEvent::SampleStart(_) | SamplingEvent::SampleEnd(..) => {}
+        };
+        let mut engine = SamplingEngine::new(&self.options)
+            .with_measurement(&mut self.measurement)
+            .with_reporter(&mut self.hooks)
+            .with_stopping_rule(move |_: &SamplingState<'_>| cancelled());
+        if !bench.profilers.is_empty() || cfg!(feature = "usdt") {
+            engine = engine.with_sampling_hook(sampling_hook);
+        }
+        engine
+    }
+
+    /// Turn the samples collected by the engine into a result.
+    fn finish(self, sampling: Sampling) -> BenchResult {
+        let bench = self.bench;
+        let options = &self.options;
+        let location = self.location;
+        let verbose = options.verbose;
+        self.hooks.progress.finish();
         if verbose {
-            println!("Starting a new benchmark.");
-            if options.warmup_iterations > 0 {
-                println!("Warming up for {} iterations.", options.warmup_iterations);
+            match sampling.stop_reason() {
+                StopReason::Interrupted => log!(bench, "Interrupted."),
+                StopReason::Reporter => log!(bench, "Stopped by the reporter."),
+                StopReason::SampleTimeout => log!(
+                    bench,
+                    "Iteration {} exceeded the sample timeout ({:?} > {:?}).",
+                    sampling.samples().len(),
+                    sampling.samples().last().unwrap(),
+                    options.sample_timeout.unwrap()
+                ),
+                StopReason::Converged => log!(bench, "Enough samples have been collected."),
+                StopReason::MaxDuration => log!(bench, "Timeout."),
+                StopReason::Rule => log!(bench, "Stopped by a rule."),
+                _ => {}
             }
         }
-        for _ in 0..options.warmup_iterations {
-            black_box(f());
+        let mut result = self
+            .measurement
+            .elapsed
+            .into_iter()
+            .skip(sampling.discarded())
+            .zip(sampling.iterations())
+            .map(|(elapsed, &n)| {
+                // Normalize samples with a jittered number of iterations.
+                let elapsed = if n != options.iterations {
+                    let ticks =
+                        elapsed.ticks() as u128 * options.iterations as u128 / n.max(1) as u128;
+                    Elapsed::from_ticks(ticks as u64)
+                } else {
+                    elapsed
+                };
+                bench.result(elapsed, options.clone(), location)
+            })
+            .min_by_key(|r| r.as_ns())
+            .unwrap();
+        result.samples = sampling.samples().into();
+        result.timestamps = sampling.timestamps().into();
+        result.resource_usage = self
+            .measurement
+            .resource_usage
+            .iter()
+            .skip(sampling.discarded())
+            .copied()
+            .collect();
+        result.stop_reason = Some(sampling.stop_reason());
+        result.sample_timed_out = sampling.stop_reason() == StopReason::SampleTimeout;
+        result.interrupted = matches!(
+            sampling.stop_reason(),
+            StopReason::Interrupted | StopReason::Reporter
+        );
+        result.rsd = sampling.rsd();
+        result.near_resolution = bench.is_near_resolution(result.as_duration());
+        result.throttled_samples = self.measurement.throttled_samples;
+        result.cpu_throttled = match (
+            self.throttled_periods,
+            environment::cgroup::throttled_periods(),
+        ) {
+            (Some(before), Some(after)) => after > before,
+            _ => false,
+        };
+        bench.event(format_args!(
+            "{{\"event\": \"finished\", \"location\": {}, \"ns\": {}, \"samples\": {}, \"rsd\": {}, \"below_resolution\": {}, \"interrupted\": {}, \"cpu_throttled\": {}, \"sample_timed_out\": {}}}",
+            self.json_location,
+            result.as_ns(),
+            result.samples.len(),
+            result.rsd.map_or_else(|| "null".to_string(), json::number),
+            result.is_below_resolution(),
+            result.interrupted,
+            result.cpu_throttled,
+            result.sample_timed_out,
+        ));
+        bench.report(|reporter| reporter.on_finish(&result));
+        if verbose {
+            log_info!(bench, "Result: {}", result);
+            if result.samples().len() > 1 {
+                log!(bench, "{}", result.histogram(10).trim_end());
+            }
+            if result.is_below_resolution() {
+                log!(
+                    bench,
+                    "Warning: the result is below the timer resolution; increase `iterations` or enable `auto_iterations`."
+                );
+            } else if result.is_near_resolution() {
+                log!(
+                    bench,
+                    "Warning: the result is close to the timer resolution; increase `iterations` or enable `auto_iterations`."
+                );
+            }
+            if result.cpu_throttled() {
+                log!(
+                    bench,
+                    "Warning: the CPU quota throttled the process during the benchmark."
+                );
+            }
+            if let Some(usage) = result.resource_usage() {
+                log!(
+                    bench,
+                    "Page faults: {} minor, {} major. Context switches: {} voluntary, {} involuntary.",
+                    usage.minor_faults,
+                    usage.major_faults,
+                    usage.voluntary_switches,
+                    usage.involuntary_switches
+                );
+            }
+            if result.throttled_samples() > 0 {
+                log!(
+                    bench,
+                    "Warning: {} samples ran while the CPU was throttling.",
+                    result.throttled_samples()
+                );
+            }
         }
-        let mut results = Vec::with_capacity(max_samples);
-        let start = self.precision.now();
-        for i in 1..=max_samples {
-            if verbose {
-                println!("Running iteration {}.", i);
+        result
+    }
+}
+
+#[cfg(feature = "std")]
+/// Measures the samples of `Bench::run()`, keeping the raw timer values.
+struct RunMeasurement<'a> {
+    bench: &'a Bench,
+    json_location: String,
+    watchdog: Option<watchdog::Watchdog>,
+    elapsed: Vec<Elapsed>,
+    verbose: bool,
+    throttling: Option<thermal::ThrottlingDetector>,
+    throttling_retries: usize,
+    throttled_samples: usize,
+    cache_evictor: Option<cache::CacheEvictor>,
+    record_resource_usage: bool,
+    resource_usage: Vec<ResourceUsage>,
+}
+
+#[cfg(feature = "std")]
+impl Measurement for RunMeasurement<'_> {
+    fn measure(&mut self, sample: &mut dyn FnMut()) -> Duration {
+        let i = self.elapsed.len() + 1;
+        if self.verbose {
+            log!(self.bench, "Running iteration {}.", i);
+        }
+        let precision = &self.bench.precision;
+        let mut retries = 0;
+        let mut clock_retries = 0;
+        let mut usage = None;
+        let elapsed = loop {
+            let throttled_before = self.throttling.as_mut().is_some_and(|t| t.probe());
+            if let Some(cache_evictor) = &mut self.cache_evictor {
+                cache_evictor.evict();
             }
-            let result = self.run_once(options.clone(), &mut f);
-            results.push(result);
-            if results.len() <= 1 {
-                if verbose {
-                    println!("Iteration {}: {}", i, results.last().unwrap());
-                }
-                continue;
+            if let Some(watchdog) = &self.watchdog {
+                watchdog.start(i);
             }
-            let mean = results.iter().map(|r| r.as_secs_f64()).sum::<f64>() / results.len() as f64;
-            let std_dev = (results
-                .iter()
-                .map(|r| (r.as_secs_f64() - mean).powi(2))
-                .sum::<f64>()
-                / (results.len() - 1) as f64)
-                .sqrt();
-            let rsd = std_dev * 100.0 / mean;
-            if verbose {
-                println!("Iteration {}: {:.2}s ± {:.2}%", i, mean, rsd);
+            // Outside of the timed region, which the lock would bias.
+            if let Some(monitor) = &self.bench.monitor {
+                monitor.start(i, precision.now());
             }
-            if i >= options.min_samples && rsd < options.max_rsd {
-                if verbose {
-                    println!("Enough samples have been collected.");
-                }
-                break;
+            let usage_before = self
+                .record_resource_usage
+                .then(ResourceUsage::now)
+                .flatten();
+            let reference = Instant::now();
+            let start = precision.now();
+            sample();
+            let mut elapsed = precision.now() - start;
+            let reference = reference.elapsed();
+            if let Some(before) = usage_before {
+                usage = ResourceUsage::now().map(|after| after.since(before));
+            }
+            if let Some(watchdog) = &self.watchdog {
+                watchdog.stop();
             }
-            if let Some(max_duration) = options.max_duration {
-                let elapsed =
-                    Duration::from_secs((self.precision.now() - start).as_secs(&self.precision));
-                if elapsed >= max_duration {
-                    if verbose {
-                        println!("Timeout.");
+            if let Some(monitor) = &self.bench.monitor {
+                monitor.stop();
+            }
+            if !is_plausible(elapsed, reference, precision) {
+                if clock_retries < CLOCK_RETRIES {
+                    clock_retries += 1;
+                    if self.verbose {
+                        log!(
+                            self.bench,
+                            "Iteration {}: the clock jumped ({} ns, expected about {} ns), running it again.",
+                            i,
+                            precision.as_ns(elapsed),
+                            reference.as_nanos()
+                        );
                     }
-                    break;
+                    continue;
+                }
+                // Trust the monotonic clock of the operating system instead.
+                elapsed = Elapsed::from_ticks(
+                    (reference.as_nanos() * precision.frequency() as u128 / 1_000_000_000) as u64,
+                );
+            }
+            let throttled_after = self.throttling.as_mut().is_some_and(|t| t.probe());
+            if !(throttled_before || throttled_after) {
+                break elapsed;
+            }
+            if retries >= self.throttling_retries {
+                if self.verbose {
+                    log!(
+                        self.bench,
+                        "Iteration {} ran while the CPU was throttling.",
+                        i
+                    );
                 }
+                self.throttled_samples += 1;
+                break elapsed;
+            }
+            retries += 1;
+            if self.verbose {
+                log!(
+                    self.bench,
+                    "Iteration {} ran while the CPU was throttling, running it again.",
+                    i
+                );
             }
+        };
+        self.elapsed.push(elapsed);
+        if let Some(monitor) = &self.bench.monitor {
+            monitor.complete(elapsed);
         }
-        let result = results.into_iter().min_by_key(|r| r.as_ns()).unwrap();
-        if verbose {
-            println!("Result: {}", result);
+        if let Some(usage) = usage {
+            self.resource_usage.push(usage);
+        }
+        self.bench.event(format_args!(
+            "{{\"event\": \"sample\", \"location\": {}, \"sample\": {}, \"ns\": {}}}",
+            self.json_location,
+            i,
+            precision.as_ns(elapsed)
+        ));
+        Duration::from_nanos(precision.as_ns(elapsed))
+    }
+}
+
+/// How many times a sample is re-run when the clock jumped while it ran.
+#[cfg(feature = "std")]
+const CLOCK_RETRIES: usize = 3;
+
+/// Returns `false` if `elapsed` can't be right, given the `reference`
+/// duration measured with the monotonic clock of the operating system around
+/// the same code: the clock went backwards, stood still during a sample that
+/// took a while, or jumped forward, such as when a VM was suspended.
+#[cfg(feature = "std")]
+fn is_plausible(elapsed: Elapsed, reference: Duration, precision: &Timer) -> bool {
+    if elapsed.ticks() > i64::MAX as u64 {
+        return false;
+    }
+    let ns = precision.as_ns(elapsed) as u128;
+    let reference_ns = reference.as_nanos();
+    if ns == 0 {
+        return reference_ns < 10_000;
+    }
+    ns <= reference_ns * 2 + 1_000_000
+}
+
+#[cfg(feature = "std")]
+/// Events, progress and verbose output of `Bench::run()`, forwarded to the
+/// reporter of the `Bench`.
+struct RunHooks<'a> {
+    bench: &'a Bench,
+    json_location: String,
+    progress: progress::Progress,
+    total: f64,
+    verbose: bool,
+}
+
+#[cfg(feature = "std")]
+impl Reporter for RunHooks<'_> {
+    fn on_start(&mut self, location: &'static Location<'static>, options: &Options) {
+        self.bench.event(format_args!(
+            "{{\"event\": \"started\", \"location\": {}}}",
+            self.json_location
+        ));
+        self.bench
+            .report(|reporter| reporter.on_start(location, options));
+        if self.verbose {
+            log!(self.bench, "Starting a new benchmark ({}).", location);
+            if options.warmup_iterations > 0 {
+                log!(
+                    self.bench,
+                    "Warming up for {} iterations.",
+                    options.warmup_iterations
+                );
+            }
+            if let Some(warmup_time) = options.warmup_time {
+                log!(self.bench, "Warming up for at least {:?}.", warmup_time);
+            }
         }
-        result
+    }
+
+    fn on_sample(
+        &mut self,
+        sample: usize,
+        duration: Duration,
+        rsd: Option<f64>,
+    ) -> ControlFlow<()> {
+        self.total += duration.as_secs_f64();
+        let mean = self.total / sample as f64;
+        self.progress.update(sample, mean, rsd);
+        if self.verbose {
+            match rsd {
+                Some(rsd) => log!(
+                    self.bench,
+                    "Iteration {}: {:.2}s ± {:.2}%",
+                    sample,
+                    mean,
+                    rsd
+                ),
+                None => log!(self.bench, "Iteration {}: {:.2}s", sample, mean),
+            }
+        }
+        self.bench
+            .report(|reporter| reporter.on_sample(sample, duration, rsd))
+            .unwrap_or(ControlFlow::Continue(()))
     }
 }
 
+#[cfg(feature = "std")]
+/// Returns the timer, calibrated once per process.
+pub(crate) fn shared_timer() -> Timer {
+    static PRECISION: OnceLock<Timer> = OnceLock::new();
+    PRECISION.get_or_init(Timer::calibrate).clone()
+}
+
+#[cfg(feature = "std")]
 impl Default for Bench {
     fn default() -> Self {
         Self::new()
     }
 }
 
+#[cfg(feature = "std")]
+/// Returns `true` if the benchmarks were compiled with optimizations.
+///
+/// This checks the optimization level of the build, and falls back to
+/// `debug_assertions` if it is unknown.
+pub fn is_optimized_build() -> bool {
+    match env!("BENCHMARK_SIMPLE_OPT_LEVEL") {
+        "" => !cfg!(debug_assertions),
+        opt_level => opt_level != "0",
+    }
+}
+
+#[cfg(feature = "std")]
+/// An error returned by [`Bench::try_new`] when the benchmarks were compiled
+/// without optimizations.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct UnoptimizedBuild;
+
+#[cfg(feature = "std")]
+impl Display for UnoptimizedBuild {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "benchmarks were compiled without optimizations; use `cargo bench` or `--release`"
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnoptimizedBuild {}
+
+#[cfg(feature = "std")]
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "std")]
+/// Returns `true` if the process received an interruption request.
+///
+/// Benchmarks stop collecting samples once this is set; callers running
+/// several benchmarks should check it to stop early and report the results
+/// collected so far.
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+#[cfg(feature = "std")]
+/// Request all running and future benchmarks to stop as soon as possible.
+pub fn interrupt() {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+}
+
+#[cfg(feature = "ctrlc")]
+fn install_interrupt_handler() {
+    static INSTALL: std::sync::Once = std::sync::Once::new();
+    INSTALL.call_once(|| {
+        // Another handler may already be installed by the application.
+        let _ = ctrlc::set_handler(|| {
+            if INTERRUPTED.swap(true, Ordering::Relaxed) {
+                std::process::exit(130);
+            }
+            eprintln!("Interrupted, finishing the current sample.");
+        });
+    });
+}
+
 /// Force the compiler to avoid optimizing away a value that is computed
 /// for benchmarking purposes, but not used afterwards.
-#[inline(never)]
+///
+/// This is `std::hint::black_box()`, which copies the value; use
+/// [`black_box_ref()`] or a [`Sink`] for large values.
+#[inline]
 pub fn black_box<T>(dummy: T) -> T {
-    let ret = unsafe { ptr::read_volatile(&dummy) };
-    mem::forget(dummy);
-    ret
+    core::hint::black_box(dummy)
+}
+
+/// Like [`black_box()`], for a value behind a reference: only the reference
+/// goes through `black_box()`, so a large value, such as an array returned
+/// by the benchmarked function, is never copied.
+#[inline]
+pub fn black_box_ref<T: ?Sized>(value: &T) -> &T {
+    black_box(value)
+}
+
+/// Consume a value, so that the computation producing it can't be optimized
+/// away, and drop it.
+///
+/// Only the address of the value is observed, whatever its size: consuming
+/// a 1 MB `Vec` or array costs the same as consuming an integer, besides
+/// dropping it. To keep the drop out of the measurement, use a [`Sink`].
+///
+/// ```rust
+/// use benchmark_simple::*;
+///
+/// for i in 0..10u8 {
+///     consume(vec![i; 1024 * 1024]);
+/// }
+/// ```
+#[inline]
+pub fn consume<T>(value: T) {
+    black_box_ref(&value);
+}
+
+/// Keeps the outputs of a benchmarked function alive, without copying them.
+///
+/// Every value given to [`Sink::consume()`] is considered used by the
+/// compiler, and replaces the previous one, which is dropped.
+///
+/// ```rust
+/// use benchmark_simple::*;
+///
+/// let mut sink = Sink::new();
+/// for i in 0..10u8 {
+///     sink.consume([i; 4096]);
+/// }
+/// assert_eq!(sink.into_inner().map(|last| last[0]), Some(9));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Sink<T> {
+    value: Option<T>,
+}
+
+impl<T> Sink<T> {
+    /// Create an empty sink.
+    pub fn new() -> Self {
+        Sink { value: None }
+    }
+
+    /// Keep `value` alive until the next call, and hide it from the optimizer.
+    #[inline]
+    pub fn consume(&mut self, value: T) {
+        black_box_ref(self.value.insert(value));
+    }
+
+    /// Returns the last value, if any.
+    pub fn into_inner(self) -> Option<T> {
+        self.value
+    }
+}
+
+/// Benchmark an expression with the default options, and print a one-line
+/// summary such as `sort(&mut v): 13.2 µs ± 1.8% (n=7)`.
+///
+/// The result is returned for further use. With the `BENCHMARK_DRY_RUN`
+/// environment variable, the expression is evaluated once and nothing is printed.
+///
+/// ```rust
+/// use benchmark_simple::quick_bench;
+///
+/// let result = quick_bench!((0..1000u64).sum::<u64>());
+/// println!("{}", result);
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! quick_bench {
+    ($e:expr) => {{
+        let options = $crate::Options::default();
+        let result = $crate::Bench::global().run(&options, || $e);
+        if !options.dry_run {
+            ::std::println!("{}: {:#}", ::std::stringify!($e), result);
+        }
+        result
+    }};
+}
+
+/// Compare two expressions with the default options, and print a one-line
+/// verdict with the speed ratio and its significance.
+///
+/// Samples of both expressions are interleaved. The comparison is returned
+/// for further use.
+///
+/// ```rust
+/// use benchmark_simple::compare_bench;
+///
+/// let comparison = compare_bench!((0..1000u64).sum::<u64>(), (0..1000u64).product::<u64>());
+/// println!("ratio: {:.2}", comparison.ratio());
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! compare_bench {
+    ($a:expr, $b:expr $(,)?) => {{
+        let comparison = $crate::Bench::global().compare(&$crate::Options::default(), || $a, || $b);
+        ::std::println!(
+            "{}",
+            comparison.named(::std::stringify!($a), ::std::stringify!($b))
+        );
+        comparison
+    }};
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    /// Returns the result of a single iteration taking about `ns`
+    /// nanoseconds, and at least one tick unless `ns` is zero.
+    fn result(ns: u64) -> BenchResult {
+        let bench = Bench::new();
+        let ticks = ns as u128 * bench.precision.frequency() as u128 / 1_000_000_000;
+        let ticks = if ns == 0 { 0 } else { ticks.max(1) };
+        let options = Options {
+            iterations: 1,
+            ..Options::default()
+        };
+        bench.result(
+            Elapsed::from_ticks(ticks as u64),
+            Arc::new(options),
+            Location::caller(),
+        )
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual / expected - 1.0).abs() < 1e-12,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn throughput_of_multi_terabyte_volumes() {
+        let res = result(1_000_000_000);
+        let ns = res.as_ns() as u128;
+        for volume in [5_000_000_000_000u128, 1 << 50, 123_456_789_012_345_678] {
+            let throughput = res.clone().throughput_bytes(volume);
+            assert_eq!(throughput.as_u128(), volume * 1_000_000_000 / ns);
+            assert_close(throughput.as_f64(), volume as f64 * 1e9 / ns as f64);
+            assert_close(throughput.as_gb(), volume as f64 / ns as f64);
+        }
+    }
+
+    #[test]
+    fn throughput_of_near_zero_durations() {
+        let res = result(1);
+        let ns = res.as_ns() as u128;
+        assert!(ns > 0);
+        let throughput = res.throughput_bytes(1000);
+        assert_eq!(throughput.as_u128(), 1000 * 1_000_000_000 / ns);
+        assert_close(throughput.as_f64(), 1000.0 * 1e9 / ns as f64);
+
+        let throughput = result(0).throughput_bytes(1000);
+        assert!(throughput.is_below_resolution());
+        assert_eq!(throughput.as_u128(), u128::MAX);
+        assert!(throughput.as_f64().is_infinite());
+    }
+
+    #[test]
+    fn throughput_of_multi_hour_durations() {
+        let res = result(3 * 3600 * 1_000_000_000);
+        let ns = res.as_ns() as u128;
+        let throughput = res.clone().throughput_items(1);
+        assert_eq!(throughput.as_u128(), 0);
+        assert_close(throughput.as_f64(), 1e9 / ns as f64);
+
+        // The remainder of the integer division isn't lost.
+        let volume = 10_000_000_000_007u128;
+        let throughput = res.throughput_bytes(volume);
+        assert_eq!(throughput.as_u128(), volume * 1_000_000_000 / ns);
+        assert_close(throughput.as_f64(), volume as f64 * 1e9 / ns as f64);
+    }
+
+    #[test]
+    fn throughput_saturates() {
+        let res = result(1);
+        let ns = res.as_ns() as u128;
+        let throughput = res.clone().throughput_volume(Volume::bytes(u128::MAX));
+        assert_eq!(throughput.as_u128(), u128::MAX);
+        assert_close(throughput.as_f64(), u128::MAX as f64 * 1e9 / ns as f64);
+        assert_eq!((throughput.clone() + throughput).as_u128(), u128::MAX);
+
+        assert_eq!(Volume::gib(u128::MAX).amount(), u128::MAX);
+        assert_eq!(Volume::kb(u128::MAX / 10).amount(), u128::MAX);
+        assert_eq!(res.throughput_bits(u128::MAX).as_u128(), u128::MAX);
+    }
 }
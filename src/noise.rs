@@ -0,0 +1,137 @@
+//! Measurement of the noise floor of the machine.
+
+use std::fmt::{self, Display, Formatter};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::{black_box_ref, format_secs, Bench, Options};
+
+/// Samples shorter than this many times the timer resolution are flagged as
+/// near the resolution.
+const RESOLUTION_WARNING_FACTOR: u32 = 100;
+
+/// How precisely the machine can measure: the timer resolution, the
+/// overhead of the benchmark loop, and the RSD of an empty benchmark.
+///
+/// Results close to these values are mostly measurement artifacts.
+#[derive(Clone, Debug)]
+pub struct NoiseFloor {
+    resolution: Duration,
+    loop_overhead_ns: f64,
+    rsd: Option<f64>,
+}
+
+impl NoiseFloor {
+    /// Returns the smallest non-zero difference between two timer readings.
+    pub fn resolution(&self) -> Duration {
+        self.resolution
+    }
+
+    /// Returns the time of an iteration of an empty closure, in nanoseconds.
+    pub fn loop_overhead_ns(&self) -> f64 {
+        self.loop_overhead_ns
+    }
+
+    /// Returns the relative standard deviation of the samples of an empty
+    /// closure (in 0...100), if more than one sample was collected.
+    pub fn rsd(&self) -> Option<f64> {
+        self.rsd
+    }
+}
+
+impl Display for NoiseFloor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Timer resolution: {}, loop overhead: {}/iteration",
+            format_secs(self.resolution.as_secs_f64()),
+            format_secs(self.loop_overhead_ns / 1e9)
+        )?;
+        if let Some(rsd) = self.rsd {
+            write!(f, ", baseline RSD: {:.1}%", rsd)?;
+        }
+        Ok(())
+    }
+}
+
+impl Bench {
+    /// Measure the noise floor: the timer resolution, and the time and RSD
+    /// of an empty closure benchmarked with `options`.
+    ///
+    /// Printing it before the results of a suite tells readers how much of
+    /// a very short result is a measurement artifact.
+    pub fn noise_floor(&self, options: &Options) -> NoiseFloor {
+        let resolution = self.timer_resolution();
+        let result = self.run(options, || ());
+        NoiseFloor {
+            resolution,
+            loop_overhead_ns: result.as_ns() as f64 / options.iterations.max(1) as f64,
+            rsd: result.rsd(),
+        }
+    }
+
+    /// Returns the smallest non-zero difference between two timer readings.
+    ///
+    /// It is measured once per process, as all the benches share the timer.
+    pub(crate) fn timer_resolution(&self) -> Duration {
+        static RESOLUTION: OnceLock<Duration> = OnceLock::new();
+        *RESOLUTION.get_or_init(|| self.measure_timer_resolution())
+    }
+
+    /// Returns `true` if a sample of this duration is too close to the
+    /// timer resolution to be measured accurately.
+    pub(crate) fn is_near_resolution(&self, sample: Duration) -> bool {
+        sample < self.timer_resolution() * RESOLUTION_WARNING_FACTOR
+    }
+
+    fn measure_timer_resolution(&self) -> Duration {
+        let ns = (0..1000)
+            .map(|_| {
+                let start = self.precision.now();
+                // Spin until the timer moves.
+                loop {
+                    let ns = self.precision.as_ns(self.precision.now() - start);
+                    if ns > 0 {
+                        break ns;
+                    }
+                }
+            })
+            .min()
+            .unwrap_or(0);
+        Duration::from_nanos(ns)
+    }
+
+    /// Returns the duration of a sample targeted by `auto_iterations`: a
+    /// thousand times the timer resolution, and at least 10 µs.
+    pub(crate) fn min_sample_time(&self) -> Duration {
+        std::cmp::max(self.timer_resolution() * 1000, Duration::from_micros(10))
+    }
+
+    /// Returns a number of iterations, starting from `iterations` and
+    /// doubling, for which running `f` takes at least `target`, or the
+    /// current number once `cancelled()` returns `true`.
+    pub(crate) fn calibrate_iterations<F, G>(
+        &self,
+        iterations: u64,
+        target: Duration,
+        cancelled: &dyn Fn() -> bool,
+        f: &mut F,
+    ) -> u64
+    where
+        F: FnMut() -> G,
+    {
+        const MAX_ITERATIONS: u64 = 1 << 40;
+        let mut iterations = iterations.max(1);
+        while iterations < MAX_ITERATIONS {
+            let start = self.precision.now();
+            for _ in 0..iterations {
+                black_box_ref(&f());
+            }
+            if self.elapsed_since(start) >= target || cancelled() {
+                break;
+            }
+            iterations *= 2;
+        }
+        iterations
+    }
+}
@@ -0,0 +1,173 @@
+//! Open-loop latency measurements, at a fixed request rate.
+
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+
+use crate::engine::warm_up;
+use crate::{black_box_ref, format_secs, is_interrupted, Bench, Options};
+
+/// Latencies measured by [`Bench::run_open_loop()`].
+#[derive(Clone, Debug)]
+pub struct Latencies {
+    sorted: Vec<Duration>,
+    target_rate: f64,
+    achieved_rate: f64,
+}
+
+impl Latencies {
+    /// Returns the number of operations.
+    pub fn len(&self) -> usize {
+        self.sorted.len()
+    }
+
+    /// Returns `true` if no operations ran.
+    pub fn is_empty(&self) -> bool {
+        self.sorted.is_empty()
+    }
+
+    /// Returns the latency below which `p` percent (in 0...100) of the
+    /// operations completed, such as `99.0` for the 99th percentile.
+    pub fn percentile(&self, p: f64) -> Duration {
+        if self.sorted.is_empty() {
+            return Duration::ZERO;
+        }
+        let rank = (p.clamp(0.0, 100.0) / 100.0 * self.sorted.len() as f64).ceil() as usize;
+        self.sorted[rank.clamp(1, self.sorted.len()) - 1]
+    }
+
+    /// Returns the mean latency.
+    pub fn mean(&self) -> Duration {
+        if self.sorted.is_empty() {
+            return Duration::ZERO;
+        }
+        self.sorted.iter().sum::<Duration>() / self.sorted.len() as u32
+    }
+
+    /// Returns the highest latency.
+    pub fn max(&self) -> Duration {
+        self.sorted.last().copied().unwrap_or_default()
+    }
+
+    /// Returns the latencies, sorted in ascending order.
+    pub fn sorted(&self) -> &[Duration] {
+        &self.sorted
+    }
+
+    /// Returns the requested rate, in operations per second.
+    pub fn target_rate(&self) -> f64 {
+        self.target_rate
+    }
+
+    /// Returns the rate at which operations completed, in operations per
+    /// second. It is below the target rate if the function couldn't keep up.
+    pub fn achieved_rate(&self) -> f64 {
+        self.achieved_rate
+    }
+}
+
+impl Display for Latencies {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "p50: {}, p99: {}, p99.9: {}, max: {} ({:.0} ops/s, target: {:.0} ops/s)",
+            format_secs(self.percentile(50.0).as_secs_f64()),
+            format_secs(self.percentile(99.0).as_secs_f64()),
+            format_secs(self.percentile(99.9).as_secs_f64()),
+            format_secs(self.max().as_secs_f64()),
+            self.achieved_rate,
+            self.target_rate
+        )
+    }
+}
+
+impl Bench {
+    /// Call `f` at a fixed rate, in operations per second, for `duration`,
+    /// and return the latency of every operation.
+    ///
+    /// Latencies are measured from the time an operation was scheduled to
+    /// start, not from the time it actually started. When an operation is
+    /// slow, the following ones start late, and that delay is part of their
+    /// latency, as it would be for the requests queued behind it in a
+    /// service. Closed-loop measurements, such as [`Bench::run()`], don't see
+    /// that delay: this is known as coordinated omission.
+    ///
+    /// The warm-up set by `warmup_iterations` and `warmup_time` runs first,
+    /// and with `dry_run`, `f` is called once.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    /// use std::time::Duration;
+    ///
+    /// let bench = Bench::new();
+    /// let latencies = bench.run_open_loop(
+    ///     &Options::default(),
+    ///     10_000.0,
+    ///     Duration::from_millis(50),
+    ///     || (0..1000u64).sum::<u64>(),
+    /// );
+    /// println!("{}", latencies);
+    /// ```
+    pub fn run_open_loop<F, G>(
+        &self,
+        options: &Options,
+        rate: f64,
+        duration: Duration,
+        mut f: F,
+    ) -> Latencies
+    where
+        F: FnMut() -> G,
+    {
+        assert!(rate > 0.0, "the rate must be positive");
+        if options.dry_run {
+            black_box_ref(&f());
+            return Latencies {
+                sorted: vec![Duration::ZERO],
+                target_rate: rate,
+                achieved_rate: rate,
+            };
+        }
+        warm_up(options, &self.precision, &mut f);
+        let count = (duration.as_secs_f64() * rate) as usize;
+        let mut latencies = Vec::with_capacity(count);
+        let start = self.precision.now();
+        for i in 0..count {
+            if is_interrupted() {
+                break;
+            }
+            let scheduled = Duration::from_secs_f64(i as f64 / rate);
+            loop {
+                let now = self.elapsed_since(start);
+                if now >= scheduled {
+                    break;
+                }
+                // Sleep if the next operation is far enough, and spin otherwise.
+                if scheduled - now > Duration::from_millis(2) {
+                    std::thread::sleep(scheduled - now - Duration::from_millis(1));
+                }
+            }
+            black_box_ref(&f());
+            latencies.push(self.elapsed_since(start).saturating_sub(scheduled));
+        }
+        let elapsed = self.elapsed_since(start).as_secs_f64();
+        let achieved_rate = if elapsed > 0.0 {
+            latencies.len() as f64 / elapsed
+        } else {
+            0.0
+        };
+        latencies.sort_unstable();
+        if options.verbose {
+            log!(
+                self,
+                "{} operations, {:.0} ops/s (target: {:.0} ops/s).",
+                latencies.len(),
+                achieved_rate,
+                rate
+            );
+        }
+        Latencies {
+            sorted: latencies,
+            target_rate: rate,
+            achieved_rate,
+        }
+    }
+}
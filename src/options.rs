@@ -0,0 +1,506 @@
+//! Benchmark options.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+
+use crate::Priority;
+
+/// Options.
+///
+/// New options may be added in minor releases. Start from
+/// `Options::default()`, a preset such as [`Options::quick()`], or
+/// [`Options::builder()`], and change the fields that matter.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Options {
+    /// Number of iterations to perform.
+    pub iterations: u64,
+    /// Number of warm-up iterations to perform.
+    pub warmup_iterations: u64,
+    /// Minimum duration of the warm-up. The warm-up runs
+    /// `warmup_iterations` iterations, and then keeps going until this
+    /// duration has elapsed.
+    pub warmup_time: Option<Duration>,
+    /// Target duration of a sample. If set, the number of iterations is
+    /// doubled, starting from `iterations`, until a sample takes at least
+    /// this long. This takes precedence over `auto_iterations`.
+    pub sample_time: Option<Duration>,
+    /// Minimum number of samples to collect.
+    pub min_samples: usize,
+    /// Maximum number of samples to collect.
+    /// Statistics are updated in constant time after every sample, so
+    /// hundreds of thousands of samples are fine.
+    pub max_samples: usize,
+    /// Maximum RSD to tolerate (in 0...100).
+    pub max_rsd: f64,
+    /// Maximum benchmark duration time.
+    pub max_duration: Option<Duration>,
+    /// Maximum duration of a single sample.
+    /// A sample exceeding it is flagged, and no more samples are collected.
+    /// With `Bench::run_monitored()` or `Suite::register_monitored()`, a
+    /// sample that doesn't complete in time is abandoned.
+    pub sample_timeout: Option<Duration>,
+    /// Exit the process if a sample of a benchmark that isn't monitored
+    /// doesn't complete within `sample_timeout`.
+    /// This requires a watchdog thread.
+    pub abort_on_sample_timeout: bool,
+    /// Verbose output
+    pub verbose: bool,
+    /// Run the function only once, without measuring it, and don't print
+    /// anything. This keeps examples and doctests fast and their output stable.
+    pub dry_run: bool,
+    /// Time a reference kernel around every sample, and flag the samples
+    /// taken while the CPU was throttling. They are re-run up to
+    /// `throttling_retries` times, or counted in
+    /// `BenchResult::throttled_samples()`.
+    pub detect_throttling: bool,
+    /// How many times a sample taken while the CPU was throttling is re-run,
+    /// when `detect_throttling` is set.
+    pub throttling_retries: usize,
+    /// Randomly vary the number of iterations of every sample by up to this
+    /// percentage (in 0...100), normalizing the durations. This averages out
+    /// periodic interference that could alias with fixed-size samples.
+    pub iteration_jitter: f64,
+    /// Scheduling priority of the benchmarks. Raising it usually requires
+    /// privileges; the priority is left unchanged, with a warning, if they
+    /// are missing. The previous priority is restored afterwards.
+    pub priority: Priority,
+    /// Evict the CPU caches before every sample, to measure cold-cache
+    /// performance. This is best combined with `iterations: 1`.
+    pub cold_cache: bool,
+    /// Raise `iterations` until a sample takes well above the timer
+    /// resolution, so that functions faster than the timer can be measured.
+    pub auto_iterations: bool,
+    /// Pause between samples, to let turbo budgets and temperatures recover.
+    /// A zero duration yields to other threads instead of sleeping. The
+    /// pause counts towards `max_duration`.
+    pub cooldown: Option<Duration>,
+    /// Number of samples to measure and then ignore, after the warm-up.
+    /// First samples are often slowed down by page faults and lazy
+    /// initialization, which the warm-up doesn't always absorb.
+    pub discard_samples: usize,
+    /// Keep collecting samples past `max_samples` while the RSD is above
+    /// `max_rsd`, until `max_duration`, or up to ten times `max_samples` if
+    /// no `max_duration` is set.
+    pub extend_samples: bool,
+    /// Also require the 95% confidence interval of the mean sample duration
+    /// to be narrower than this percentage of the mean to stop sampling.
+    /// Unlike the RSD, it narrows as more samples are collected.
+    pub max_ci_width: Option<f64>,
+    /// Record the page faults and context switches of every sample, with
+    /// `getrusage()`. This is only supported on Unix systems.
+    pub resource_usage: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::base().with_env_overrides()
+    }
+}
+
+impl Options {
+    pub(crate) fn base() -> Self {
+        Self {
+            iterations: 1,
+            warmup_iterations: 0,
+            warmup_time: None,
+            sample_time: None,
+            min_samples: 3,
+            max_samples: 5,
+            max_rsd: 5.0,
+            verbose: false,
+            max_duration: None,
+            sample_timeout: None,
+            abort_on_sample_timeout: false,
+            dry_run: false,
+            detect_throttling: false,
+            throttling_retries: 0,
+            iteration_jitter: 0.0,
+            priority: Priority::Normal,
+            cold_cache: false,
+            auto_iterations: false,
+            cooldown: None,
+            discard_samples: 0,
+            extend_samples: false,
+            max_ci_width: None,
+            resource_usage: false,
+        }
+    }
+
+    /// Options for smoke tests: a single sample, without warm-up.
+    pub fn quick() -> Self {
+        Options {
+            iterations: 1,
+            warmup_iterations: 0,
+            min_samples: 1,
+            max_samples: 1,
+            ..Self::base()
+        }
+        .with_env_overrides()
+    }
+
+    /// Options for accurate measurements: a longer warm-up, many samples,
+    /// and a tight RSD target.
+    pub fn precise() -> Self {
+        Options {
+            warmup_iterations: 10,
+            min_samples: 10,
+            max_samples: 100,
+            max_rsd: 1.0,
+            ..Self::base()
+        }
+        .with_env_overrides()
+    }
+
+    /// Options for reproducible runs, such as in CI: every benchmark
+    /// collects exactly `max_samples` samples of `iterations` iterations,
+    /// after the same warm-up.
+    ///
+    /// Sampling never stops early, nothing depends on the time budget or on
+    /// the machine, and environment variables are ignored, so two runs of
+    /// the same binary do exactly the same amount of work. Fields can still
+    /// be changed, as long as adaptive options are left disabled.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// let options = Options::deterministic()
+    ///     .into_builder()
+    ///     .iterations(100)
+    ///     .build()
+    ///     .unwrap();
+    /// let res = Bench::new().run(&options, || (0..1000u64).sum::<u64>());
+    /// assert_eq!(res.samples().len(), options.max_samples);
+    /// ```
+    pub fn deterministic() -> Self {
+        Options {
+            warmup_iterations: 10,
+            min_samples: 10,
+            max_samples: 10,
+            // No RSD is below zero: sampling never converges early.
+            max_rsd: 0.0,
+            warmup_time: None,
+            sample_time: None,
+            max_duration: None,
+            sample_timeout: None,
+            throttling_retries: 0,
+            iteration_jitter: 0.0,
+            auto_iterations: false,
+            extend_samples: false,
+            max_ci_width: None,
+            ..Self::base()
+        }
+    }
+
+    /// Override options with values from environment variables.
+    ///
+    /// `BENCHMARK_VERBOSE`, `BENCHMARK_DRY_RUN`, `BENCHMARK_ITERATIONS`, `BENCHMARK_MIN_SAMPLES`,
+    /// `BENCHMARK_MAX_SAMPLES`, `BENCHMARK_MAX_RSD` and `BENCHMARK_MAX_DURATION`
+    /// are recognized. Durations are numbers of seconds, or numbers followed by
+    /// `ns`, `us`, `ms`, `s` or `m`. This is already done by `Options::default()`.
+    pub fn with_env_overrides(mut self) -> Self {
+        if std::env::var_os("BENCHMARK_VERBOSE").is_some() {
+            self.verbose = true;
+        }
+        if std::env::var_os("BENCHMARK_DRY_RUN").is_some() {
+            self.dry_run = true;
+        }
+        if let Some(iterations) = env_override("BENCHMARK_ITERATIONS", |v| v.parse().ok()) {
+            self.iterations = iterations;
+        }
+        if let Some(min_samples) = env_override("BENCHMARK_MIN_SAMPLES", |v| v.parse().ok()) {
+            self.min_samples = min_samples;
+        }
+        if let Some(max_samples) = env_override("BENCHMARK_MAX_SAMPLES", |v| v.parse().ok()) {
+            self.max_samples = max_samples;
+        }
+        if let Some(max_rsd) = env_override("BENCHMARK_MAX_RSD", |v| v.parse().ok()) {
+            self.max_rsd = max_rsd;
+        }
+        if let Some(max_duration) = env_override("BENCHMARK_MAX_DURATION", parse_duration) {
+            self.max_duration = Some(max_duration);
+        }
+        self
+    }
+
+    /// Returns a builder to create validated options.
+    pub fn builder() -> OptionsBuilder {
+        Options::default().into_builder()
+    }
+
+    /// Returns a builder starting from these options.
+    pub fn into_builder(self) -> OptionsBuilder {
+        OptionsBuilder { options: self }
+    }
+
+    /// Returns `true` if samples with this RSD (in 0...100) are precise
+    /// enough to stop sampling: the RSD is below `max_rsd` and, if
+    /// `max_ci_width` is set, the confidence interval is narrow enough.
+    pub(crate) fn is_converged(&self, rsd: f64, samples: usize) -> bool {
+        rsd < self.max_rsd
+            && self
+                .max_ci_width
+                .is_none_or(|max_ci_width| ci_width(rsd, samples) < max_ci_width)
+    }
+
+    /// Check that the options are consistent.
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        if self.iterations == 0 {
+            return Err(OptionsError::ZeroIterations);
+        }
+        if self.max_samples == 0 {
+            return Err(OptionsError::ZeroMaxSamples);
+        }
+        if self.min_samples > self.max_samples {
+            return Err(OptionsError::MinSamplesAboveMaxSamples);
+        }
+        if self.max_rsd.is_nan() || self.max_rsd < 0.0 {
+            return Err(OptionsError::InvalidMaxRsd);
+        }
+        if self
+            .max_ci_width
+            .is_some_and(|max_ci_width| max_ci_width.is_nan() || max_ci_width <= 0.0)
+        {
+            return Err(OptionsError::InvalidMaxCiWidth);
+        }
+        if !(0.0..100.0).contains(&self.iteration_jitter) {
+            return Err(OptionsError::InvalidIterationJitter);
+        }
+        if self.abort_on_sample_timeout && self.sample_timeout.is_none() {
+            return Err(OptionsError::MissingSampleTimeout);
+        }
+        Ok(())
+    }
+}
+
+/// Returns the width of the 95% confidence interval of the mean, relative to
+/// the mean (in %), for `samples` samples with the given RSD.
+pub(crate) fn ci_width(rsd: f64, samples: usize) -> f64 {
+    2.0 * 1.96 * rsd / (samples as f64).sqrt()
+}
+
+fn env_override<T>(name: &str, parse: impl FnOnce(&str) -> Option<T>) -> Option<T> {
+    let value = std::env::var(name).ok()?;
+    let parsed = parse(value.trim());
+    if parsed.is_none() {
+        eprintln!("Ignoring invalid value for {}: [{}]", name, value);
+    }
+    parsed
+}
+
+/// Parse a duration such as `1.5`, `200ms` or `2m`. Plain numbers are seconds.
+pub(crate) fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (number, scale) = [
+        ("ns", 1e-9),
+        ("us", 1e-6),
+        ("µs", 1e-6),
+        ("ms", 1e-3),
+        ("s", 1.0),
+        ("m", 60.0),
+    ]
+    .iter()
+    .find_map(|&(suffix, scale)| value.strip_suffix(suffix).map(|number| (number, scale)))
+    .unwrap_or((value, 1.0));
+    let secs = number.trim().parse::<f64>().ok()? * scale;
+    if secs.is_finite() && secs >= 0.0 {
+        Some(Duration::from_secs_f64(secs))
+    } else {
+        None
+    }
+}
+
+/// An error returned when options are inconsistent.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OptionsError {
+    /// `iterations` is zero.
+    ZeroIterations,
+    /// `max_samples` is zero.
+    ZeroMaxSamples,
+    /// `min_samples` is larger than `max_samples`.
+    MinSamplesAboveMaxSamples,
+    /// `max_rsd` is negative or not a number.
+    InvalidMaxRsd,
+    /// `max_ci_width` is not a positive number.
+    InvalidMaxCiWidth,
+    /// `iteration_jitter` is not in 0...100.
+    InvalidIterationJitter,
+    /// `abort_on_sample_timeout` is set, but `sample_timeout` isn't.
+    MissingSampleTimeout,
+}
+
+impl Display for OptionsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionsError::ZeroIterations => write!(f, "iterations must be at least 1"),
+            OptionsError::ZeroMaxSamples => write!(f, "max_samples must be at least 1"),
+            OptionsError::MinSamplesAboveMaxSamples => {
+                write!(f, "min_samples must not be larger than max_samples")
+            }
+            OptionsError::InvalidMaxRsd => write!(f, "max_rsd must be a non-negative number"),
+            OptionsError::InvalidMaxCiWidth => write!(f, "max_ci_width must be a positive number"),
+            OptionsError::InvalidIterationJitter => {
+                write!(f, "iteration_jitter must be at least 0 and less than 100")
+            }
+            OptionsError::MissingSampleTimeout => {
+                write!(f, "abort_on_sample_timeout requires a sample_timeout")
+            }
+        }
+    }
+}
+
+impl Error for OptionsError {}
+
+/// A builder for [`Options`].
+#[derive(Clone, Debug)]
+pub struct OptionsBuilder {
+    options: Options,
+}
+
+impl OptionsBuilder {
+    /// Set the number of iterations to perform.
+    pub fn iterations(mut self, iterations: u64) -> Self {
+        self.options.iterations = iterations;
+        self
+    }
+
+    /// Set the number of warm-up iterations to perform.
+    pub fn warmup_iterations(mut self, warmup_iterations: u64) -> Self {
+        self.options.warmup_iterations = warmup_iterations;
+        self
+    }
+
+    /// Set the minimum duration of the warm-up.
+    pub fn warmup_time(mut self, warmup_time: Duration) -> Self {
+        self.options.warmup_time = Some(warmup_time);
+        self
+    }
+
+    /// Set the target duration of a sample.
+    pub fn sample_time(mut self, sample_time: Duration) -> Self {
+        self.options.sample_time = Some(sample_time);
+        self
+    }
+
+    /// Set the minimum number of samples to collect.
+    pub fn min_samples(mut self, min_samples: usize) -> Self {
+        self.options.min_samples = min_samples;
+        self
+    }
+
+    /// Set the maximum number of samples to collect.
+    pub fn max_samples(mut self, max_samples: usize) -> Self {
+        self.options.max_samples = max_samples;
+        self
+    }
+
+    /// Set the maximum RSD to tolerate (in 0...100).
+    pub fn max_rsd(mut self, max_rsd: f64) -> Self {
+        self.options.max_rsd = max_rsd;
+        self
+    }
+
+    /// Set the maximum benchmark duration time.
+    pub fn max_duration(mut self, max_duration: Duration) -> Self {
+        self.options.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Set the maximum duration of a single sample.
+    pub fn sample_timeout(mut self, sample_timeout: Duration) -> Self {
+        self.options.sample_timeout = Some(sample_timeout);
+        self
+    }
+
+    /// Exit the process if a sample doesn't complete within the sample timeout.
+    pub fn abort_on_sample_timeout(mut self, abort_on_sample_timeout: bool) -> Self {
+        self.options.abort_on_sample_timeout = abort_on_sample_timeout;
+        self
+    }
+
+    /// Enable or disable verbose output.
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.options.verbose = verbose;
+        self
+    }
+
+    /// Enable or disable dry runs, where the function runs only once.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.options.dry_run = dry_run;
+        self
+    }
+
+    /// Enable or disable the detection of samples taken while the CPU was throttling.
+    pub fn detect_throttling(mut self, detect_throttling: bool) -> Self {
+        self.options.detect_throttling = detect_throttling;
+        self
+    }
+
+    /// Set how many times a sample taken while the CPU was throttling is re-run.
+    pub fn throttling_retries(mut self, throttling_retries: usize) -> Self {
+        self.options.throttling_retries = throttling_retries;
+        self
+    }
+
+    /// Set by how much the number of iterations of every sample randomly varies (in 0...100).
+    pub fn iteration_jitter(mut self, iteration_jitter: f64) -> Self {
+        self.options.iteration_jitter = iteration_jitter;
+        self
+    }
+
+    /// Set the scheduling priority of the benchmarks.
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.options.priority = priority;
+        self
+    }
+
+    /// Enable or disable the eviction of the CPU caches before every sample.
+    pub fn cold_cache(mut self, cold_cache: bool) -> Self {
+        self.options.cold_cache = cold_cache;
+        self
+    }
+
+    /// Enable or disable the calibration of the number of iterations.
+    pub fn auto_iterations(mut self, auto_iterations: bool) -> Self {
+        self.options.auto_iterations = auto_iterations;
+        self
+    }
+
+    /// Set the pause between samples.
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.options.cooldown = Some(cooldown);
+        self
+    }
+
+    /// Set the number of initial samples to ignore.
+    pub fn discard_samples(mut self, discard_samples: usize) -> Self {
+        self.options.discard_samples = discard_samples;
+        self
+    }
+
+    /// Enable or disable collecting more samples when the RSD is too high.
+    pub fn extend_samples(mut self, extend_samples: bool) -> Self {
+        self.options.extend_samples = extend_samples;
+        self
+    }
+
+    /// Require the 95% confidence interval of the mean to be narrower than
+    /// this percentage of the mean.
+    pub fn max_ci_width(mut self, max_ci_width: f64) -> Self {
+        self.options.max_ci_width = Some(max_ci_width);
+        self
+    }
+
+    /// Record the page faults and context switches of every sample.
+    pub fn resource_usage(mut self, resource_usage: bool) -> Self {
+        self.options.resource_usage = resource_usage;
+        self
+    }
+
+    /// Validate and return the options.
+    pub fn build(self) -> Result<Options, OptionsError> {
+        self.options.validate()?;
+        Ok(self.options)
+    }
+}
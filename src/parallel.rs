@@ -0,0 +1,49 @@
+//! Benchmarks of data-parallel code, running in a Rayon thread pool.
+//!
+//! Only available with the `rayon` feature.
+
+use std::panic::Location;
+
+use crate::{Bench, BenchResult, Options};
+
+impl Bench {
+    /// Run a single test in a Rayon thread pool of `threads` threads.
+    ///
+    /// The pool is created, and its threads started, before measuring, so
+    /// that the results don't include the construction of the pool. `f` runs
+    /// inside the pool: parallel iterators and `rayon::join()` called from it
+    /// use that pool, and `rayon::current_num_threads()` returns `threads`.
+    /// The number of threads is recorded in the result (see
+    /// [`BenchResult::threads()`]).
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    /// use rayon::prelude::*;
+    ///
+    /// let bench = Bench::new();
+    /// let options = Options::builder()
+    ///     .iterations(10)
+    ///     .build()
+    ///     .unwrap();
+    /// let data: Vec<u64> = (0..100_000).collect();
+    /// let res = bench.run_parallel(&options, 4, || data.par_iter().sum::<u64>());
+    /// assert_eq!(res.threads(), Some(4));
+    /// ```
+    #[track_caller]
+    pub fn run_parallel<F, G>(&self, options: &Options, threads: usize, mut f: F) -> BenchResult
+    where
+        F: FnMut() -> G + Send,
+    {
+        let location = Location::caller();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("benchmark-{}", i))
+            .build()
+            .expect("unable to create the thread pool");
+        // Make sure that all the threads are running.
+        pool.broadcast(|_| ());
+        let mut result = pool.install(|| self.run_at(options, location, &mut f));
+        result.threads = Some(pool.current_num_threads());
+        result
+    }
+}
@@ -0,0 +1,211 @@
+//! SVG plots of reports.
+//!
+//! Only available with the `plot` feature.
+
+use std::fmt::Write;
+
+use crate::report::html_escape;
+use crate::{format_secs, Report, Volume};
+
+const WIDTH: f64 = 800.0;
+const LABEL_WIDTH: f64 = 200.0;
+const ROW_HEIGHT: f64 = 40.0;
+const AXIS_HEIGHT: f64 = 30.0;
+const PLOT_HEIGHT: f64 = 400.0;
+const DENSITY_POINTS: usize = 64;
+
+impl Report {
+    /// Returns an SVG violin plot of the sample durations of every benchmark.
+    ///
+    /// All benchmarks share the same time axis, so that both their speed
+    /// and their stability can be compared at a glance.
+    pub fn distribution_svg(&self) -> String {
+        let rows: Vec<(&str, Vec<f64>)> = self
+            .sorted()
+            .into_iter()
+            .map(|(name, result)| {
+                let samples = result.samples().iter().map(|d| d.as_secs_f64()).collect();
+                (name, samples)
+            })
+            .collect();
+        let all = rows.iter().flat_map(|(_, samples)| samples.iter().cloned());
+        let (min, max) = bounds(all);
+        let height = rows.len() as f64 * ROW_HEIGHT + AXIS_HEIGHT;
+        let x = |secs: f64| scale(secs, min, max, LABEL_WIDTH, WIDTH - 10.0);
+
+        let mut svg = header(height);
+        for (i, (name, samples)) in rows.iter().enumerate() {
+            let center = i as f64 * ROW_HEIGHT + ROW_HEIGHT / 2.0;
+            let _ = writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="end" dominant-baseline="middle">{}</text>"#,
+                LABEL_WIDTH - 10.0,
+                center,
+                html_escape(name)
+            );
+            if samples.is_empty() {
+                continue;
+            }
+            let (row_min, row_max) = bounds(samples.iter().cloned());
+            if row_max > row_min {
+                let densities = density(samples, row_min, row_max);
+                let peak = densities.iter().cloned().fold(0.0, f64::max);
+                let half = ROW_HEIGHT * 0.4;
+                let mut path = String::new();
+                for (j, &d) in densities.iter().enumerate() {
+                    let secs =
+                        row_min + (row_max - row_min) * j as f64 / (DENSITY_POINTS - 1) as f64;
+                    let op = if j == 0 { 'M' } else { 'L' };
+                    let _ = write!(
+                        path,
+                        "{}{:.1},{:.1} ",
+                        op,
+                        x(secs),
+                        center - half * d / peak
+                    );
+                }
+                for (j, &d) in densities.iter().enumerate().rev() {
+                    let secs =
+                        row_min + (row_max - row_min) * j as f64 / (DENSITY_POINTS - 1) as f64;
+                    let _ = write!(path, "L{:.1},{:.1} ", x(secs), center + half * d / peak);
+                }
+                let _ = writeln!(
+                    svg,
+                    r##"<path d="{}Z" fill="#8fb8de" stroke="#3a6ea5"/>"##,
+                    path
+                );
+            }
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{0:.1}" x2="{0:.1}" y1="{1:.1}" y2="{2:.1}" stroke="black"/>"#,
+                x(row_min),
+                center - ROW_HEIGHT * 0.4,
+                center + ROW_HEIGHT * 0.4
+            );
+        }
+        let axis = rows.len() as f64 * ROW_HEIGHT;
+        for secs in [min, (min + max) / 2.0, max] {
+            let _ = writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+                x(secs),
+                axis + AXIS_HEIGHT / 2.0,
+                format_secs(secs)
+            );
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Returns an SVG line plot of the throughput as a function of the volume
+    /// processed per iteration.
+    ///
+    /// `volume` returns the volume for a benchmark name, or `None` to leave
+    /// that benchmark out, for example `|name| name.parse().ok().map(Volume::bytes)`
+    /// for benchmarks named after their input size. The volume axis is logarithmic,
+    /// which makes cache size boundaries stand out.
+    pub fn throughput_svg(&self, volume: impl Fn(&str) -> Option<Volume>) -> String {
+        let mut points: Vec<(Volume, f64)> = self
+            .iter()
+            .filter_map(|(name, result)| {
+                let volume = volume(name)?;
+                let throughput = result.clone().throughput_volume(volume);
+                if throughput.is_below_resolution() || volume.amount() == 0 {
+                    return None;
+                }
+                Some((volume, throughput.as_f64()))
+            })
+            .collect();
+        points.sort_by_key(|&(volume, _)| volume.amount());
+        let height = PLOT_HEIGHT + AXIS_HEIGHT;
+        let mut svg = header(height);
+        let (min, max) = bounds(points.iter().map(|&(v, _)| (v.amount() as f64).log2()));
+        let (_, peak) = bounds(points.iter().map(|&(_, rate)| rate));
+        let x = |amount: u128| scale((amount as f64).log2(), min, max, LABEL_WIDTH, WIDTH - 10.0);
+        let y = |rate: f64| scale(rate, 0.0, peak, PLOT_HEIGHT, 10.0);
+
+        let line: Vec<String> = points
+            .iter()
+            .map(|&(v, rate)| format!("{:.1},{:.1}", x(v.amount()), y(rate)))
+            .collect();
+        let _ = writeln!(
+            svg,
+            r##"<polyline points="{}" fill="none" stroke="#3a6ea5"/>"##,
+            line.join(" ")
+        );
+        for &(v, rate) in &points {
+            let _ = writeln!(
+                svg,
+                r##"<circle cx="{:.1}" cy="{:.1}" r="3" fill="#3a6ea5"/>"##,
+                x(v.amount()),
+                y(rate)
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}{}</text>"#,
+                x(v.amount()),
+                PLOT_HEIGHT + AXIS_HEIGHT / 2.0,
+                v.amount(),
+                v.unit()
+            );
+        }
+        if let Some(&(v, _)) = points.first() {
+            for rate in [0.0, peak / 2.0, peak] {
+                let _ = writeln!(
+                    svg,
+                    r#"<text x="{:.1}" y="{:.1}" text-anchor="end" dominant-baseline="middle">{:.3e} {}/s</text>"#,
+                    LABEL_WIDTH - 10.0,
+                    y(rate),
+                    rate,
+                    v.unit()
+                );
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+fn header(height: f64) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" font-family=\"sans-serif\" font-size=\"12\">\n",
+        WIDTH, height
+    )
+}
+
+/// Returns the minimum and maximum values, or `(0, 1)` without any values.
+fn bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values
+        .fold(None, |acc: Option<(f64, f64)>, v| match acc {
+            Some((min, max)) => Some((min.min(v), max.max(v))),
+            None => Some((v, v)),
+        })
+        .unwrap_or((0.0, 1.0))
+}
+
+/// Map `value` from `[min, max]` to `[from, to]`.
+fn scale(value: f64, min: f64, max: f64, from: f64, to: f64) -> f64 {
+    if max > min {
+        from + (value - min) / (max - min) * (to - from)
+    } else {
+        (from + to) / 2.0
+    }
+}
+
+/// Gaussian kernel density estimate of `samples` over `[min, max]`.
+fn density(samples: &[f64], min: f64, max: f64) -> Vec<f64> {
+    let n = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    let std_dev = (samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n).sqrt();
+    // Silverman's rule of thumb, with a floor for identical samples.
+    let bandwidth = (1.06 * std_dev * n.powf(-0.2)).max((max - min) / DENSITY_POINTS as f64);
+    (0..DENSITY_POINTS)
+        .map(|j| {
+            let at = min + (max - min) * j as f64 / (DENSITY_POINTS - 1) as f64;
+            samples
+                .iter()
+                .map(|x| (-0.5 * ((at - x) / bandwidth).powi(2)).exp())
+                .sum::<f64>()
+        })
+        .collect()
+}
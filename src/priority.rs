@@ -0,0 +1,169 @@
+//! Scheduling priority elevation while benchmarks run.
+
+use std::io;
+
+/// Scheduling priority of the thread running a benchmark.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Priority {
+    /// Don't change the priority.
+    #[default]
+    Normal,
+    /// A high priority: a nice value of -10 on Unix systems, and the high
+    /// priority class on Windows.
+    High,
+    /// A real-time priority: `SCHED_FIFO` on Linux, and the real-time
+    /// priority class on Windows. Other systems use `High` instead.
+    ///
+    /// A benchmark that never yields can starve the rest of the system.
+    Realtime,
+}
+
+/// Restores the previous priority when dropped.
+pub(crate) struct PriorityGuard {
+    restore: Restore,
+}
+
+impl Drop for PriorityGuard {
+    fn drop(&mut self) {
+        self.restore.restore();
+    }
+}
+
+/// Raise the priority of the current thread until the returned guard is
+/// dropped.
+///
+/// Raising the priority usually requires privileges; an error is returned
+/// if they are missing, or if the platform isn't supported.
+pub(crate) fn elevate(priority: Priority) -> io::Result<Option<PriorityGuard>> {
+    let restore = match priority {
+        Priority::Normal => return Ok(None),
+        Priority::High => imp::high()?,
+        Priority::Realtime => match imp::realtime() {
+            Ok(restore) => restore,
+            Err(_) => imp::high()?,
+        },
+    };
+    Ok(Some(PriorityGuard { restore }))
+}
+
+use imp::Restore;
+
+#[cfg(unix)]
+mod imp {
+    use std::io;
+
+    pub(crate) enum Restore {
+        Nice(libc::c_int),
+        #[cfg(target_os = "linux")]
+        Scheduler(libc::c_int, libc::sched_param),
+    }
+
+    impl Restore {
+        pub(crate) fn restore(&self) {
+            // Lowering the priority back doesn't require privileges.
+            match self {
+                Restore::Nice(nice) => unsafe {
+                    libc::setpriority(libc::PRIO_PROCESS, 0, *nice);
+                },
+                #[cfg(target_os = "linux")]
+                Restore::Scheduler(policy, param) => unsafe {
+                    libc::sched_setscheduler(0, *policy, param);
+                },
+            }
+        }
+    }
+
+    pub(crate) fn high() -> io::Result<Restore> {
+        let nice = unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) };
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, -10) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Restore::Nice(nice))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn realtime() -> io::Result<Restore> {
+        let policy = unsafe { libc::sched_getscheduler(0) };
+        let mut previous = libc::sched_param { sched_priority: 0 };
+        if policy < 0 || unsafe { libc::sched_getparam(0, &mut previous) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let param = libc::sched_param {
+            sched_priority: unsafe { libc::sched_get_priority_min(libc::SCHED_FIFO) },
+        };
+        if unsafe { libc::sched_setscheduler(0, libc::SCHED_FIFO, &param) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Restore::Scheduler(policy, previous))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn realtime() -> io::Result<Restore> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::io;
+
+    type Handle = *mut std::ffi::c_void;
+
+    const HIGH_PRIORITY_CLASS: u32 = 0x80;
+    const REALTIME_PRIORITY_CLASS: u32 = 0x100;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentProcess() -> Handle;
+        fn GetPriorityClass(process: Handle) -> u32;
+        fn SetPriorityClass(process: Handle, class: u32) -> i32;
+    }
+
+    pub(crate) struct Restore(u32);
+
+    impl Restore {
+        pub(crate) fn restore(&self) {
+            unsafe {
+                SetPriorityClass(GetCurrentProcess(), self.0);
+            }
+        }
+    }
+
+    fn set(class: u32) -> io::Result<Restore> {
+        let process = unsafe { GetCurrentProcess() };
+        let previous = unsafe { GetPriorityClass(process) };
+        if previous == 0 || unsafe { SetPriorityClass(process, class) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Restore(previous))
+    }
+
+    pub(crate) fn high() -> io::Result<Restore> {
+        set(HIGH_PRIORITY_CLASS)
+    }
+
+    pub(crate) fn realtime() -> io::Result<Restore> {
+        set(REALTIME_PRIORITY_CLASS)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod imp {
+    use std::io;
+
+    pub(crate) enum Restore {}
+
+    impl Restore {
+        pub(crate) fn restore(&self) {
+            match *self {}
+        }
+    }
+
+    pub(crate) fn high() -> io::Result<Restore> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub(crate) fn realtime() -> io::Result<Restore> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
@@ -0,0 +1,34 @@
+//! Hooks to attach external profilers to the measured samples.
+
+/// Called around the measured samples of every benchmark, excluding the
+/// warm-up, to attach a profiler such as `perf`, VTune, Instruments
+/// signposts or custom tracing.
+///
+/// `bench_name` is the name of the benchmark or, if it has none, its source
+/// location. See [`Bench::with_profiler()`](crate::Bench::with_profiler).
+///
+/// ```rust
+/// use benchmark_simple::*;
+///
+/// struct Trace;
+///
+/// impl Profiler for Trace {
+///     fn start(&mut self, bench_name: &str) {
+///         eprintln!("Profiling {}", bench_name);
+///     }
+///
+///     fn stop(&mut self, bench_name: &str) {
+///         eprintln!("Done profiling {}", bench_name);
+///     }
+/// }
+///
+/// let bench = Bench::new().with_profiler(Trace);
+/// bench.run_named(&Options::quick(), "sum", || (0..1000u64).sum::<u64>());
+/// ```
+pub trait Profiler {
+    /// Called before the first measured sample of a benchmark.
+    fn start(&mut self, bench_name: &str);
+
+    /// Called after the last measured sample of a benchmark.
+    fn stop(&mut self, bench_name: &str);
+}
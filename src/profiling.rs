@@ -0,0 +1,83 @@
+//! Flamegraphs of the measured samples, recorded with a sampling profiler.
+//!
+//! Only available with the `profiling` feature, on Unix systems.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use pprof::{ProfilerGuard, ProfilerGuardBuilder};
+
+use crate::Profiler;
+
+/// Profiler sampling frequency, in Hz.
+const FREQUENCY: i32 = 997;
+
+/// A [`Profiler`] saving a flamegraph of every benchmark as
+/// `<dir>/<name>.svg`.
+///
+/// Only available with the `profiling` feature, on Unix systems.
+pub struct Flamegraphs {
+    dir: PathBuf,
+    guard: Option<ProfilerGuard<'static>>,
+}
+
+impl Flamegraphs {
+    /// Save flamegraphs in `dir`, which is created if needed.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Flamegraphs {
+            dir: dir.into(),
+            guard: None,
+        }
+    }
+
+    fn path(&self, bench_name: &str) -> PathBuf {
+        let file_name: String = bench_name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || "-_.".contains(c) {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.svg", file_name))
+    }
+}
+
+impl Profiler for Flamegraphs {
+    fn start(&mut self, _bench_name: &str) {
+        match ProfilerGuardBuilder::default()
+            .frequency(FREQUENCY)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()
+        {
+            Ok(guard) => self.guard = Some(guard),
+            Err(e) => eprintln!("Unable to start the profiler: {}", e),
+        }
+    }
+
+    fn stop(&mut self, bench_name: &str) {
+        let guard = match self.guard.take() {
+            Some(guard) => guard,
+            None => return,
+        };
+        let path = self.path(bench_name);
+        if let Err(e) = save(&guard, &path) {
+            eprintln!("Unable to save the flamegraph [{}]: {}", path.display(), e);
+        }
+    }
+}
+
+fn save(guard: &ProfilerGuard<'_>, path: &Path) -> io::Result<()> {
+    let report = guard.report().build().map_err(io::Error::other)?;
+    // Too short to be sampled.
+    if report.data.is_empty() {
+        return Ok(());
+    }
+    let mut svg = Vec::new();
+    report.flamegraph(&mut svg).map_err(io::Error::other)?;
+    fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+    fs::write(path, svg)
+}
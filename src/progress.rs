@@ -0,0 +1,56 @@
+//! Progress reporting while samples are collected.
+//!
+//! With the `indicatif` feature, a progress bar showing the current sample,
+//! the running mean and the RSD is drawn on the standard error. Without it,
+//! all the methods are no-ops.
+
+pub(crate) struct Progress {
+    #[cfg(feature = "indicatif")]
+    bar: indicatif::ProgressBar,
+}
+
+impl Progress {
+    /// Create a progress bar for up to `max_samples` samples.
+    ///
+    /// The bar is hidden with `hidden`, such as in verbose mode, where it
+    /// would be interleaved with the messages, and when the standard error is
+    /// not a terminal.
+    #[cfg_attr(not(feature = "indicatif"), allow(unused_variables))]
+    pub(crate) fn new(max_samples: usize, hidden: bool) -> Self {
+        #[cfg(feature = "indicatif")]
+        {
+            let bar = if hidden {
+                indicatif::ProgressBar::hidden()
+            } else {
+                indicatif::ProgressBar::new(max_samples as u64)
+            };
+            if let Ok(style) = indicatif::ProgressStyle::with_template(
+                "{spinner} sample {pos}/{len} [{elapsed_precise}] {msg}",
+            ) {
+                bar.set_style(style);
+            }
+            Progress { bar }
+        }
+        #[cfg(not(feature = "indicatif"))]
+        Progress {}
+    }
+
+    /// Record that sample `sample` completed, with the current statistics.
+    #[cfg_attr(not(feature = "indicatif"), allow(unused_variables))]
+    pub(crate) fn update(&self, sample: usize, mean: f64, rsd: Option<f64>) {
+        #[cfg(feature = "indicatif")]
+        {
+            self.bar.set_position(sample as u64);
+            self.bar.set_message(match rsd {
+                Some(rsd) => format!("{:.2}s ± {:.2}%", mean, rsd),
+                None => format!("{:.2}s", mean),
+            });
+        }
+    }
+
+    /// Remove the progress bar.
+    pub(crate) fn finish(&self) {
+        #[cfg(feature = "indicatif")]
+        self.bar.finish_and_clear();
+    }
+}
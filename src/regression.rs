@@ -0,0 +1,81 @@
+//! Detection of performance regressions against a baseline.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use crate::report::change;
+use crate::{Baseline, Report};
+
+/// A benchmark slower than its baseline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Regression {
+    /// Name of the benchmark.
+    pub name: String,
+    /// Relative change compared to the baseline (in %).
+    pub change: f64,
+    /// Largest change that was tolerated (in %): the threshold, plus the
+    /// margin of error of the result.
+    pub tolerance: f64,
+}
+
+/// An error returned when benchmarks regressed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegressionError {
+    regressions: Vec<Regression>,
+}
+
+impl RegressionError {
+    /// Returns the benchmarks that regressed.
+    pub fn regressions(&self) -> &[Regression] {
+        &self.regressions
+    }
+}
+
+impl Display for RegressionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} benchmark(s) regressed:", self.regressions.len())?;
+        for regression in &self.regressions {
+            write!(
+                f,
+                "\n  {}: {:+.2}% (tolerance: {:.2}%)",
+                regression.name, regression.change, regression.tolerance
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for RegressionError {}
+
+impl Report {
+    /// Check that no benchmark is slower than the baseline by more than
+    /// `threshold_pct` percent.
+    ///
+    /// The margin of error of every result is added to the threshold, so
+    /// that noisy results don't fail the check on their own. Benchmarks
+    /// missing from the baseline are ignored.
+    pub fn assert_no_regression(
+        &self,
+        baseline: &Baseline,
+        threshold_pct: f64,
+    ) -> Result<(), RegressionError> {
+        let regressions: Vec<_> = self
+            .sorted()
+            .into_iter()
+            .filter_map(|(name, result)| {
+                let change = change(baseline, name, result)?;
+                let tolerance = threshold_pct + result.margin_of_error().unwrap_or(0.0);
+                (change > tolerance).then(|| Regression {
+                    name: name.to_string(),
+                    change,
+                    tolerance,
+                })
+            })
+            .collect();
+        if regressions.is_empty() {
+            Ok(())
+        } else {
+            Err(RegressionError { regressions })
+        }
+    }
+}
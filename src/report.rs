@@ -0,0 +1,919 @@
+//! Results of a benchmark suite.
+
+use std::fmt::{self, Display, Formatter};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::panic::Location;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::color::{self, paint, Color};
+use crate::{format_secs, json, Baseline, BenchResult, Environment, TimeFormat, TimeUnit};
+
+/// The results of a suite of benchmarks, in execution order.
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    results: Vec<(String, BenchResult)>,
+    failures: Vec<(String, String)>,
+    environment: Environment,
+    pub(crate) wall_time: Option<Duration>,
+    source_url: Option<String>,
+}
+
+impl Report {
+    pub(crate) fn new(environment: Environment) -> Self {
+        Report {
+            results: Vec::new(),
+            failures: Vec::new(),
+            environment,
+            wall_time: None,
+            source_url: None,
+        }
+    }
+
+    /// Link the source of the benchmarks in Markdown and HTML reports to
+    /// `template`, where `{file}` and `{line}` are replaced with the location
+    /// of each benchmark, such as
+    /// `https://git.example.com/project/src/{file}#L{line}`.
+    ///
+    /// By default, sources are linked on GitHub in GitHub Actions runs, and
+    /// to the file, relative to the workspace, otherwise.
+    pub fn set_source_url(&mut self, template: impl Into<String>) -> &mut Self {
+        self.source_url = Some(template.into());
+        self
+    }
+
+    /// Returns the environment the benchmarks ran in.
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    /// Add a result. A number is added to names that are already in use
+    /// (`name #2`), so that exported results never overwrite each other.
+    pub(crate) fn push(&mut self, name: String, mut result: BenchResult) {
+        let name = if self.get(&name).is_none() {
+            name
+        } else {
+            (2..)
+                .map(|n| format!("{} #{}", name, n))
+                .find(|name| self.get(name).is_none())
+                .unwrap()
+        };
+        if result.name.is_none() {
+            result.name = Some(name.as_str().into());
+        }
+        self.results.push((name, result));
+    }
+
+    /// Record that a benchmark failed, with the reason.
+    pub(crate) fn push_failure(&mut self, name: String, message: String) {
+        self.failures.push((name, message));
+    }
+
+    /// Returns the names of the benchmarks that failed, such as by
+    /// panicking with [`Suite::catch_panics()`](crate::Suite::catch_panics),
+    /// and why. They have no result.
+    pub fn failures(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.failures
+            .iter()
+            .map(|(name, message)| (name.as_str(), message.as_str()))
+    }
+
+    /// Returns how long the suite took to run, including the warm-up, if
+    /// the report was produced by a [`Suite`](crate::Suite).
+    pub fn wall_time(&self) -> Option<Duration> {
+        self.wall_time
+    }
+
+    /// Returns summary statistics of the results, to notice the benchmarks
+    /// that never converged among many others.
+    pub fn summary(&self) -> Summary<'_> {
+        let rsd = |result: &BenchResult| result.rsd().unwrap_or(f64::NAN);
+        Summary {
+            wall_time: self.wall_time,
+            benchmarks: self.results.len(),
+            failed: self.failures.len(),
+            converged: self
+                .results
+                .iter()
+                .filter(|(_, result)| result.rsd().is_some_and(|rsd| rsd < result.options.max_rsd))
+                .count(),
+            noisiest: self
+                .iter()
+                .filter(|(_, result)| result.rsd().is_some())
+                .max_by(|(_, a), (_, b)| rsd(a).total_cmp(&rsd(b))),
+            slowest: self
+                .iter()
+                .max_by_key(|(_, result)| result.per_iteration_duration()),
+        }
+    }
+
+    /// Returns the number of results.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Returns `true` if the report doesn't contain any results.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Returns the result of the benchmark with the given name.
+    pub fn get(&self, name: &str) -> Option<&BenchResult> {
+        self.results
+            .iter()
+            .find(|(result_name, _)| result_name == name)
+            .map(|(_, result)| result)
+    }
+
+    /// Iterate over the benchmark names and results.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &BenchResult)> {
+        self.results
+            .iter()
+            .map(|(name, result)| (name.as_str(), result))
+    }
+
+    /// Returns the results sorted by benchmark name.
+    ///
+    /// The sort is stable, so that results sharing a name keep their
+    /// execution order. Exported artifacts use this order, so that they only
+    /// change when the results do.
+    pub(crate) fn sorted(&self) -> Vec<(&str, &BenchResult)> {
+        let mut results: Vec<_> = self.iter().collect();
+        results.sort_by_key(|&(name, _)| name);
+        results
+    }
+
+    /// Returns the report as a JSON document.
+    ///
+    /// Results are sorted by name, and the output is deterministic. The
+    /// `location` of each result is an object with the `file`, `line` and
+    /// `column` of the benchmark, for editors to jump to.
+    pub fn to_json(&self) -> String {
+        let env = &self.environment;
+        let mut out = String::new();
+        out.push_str("{\n  \"environment\": {");
+        out.push_str(&format!(
+            "\"cpu_model\": {}, \"microarchitecture\": {}, \"allowed_cpus\": {}, \"cpu_quota\": {}, \"hypervisor\": {}",
+            json::opt_string(env.cpu_model.as_deref()),
+            json::opt_string(env.microarchitecture.as_deref()),
+            env.allowed_cpus
+                .map_or_else(|| "null".to_string(), |n| n.to_string()),
+            env.cpu_quota.map_or_else(|| "null".to_string(), json::number),
+            json::opt_string(env.hypervisor.as_deref()),
+        ));
+        out.push_str(&format!(
+            ", \"logical_cpus\": {}, \"os\": {}, \"target\": {}, \"rustc_version\": {}, \"profile\": {}, \"git_commit\": {}",
+            env.logical_cpus
+                .map_or_else(|| "null".to_string(), |n| n.to_string()),
+            json::opt_string(env.os.as_deref()),
+            json::opt_string(env.target.as_deref()),
+            json::opt_string(env.rustc_version.as_deref()),
+            json::opt_string(env.profile.as_deref()),
+            json::opt_string(env.git_commit.as_deref()),
+        ));
+        out.push_str(&format!(
+            ", \"cpu_governor\": {}, \"turbo\": {}, \"calibration_score\": {}",
+            json::opt_string(env.cpu_governor.as_deref()),
+            env.turbo
+                .map_or_else(|| "null".to_string(), |turbo| turbo.to_string()),
+            env.calibration_score
+                .map_or_else(|| "null".to_string(), json::number),
+        ));
+        out.push_str("},\n  \"results\": [");
+        for (i, (name, result)) in self.sorted().into_iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str(&format!(
+                "\n    {{\"name\": {}, \"location\": {{\"file\": {}, \"line\": {}, \"column\": {}}}, \"iterations\": {}, \"ns\": {}, \"margin_of_error\": {}, \"below_resolution\": {}, \"near_resolution\": {}, \"interrupted\": {}, \"cpu_throttled\": {}, \"throttled_samples\": {}, \"process_rsd\": {}, \"threads\": {}, \"counters\": {{{}}}, \"parameters\": {{{}}}, \"sample_timed_out\": {}, \"samples\": {}, \"stop_reason\": {}}}",
+                json::string(name),
+                json::string(result.location().file()),
+                result.location().line(),
+                result.location().column(),
+                result.options.iterations,
+                result.as_ns(),
+                result
+                    .margin_of_error()
+                    .map_or_else(|| "null".to_string(), json::number),
+                result.is_below_resolution(),
+                result.is_near_resolution(),
+                result.interrupted(),
+                result.cpu_throttled(),
+                result.throttled_samples(),
+                result
+                    .process_rsd()
+                    .map_or_else(|| "null".to_string(), json::number),
+                result
+                    .threads()
+                    .map_or_else(|| "null".to_string(), |threads| threads.to_string()),
+                result
+                    .counters()
+                    .map(|(name, value)| format!("{}: {}", json::string(name), json::number(value)))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                result
+                    .parameters()
+                    .map(|(key, value)| format!("{}: {}", json::string(key), json::string(value)))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                result.sample_timed_out(),
+                result.samples_collected(),
+                result
+                    .stop_reason()
+                    .map_or_else(|| "null".to_string(), |reason| json::string(reason.name())),
+            ));
+        }
+        out.push_str("\n  ],\n  \"failures\": [");
+        for (i, (name, message)) in self.failures().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str(&format!(
+                "\n    {{\"name\": {}, \"message\": {}}}",
+                json::string(name),
+                json::string(message)
+            ));
+        }
+        out.push_str("\n  ]\n}\n");
+        out
+    }
+
+    /// Returns the samples of all the results as CSV, with a line per sample:
+    /// the benchmark name, the wall-clock time at which the sample started
+    /// (in nanoseconds since the Unix epoch), and its duration (in
+    /// nanoseconds).
+    ///
+    /// Results are sorted by name, and the samples of each result are in
+    /// execution order, so that plotting the durations against the
+    /// timestamps shows when external events interfered. Results without
+    /// timestamps are skipped.
+    pub fn to_timeline_csv(&self) -> String {
+        let mut out = String::from("name,timestamp_ns,ns\n");
+        for (name, result) in self.sorted() {
+            let name = if name.contains([',', '"', '\n']) {
+                format!("\"{}\"", name.replace('"', "\"\""))
+            } else {
+                name.to_string()
+            };
+            for (timestamp, sample) in result.sample_timestamps().iter().zip(result.samples()) {
+                let timestamp = timestamp
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_nanos());
+                out.push_str(&format!("{},{},{}\n", name, timestamp, sample.as_nanos()));
+            }
+        }
+        out
+    }
+
+    /// Append the results to a history file, as one JSON object per line
+    /// and per benchmark, so that a performance history accumulates across
+    /// runs.
+    ///
+    /// Every line includes the time of the run (seconds since the Unix
+    /// epoch) and the git commit the benchmarks were built from, if known.
+    pub fn append_history(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let git_commit = json::opt_string(self.environment.git_commit.as_deref());
+        let mut out = String::new();
+        for (name, result) in self.sorted() {
+            out.push_str(&format!(
+                "{{\"timestamp\": {}, \"git_commit\": {}, \"name\": {}, \"iterations\": {}, \"ns\": {}, \"rsd\": {}, \"samples\": {}, \"interrupted\": {}}}\n",
+                timestamp,
+                git_commit,
+                json::string(name),
+                result.options.iterations,
+                result.as_ns(),
+                result.rsd().map_or_else(|| "null".to_string(), json::number),
+                result.samples().len(),
+                result.interrupted(),
+            ));
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(out.as_bytes())
+    }
+
+    /// Returns the report as compiler-style diagnostics, with a line per
+    /// result, sorted by name, such as
+    /// `benches/hash.rs:12:5: note: hash/sha256: 1.21 µs`.
+    ///
+    /// Editors and CI problem matchers understanding `rustc` output can
+    /// jump from these lines to the benchmarks. Results that are noisy,
+    /// throttled, interrupted or timed out, or slower than the baseline by
+    /// more than `max_rsd`, are reported as warnings, with the reasons.
+    pub fn to_diagnostics(&self, baseline: Option<&Baseline>) -> String {
+        let mut out = String::new();
+        for (name, result) in self.sorted() {
+            let mut reasons = Vec::new();
+            if let Some(rsd) = result.rsd().filter(|&rsd| rsd > result.options.max_rsd) {
+                reasons.push(format!(
+                    "RSD {:.1}% above {:.1}%",
+                    rsd, result.options.max_rsd
+                ));
+            }
+            if result.is_near_resolution() {
+                reasons.push("near the timer resolution".to_string());
+            }
+            if result.cpu_throttled() || result.throttled_samples() > 0 {
+                reasons.push("CPU throttled".to_string());
+            }
+            if result.interrupted() {
+                reasons.push("interrupted".to_string());
+            }
+            if result.sample_timed_out() {
+                reasons.push("sample timed out".to_string());
+            }
+            let change = baseline.and_then(|baseline| change(baseline, name, result));
+            if let Some(change) = change.filter(|&change| change > result.options.max_rsd) {
+                reasons.push(format!("{:.2}% slower than the baseline", change));
+            }
+            let location = result.location();
+            out.push_str(&format!(
+                "{}:{}:{}: {}: {}: {}",
+                location.file(),
+                location.line(),
+                location.column(),
+                if reasons.is_empty() {
+                    "note"
+                } else {
+                    "warning"
+                },
+                name,
+                format_secs(result.as_secs_f64())
+            ));
+            if let Some(change) = change {
+                out.push_str(&format!(" ({:+.2}%)", change));
+            }
+            if !reasons.is_empty() {
+                out.push_str(&format!(" [{}]", reasons.join(", ")));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Returns the report as a Markdown table, with the relative change of
+    /// each result compared to a baseline, if one is given.
+    ///
+    /// Changes larger than `max_rsd` are marked as slower or faster.
+    pub fn to_markdown(&self, baseline: Option<&Baseline>) -> String {
+        let mut out = String::from("| Benchmark | Time | RSD | Samples |");
+        out.push_str(if baseline.is_some() {
+            " Change | Source |\n|---|---:|---:|---:|---:|---|\n"
+        } else {
+            " Source |\n|---|---:|---:|---:|---|\n"
+        });
+        for (name, result) in self.sorted() {
+            out.push_str(&format!(
+                "| `{}` | {} | {} | {} |",
+                name.replace('|', "\\|").replace('`', "'"),
+                format_secs(result.as_secs_f64()),
+                result
+                    .rsd()
+                    .map_or_else(|| "-".to_string(), |rsd| format!("{:.1}%", rsd)),
+                result.samples().len()
+            ));
+            if let Some(baseline) = baseline {
+                let threshold = result.options.max_rsd;
+                match change(baseline, name, result) {
+                    Some(change) if change > threshold => {
+                        out.push_str(&format!(" **{:+.2}%** (slower) |", change))
+                    }
+                    Some(change) if change < -threshold => {
+                        out.push_str(&format!(" **{:+.2}%** (faster) |", change))
+                    }
+                    Some(change) => out.push_str(&format!(" {:+.2}% |", change)),
+                    None => out.push_str(" - |"),
+                }
+            }
+            out.push_str(&format!(
+                " [{}]({}) |\n",
+                result.location().to_string().replace('|', "\\|"),
+                source_url(result.location(), self.source_url.as_deref())
+            ));
+        }
+        out
+    }
+
+    /// Append the Markdown report to the GitHub Actions job summary, if the
+    /// `GITHUB_STEP_SUMMARY` environment variable is set, so that results
+    /// show up on the page of the run.
+    ///
+    /// Returns `true` if the summary was written.
+    pub fn write_github_summary(&self, baseline: Option<&Baseline>) -> io::Result<bool> {
+        let path = match std::env::var_os("GITHUB_STEP_SUMMARY") {
+            Some(path) if !path.is_empty() => path,
+            _ => return Ok(false),
+        };
+        let mut out = String::from("## Benchmarks\n\n");
+        out.push_str(&self.to_markdown(baseline));
+        out.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(out.as_bytes())?;
+        Ok(true)
+    }
+
+    /// Write the report as a JUnit XML file, with a test case per benchmark
+    /// and the duration of the benchmark as the time of the test case.
+    ///
+    /// CI systems render JUnit reports natively.
+    pub fn write_junit(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.junit(None))
+    }
+
+    /// Write the report as a JUnit XML file, reporting results slower than
+    /// the baseline by more than `max_rsd` as failures.
+    pub fn write_junit_compared(
+        &self,
+        path: impl AsRef<Path>,
+        baseline: &Baseline,
+    ) -> io::Result<()> {
+        std::fs::write(path, self.junit(Some(baseline)))
+    }
+
+    fn junit(&self, baseline: Option<&Baseline>) -> String {
+        let mut cases = String::new();
+        let mut failures = 0;
+        let mut total = 0.0;
+        for (name, result) in self.sorted() {
+            let (group, leaf) = split_group(name);
+            let secs = result.as_secs_f64();
+            total += secs;
+            cases.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{}\">\n",
+                html_escape(group.unwrap_or("benchmarks")),
+                html_escape(leaf),
+                json::number(secs)
+            ));
+            let change = baseline.and_then(|baseline| change(baseline, name, result));
+            if let Some(change) = change.filter(|&change| change > result.options.max_rsd) {
+                failures += 1;
+                cases.push_str(&format!(
+                    "      <failure message=\"{:+.2}% vs baseline\">{} is {:.2}% slower than the baseline</failure>\n",
+                    change,
+                    html_escape(name),
+                    change
+                ));
+            }
+            cases.push_str(&format!(
+                "      <system-out>{}</system-out>\n    </testcase>\n",
+                html_escape(&format!("{:#}", result))
+            ));
+        }
+        let count = self.results.len();
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"benchmarks\" tests=\"{count}\" failures=\"{failures}\" time=\"{time}\">\n  <testsuite name=\"benchmarks\" tests=\"{count}\" failures=\"{failures}\" time=\"{time}\">\n{cases}  </testsuite>\n</testsuites>\n",
+            count = count,
+            failures = failures,
+            time = json::number(total),
+            cases = cases
+        )
+    }
+
+    /// Returns the report as a standalone HTML page.
+    ///
+    /// Results are sorted by name, and the output is deterministic.
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Benchmark report</title></head>\n<body>\n");
+        out.push_str(&self.to_html_table());
+        out.push_str("<pre>");
+        out.push_str(&html_escape(&self.environment.to_string()));
+        out.push_str("</pre>\n</body>\n</html>\n");
+        out
+    }
+}
+
+impl Report {
+    /// Returns the results as an HTML table, without the rest of the page.
+    ///
+    /// Hierarchical names such as `aead/aes256gcm/encrypt/16384` are
+    /// grouped under a header row with their parent name.
+    pub fn to_html_table(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<table>\n<tr><th>Benchmark</th><th>Time</th><th>Source</th></tr>\n");
+        for (group, members) in grouped(self.sorted().into_iter()) {
+            if let Some(group) = group {
+                out.push_str(&format!(
+                    "<tr><th colspan=\"3\">{}</th></tr>\n",
+                    html_escape(group)
+                ));
+            }
+            for (name, result) in members {
+                out.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td><a href=\"{}\"><code>{}</code></a></td></tr>\n",
+                    html_escape(split_group(name).1),
+                    html_escape(&format!("{:#}", result)),
+                    html_escape(&source_url(result.location(), self.source_url.as_deref())),
+                    html_escape(&result.location().to_string()),
+                ));
+            }
+        }
+        out.push_str("</table>\n");
+        out
+    }
+
+    /// Display the report as an HTML table in an evcxr notebook.
+    pub fn evcxr_display(&self) {
+        crate::evcxr_html(&self.to_html_table());
+    }
+}
+
+/// Returns the URL of the source of a benchmark, from a template with
+/// `{file}` and `{line}` placeholders, or the default one.
+pub(crate) fn source_url(location: &Location<'_>, template: Option<&str>) -> String {
+    let template = match template {
+        Some(template) => template.to_string(),
+        None => {
+            let var = |name| std::env::var(name).ok().filter(|v: &String| !v.is_empty());
+            match (
+                var("GITHUB_SERVER_URL"),
+                var("GITHUB_REPOSITORY"),
+                var("GITHUB_SHA"),
+            ) {
+                (Some(server), Some(repository), Some(commit)) => format!(
+                    "{}/{}/blob/{}/{{file}}#L{{line}}",
+                    server, repository, commit
+                ),
+                _ => "{file}#L{line}".to_string(),
+            }
+        }
+    };
+    let file = location
+        .file()
+        .replace('\\', "/")
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29");
+    template
+        .replace("{file}", &file)
+        .replace("{line}", &location.line().to_string())
+}
+
+pub(crate) fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Report {
+    /// Print the report to the standard output.
+    ///
+    /// Hierarchical names such as `aead/aes256gcm/encrypt/16384` are grouped
+    /// in sections named after their parent (`aead/aes256gcm/encrypt`), where
+    /// only their last component, usually a parameter, is shown.
+    ///
+    /// Results whose RSD is above `max_rsd`, or that were flagged, are shown
+    /// in yellow when the standard output is a terminal and `NO_COLOR` is not set.
+    pub fn print(&self) {
+        print!(
+            "{}",
+            Styled {
+                report: self,
+                baseline: None,
+                colors: color::stdout_supports_color(),
+                format: TimeFormat::default(),
+            }
+        );
+    }
+
+    /// Print the report to the standard output, along with the relative
+    /// change of each result compared to a baseline.
+    ///
+    /// A previous report can be used as a baseline with `Baseline::from(&report)`,
+    /// or loaded from a JSON export with `Baseline::load()`.
+    ///
+    /// Changes larger than `max_rsd` are considered significant: regressions
+    /// are shown in red and improvements in green, when colors are enabled.
+    /// A warning is printed if the baseline comes from a different
+    /// microarchitecture.
+    pub fn print_compared(&self, baseline: &Baseline) {
+        if let (Some(old), Some(new)) = (
+            baseline.microarchitecture(),
+            self.environment.microarchitecture.as_deref(),
+        ) {
+            if old != new {
+                eprintln!(
+                    "WARNING: the baseline comes from a different microarchitecture ({} vs {}), changes may come from the hardware.",
+                    old, new
+                );
+            }
+        }
+        print!(
+            "{}",
+            Styled {
+                report: self,
+                baseline: Some(baseline),
+                colors: color::stdout_supports_color(),
+                format: TimeFormat::default(),
+            }
+        );
+    }
+}
+
+impl Report {
+    /// Returns the report with configurable display options, such as a
+    /// fixed unit and number of decimals, so that durations line up and
+    /// don't switch units across rows or runs.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// let mut suite = Suite::new(Options::quick());
+    /// suite.register("sum", || (0..1000u64).sum::<u64>());
+    /// let report = suite.run();
+    /// let table = report
+    ///     .format()
+    ///     .decimals(3)
+    ///     .force_unit(TimeUnit::Microseconds)
+    ///     .to_string();
+    /// assert!(table.contains(" µs"));
+    /// ```
+    pub fn format(&self) -> ReportFormat<'_> {
+        ReportFormat {
+            report: self,
+            baseline: None,
+            format: TimeFormat::default(),
+        }
+    }
+}
+
+/// A [`Report`] with display options, returned by [`Report::format()`].
+///
+/// It is displayed like the report itself, with its durations in a fixed
+/// format, right-aligned.
+#[derive(Clone, Copy)]
+pub struct ReportFormat<'a> {
+    report: &'a Report,
+    baseline: Option<&'a Baseline>,
+    format: TimeFormat,
+}
+
+impl<'a> ReportFormat<'a> {
+    /// Show `decimals` decimals, instead of three significant digits.
+    pub fn decimals(mut self, decimals: usize) -> Self {
+        self.format.decimals = Some(decimals);
+        self
+    }
+
+    /// Show all durations in `unit`, overriding the unit of the results.
+    pub fn force_unit(mut self, unit: TimeUnit) -> Self {
+        self.format.unit = Some(unit);
+        self
+    }
+
+    /// Show the relative change of each result compared to a baseline, as
+    /// [`Report::print_compared()`] does.
+    pub fn compared(mut self, baseline: &'a Baseline) -> Self {
+        self.baseline = Some(baseline);
+        self
+    }
+
+    /// Print the report to the standard output, with colors if it is a
+    /// terminal and `NO_COLOR` is not set.
+    pub fn print(&self) {
+        print!("{}", self.styled(color::stdout_supports_color()));
+    }
+
+    fn styled(&self, colors: bool) -> Styled<'a> {
+        Styled {
+            report: self.report,
+            baseline: self.baseline,
+            colors,
+            format: self.format,
+        }
+    }
+}
+
+impl Display for ReportFormat<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.styled(false).fmt(f)
+    }
+}
+
+/// Summary statistics of a [`Report`], returned by [`Report::summary()`].
+#[derive(Clone, Copy, Debug)]
+pub struct Summary<'a> {
+    wall_time: Option<Duration>,
+    benchmarks: usize,
+    failed: usize,
+    converged: usize,
+    noisiest: Option<(&'a str, &'a BenchResult)>,
+    slowest: Option<(&'a str, &'a BenchResult)>,
+}
+
+impl<'a> Summary<'a> {
+    /// Returns how long the suite took to run, if known.
+    pub fn wall_time(&self) -> Option<Duration> {
+        self.wall_time
+    }
+
+    /// Returns the number of benchmarks.
+    pub fn benchmarks(&self) -> usize {
+        self.benchmarks
+    }
+
+    /// Returns the number of benchmarks that failed, and have no result.
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+
+    /// Returns the number of benchmarks whose RSD is below their `max_rsd`.
+    pub fn converged(&self) -> usize {
+        self.converged
+    }
+
+    /// Returns the name and result of the benchmark with the highest RSD.
+    pub fn noisiest(&self) -> Option<(&'a str, &'a BenchResult)> {
+        self.noisiest
+    }
+
+    /// Returns the name and result of the benchmark with the slowest
+    /// iterations.
+    pub fn slowest(&self) -> Option<(&'a str, &'a BenchResult)> {
+        self.slowest
+    }
+}
+
+impl Display for Summary<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} benchmarks", self.benchmarks)?;
+        if let Some(wall_time) = self.wall_time {
+            write!(f, " in {}", format_secs(wall_time.as_secs_f64()))?;
+        }
+        write!(
+            f,
+            "\n  Met the RSD target: {}/{}",
+            self.converged, self.benchmarks
+        )?;
+        if self.failed > 0 {
+            write!(f, "\n  Failed: {}", self.failed)?;
+        }
+        if let Some((name, result)) = self.noisiest {
+            write!(
+                f,
+                "\n  Noisiest: {} (± {:.1}%)",
+                name,
+                result.rsd().unwrap_or(0.0)
+            )?;
+        }
+        if let Some((name, result)) = self.slowest {
+            write!(
+                f,
+                "\n  Slowest: {} ({})",
+                name,
+                format_secs(result.per_iteration_duration().as_secs_f64())
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the relative change of a result compared to a baseline (in %),
+/// if the baseline has it.
+pub(crate) fn change(baseline: &Baseline, name: &str, result: &BenchResult) -> Option<f64> {
+    let base = baseline
+        .secs_per_iteration(name)
+        .filter(|&base| base > 0.0)?;
+    Some((result.secs_per_iteration() / base - 1.0) * 100.0)
+}
+
+struct Styled<'a> {
+    report: &'a Report,
+    baseline: Option<&'a Baseline>,
+    colors: bool,
+    format: TimeFormat,
+}
+
+/// Splits a hierarchical name such as `aead/aes256gcm/encrypt/16384` into
+/// its group (`aead/aes256gcm/encrypt`) and its last component (`16384`).
+pub(crate) fn split_group(name: &str) -> (Option<&str>, &str) {
+    match name.rsplit_once('/') {
+        Some((group, leaf)) => (Some(group), leaf),
+        None => (None, name),
+    }
+}
+
+/// A group name, and the names and results of its members.
+type Group<'a> = (Option<&'a str>, Vec<(&'a str, &'a BenchResult)>);
+
+/// Returns the results grouped by the parent of their name, in order of
+/// first appearance.
+fn grouped<'a>(results: impl Iterator<Item = (&'a str, &'a BenchResult)>) -> Vec<Group<'a>> {
+    let mut groups: Vec<(Option<&str>, Vec<_>)> = Vec::new();
+    for (name, result) in results {
+        let (group, _) = split_group(name);
+        match groups.iter_mut().find(|(g, _)| *g == group) {
+            Some((_, members)) => members.push((name, result)),
+            None => groups.push((group, vec![(name, result)])),
+        }
+    }
+    groups
+}
+
+impl Display for Styled<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let report = self.report;
+        let colors = self.colors;
+        for (group, members) in grouped(report.iter()) {
+            // Members of a group are indented below its name, and only show
+            // their last component.
+            let (indent, label): (usize, fn(&str) -> &str) = match group {
+                Some(group) => {
+                    writeln!(f, "{}:", group)?;
+                    (2, |name| split_group(name).1)
+                }
+                None => (0, |name| name),
+            };
+            let width = members
+                .iter()
+                .map(|(name, _)| label(name).len())
+                .max()
+                .unwrap_or(0);
+            // With a fixed format, durations are right-aligned, so that
+            // their digits line up.
+            let mut format = self.format;
+            if format.unit.is_some() || format.decimals.is_some() {
+                format.width = members
+                    .iter()
+                    .filter(|(_, result)| !result.options.dry_run)
+                    .map(|(_, result)| result.formatted_time(format).chars().count())
+                    .max()
+                    .unwrap_or(0);
+            }
+            for (name, result) in members {
+                let noisy = result.rsd().is_some_and(|rsd| rsd > result.options.max_rsd)
+                    || result.is_near_resolution()
+                    || result.cpu_throttled()
+                    || result.throttled_samples() > 0
+                    || result.interrupted()
+                    || result.sample_timed_out();
+                write!(
+                    f,
+                    "{:indent$}{:width$}  {}",
+                    "",
+                    label(name),
+                    paint(
+                        result.display_with(format),
+                        (colors && noisy).then_some(Color::Yellow)
+                    ),
+                    indent = indent,
+                    width = width
+                )?;
+                if let Some(change) = self.baseline.and_then(|b| change(b, name, result)) {
+                    let threshold = result.options.max_rsd;
+                    let color = if change > threshold {
+                        Some(Color::Red)
+                    } else if change < -threshold {
+                        Some(Color::Green)
+                    } else {
+                        None
+                    };
+                    write!(
+                        f,
+                        "  ({} vs baseline)",
+                        paint(format!("{:+.2}%", change), color.filter(|_| colors))
+                    )?;
+                }
+                writeln!(f)?;
+            }
+        }
+        for (name, message) in report.failures() {
+            writeln!(
+                f,
+                "{}  {}",
+                name,
+                paint(format!("failed: {}", message), colors.then_some(Color::Red))
+            )?;
+        }
+        if let Some(hypervisor) = &report.environment.hypervisor {
+            writeln!(
+                f,
+                "Note: measured under a hypervisor ({}), timers may be virtualized.",
+                hypervisor
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Styled {
+            report: self,
+            baseline: None,
+            colors: false,
+            format: TimeFormat::default(),
+        }
+        .fmt(f)
+    }
+}
@@ -0,0 +1,70 @@
+//! Hooks called while benchmarks run.
+
+use std::ops::ControlFlow;
+use std::panic::Location;
+use std::time::Duration;
+
+use crate::{format_secs, BenchResult, Options};
+
+/// Receives notifications while a benchmark runs.
+///
+/// All methods have a default implementation doing nothing, so that only the
+/// relevant ones have to be implemented.
+pub trait Reporter {
+    /// Called before the warm-up of a benchmark.
+    fn on_start(&mut self, location: &'static Location<'static>, options: &Options) {
+        let _ = (location, options);
+    }
+
+    /// Called after every sample, with its duration and, from the second
+    /// sample on, the RSD of the samples collected so far (in 0...100).
+    ///
+    /// Returning `ControlFlow::Break(())` stops the benchmark; the result is
+    /// then marked as interrupted.
+    fn on_sample(
+        &mut self,
+        sample: usize,
+        duration: Duration,
+        rsd: Option<f64>,
+    ) -> ControlFlow<()> {
+        let _ = (sample, duration, rsd);
+        ControlFlow::Continue(())
+    }
+
+    /// Called with the result of a benchmark.
+    fn on_finish(&mut self, result: &BenchResult) {
+        let _ = result;
+    }
+}
+
+/// A reporter printing a line to the standard output for every event.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PrintReporter;
+
+impl Reporter for PrintReporter {
+    fn on_start(&mut self, location: &'static Location<'static>, _options: &Options) {
+        println!("Starting a new benchmark ({}).", location);
+    }
+
+    fn on_sample(
+        &mut self,
+        sample: usize,
+        duration: Duration,
+        rsd: Option<f64>,
+    ) -> ControlFlow<()> {
+        match rsd {
+            Some(rsd) => println!(
+                "Sample {}: {} (RSD: {:.2}%)",
+                sample,
+                format_secs(duration.as_secs_f64()),
+                rsd
+            ),
+            None => println!("Sample {}: {}", sample, format_secs(duration.as_secs_f64())),
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn on_finish(&mut self, result: &BenchResult) {
+        println!("Result: {:#}", result);
+    }
+}
@@ -0,0 +1,88 @@
+//! Page faults and context switches of the samples, measured with
+//! `getrusage()`.
+
+use std::ops::Add;
+
+/// Page faults and context switches during a sample.
+///
+/// When a sample is an outlier, these tell whether the operating system
+/// preempted the benchmark, or whether it faulted memory in. Recorded with
+/// the `resource_usage` option, on Unix systems. On Linux, only the thread
+/// running the samples is accounted for; elsewhere, the whole process is.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ResourceUsage {
+    /// Page faults served without any I/O.
+    pub minor_faults: u64,
+    /// Page faults that required I/O.
+    pub major_faults: u64,
+    /// Context switches due to the benchmark waiting for a resource.
+    pub voluntary_switches: u64,
+    /// Context switches due to the scheduler preempting the benchmark.
+    pub involuntary_switches: u64,
+}
+
+impl ResourceUsage {
+    /// Returns the counters accumulated so far, if they are available.
+    pub(crate) fn now() -> Option<Self> {
+        imp::now()
+    }
+
+    /// Returns the difference between these counters and earlier ones.
+    pub(crate) fn since(self, earlier: Self) -> Self {
+        ResourceUsage {
+            minor_faults: self.minor_faults.saturating_sub(earlier.minor_faults),
+            major_faults: self.major_faults.saturating_sub(earlier.major_faults),
+            voluntary_switches: self
+                .voluntary_switches
+                .saturating_sub(earlier.voluntary_switches),
+            involuntary_switches: self
+                .involuntary_switches
+                .saturating_sub(earlier.involuntary_switches),
+        }
+    }
+}
+
+impl Add for ResourceUsage {
+    type Output = ResourceUsage;
+
+    fn add(self, other: ResourceUsage) -> Self::Output {
+        ResourceUsage {
+            minor_faults: self.minor_faults + other.minor_faults,
+            major_faults: self.major_faults + other.major_faults,
+            voluntary_switches: self.voluntary_switches + other.voluntary_switches,
+            involuntary_switches: self.involuntary_switches + other.involuntary_switches,
+        }
+    }
+}
+
+#[cfg(unix)]
+mod imp {
+    use super::ResourceUsage;
+
+    #[cfg(target_os = "linux")]
+    const WHO: libc::c_int = libc::RUSAGE_THREAD;
+    #[cfg(not(target_os = "linux"))]
+    const WHO: libc::c_int = libc::RUSAGE_SELF;
+
+    pub(crate) fn now() -> Option<ResourceUsage> {
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        if unsafe { libc::getrusage(WHO, &mut usage) } != 0 {
+            return None;
+        }
+        Some(ResourceUsage {
+            minor_faults: usage.ru_minflt as u64,
+            major_faults: usage.ru_majflt as u64,
+            voluntary_switches: usage.ru_nvcsw as u64,
+            involuntary_switches: usage.ru_nivcsw as u64,
+        })
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use super::ResourceUsage;
+
+    pub(crate) fn now() -> Option<ResourceUsage> {
+        None
+    }
+}
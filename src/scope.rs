@@ -0,0 +1,111 @@
+//! Timing regions of application code with guards.
+
+use std::panic::Location;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use precision::{Elapsed, Timestamp};
+
+use crate::{stats, Bench, BenchResult, Environment, Options, Report};
+
+/// The measurements of the scopes of a `Bench`, by label, in the order the
+/// labels were first used.
+pub(crate) type Scopes = Arc<Mutex<Vec<(String, &'static Location<'static>, Vec<Elapsed>)>>>;
+
+/// A guard returned by [`Bench::scope()`], measuring the time until it is
+/// dropped.
+#[must_use = "the scope ends when the guard is dropped"]
+pub struct ScopeTimer<'a> {
+    bench: &'a Bench,
+    label: Option<String>,
+    location: &'static Location<'static>,
+    start: Timestamp,
+}
+
+impl Drop for ScopeTimer<'_> {
+    fn drop(&mut self) {
+        let elapsed = self.bench.precision.now() - self.start;
+        let label = self.label.take().unwrap_or_default();
+        let mut scopes = match self.bench.scopes.lock() {
+            Ok(scopes) => scopes,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        match scopes.iter_mut().find(|(name, _, _)| *name == label) {
+            Some((_, _, samples)) => samples.push(elapsed),
+            None => scopes.push((label, self.location, vec![elapsed])),
+        }
+    }
+}
+
+impl Bench {
+    /// Start timing a region of code, until the returned guard is dropped.
+    ///
+    /// Every time a scope with the same label ends counts as a sample of
+    /// that label, and [`Bench::scopes_report()`] returns a result per
+    /// label. This times real application code, without wrapping it into a
+    /// closure.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// let bench = Bench::new();
+    /// for _ in 0..10 {
+    ///     let _t = bench.scope("parse");
+    ///     let _ = "12345".parse::<u64>();
+    /// }
+    /// let report = bench.scopes_report();
+    /// assert_eq!(report.get("parse").unwrap().samples().len(), 10);
+    /// ```
+    #[track_caller]
+    pub fn scope(&self, label: impl Into<String>) -> ScopeTimer<'_> {
+        ScopeTimer {
+            bench: self,
+            label: Some(label.into()),
+            location: Location::caller(),
+            start: self.precision.now(),
+        }
+    }
+
+    /// Returns a report with a result per scope label: the fastest sample,
+    /// with every sample and their RSD.
+    pub fn scopes_report(&self) -> Report {
+        let scopes = match self.scopes.lock() {
+            Ok(scopes) => scopes,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let mut report = Report::new(Environment::detect());
+        for (label, location, samples) in scopes.iter() {
+            report.push(label.clone(), self.result_from_samples(samples, location));
+        }
+        report
+    }
+
+    /// Returns a result made of samples of a single iteration each.
+    pub(crate) fn result_from_samples(
+        &self,
+        samples: &[Elapsed],
+        location: &'static Location<'static>,
+    ) -> BenchResult {
+        let options = Options {
+            min_samples: samples.len(),
+            max_samples: samples.len().max(1),
+            ..Options::base()
+        };
+        let min = samples
+            .iter()
+            .min_by_key(|elapsed| elapsed.ticks())
+            .copied()
+            .unwrap_or_default();
+        let secs: Vec<f64> = samples
+            .iter()
+            .map(|elapsed| self.precision.as_secs_f64(*elapsed))
+            .collect();
+        let mut result = self.result(min, Arc::new(options), location);
+        result.rsd = stats::rsd(&secs);
+        result.samples = samples
+            .iter()
+            .map(|elapsed| Duration::from_nanos(self.precision.as_ns(*elapsed)))
+            .collect();
+        result
+    }
+}
@@ -0,0 +1,481 @@
+//! `Serialize` and `Deserialize` implementations for the core types.
+//!
+//! Only available with the `serde` feature.
+//!
+//! Results are serialized with their elapsed time in nanoseconds, and
+//! converted back to ticks of the local timer when deserialized. The source
+//! location of a deserialized result is not preserved.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Formatter};
+use std::marker::PhantomData;
+use std::panic::Location;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use precision::Elapsed;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeStruct, Serializer};
+
+use crate::{
+    shared_timer, BenchResult, Options, Priority, ResourceUsage, StopReason, Throughput, TimeUnit,
+    Unit,
+};
+
+impl Serialize for Unit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match self {
+            Unit::None => "none",
+            Unit::Bytes => "bytes",
+            Unit::Bits => "bits",
+            Unit::Elements => "elements",
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Unit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        const VARIANTS: &[&str] = &["none", "bytes", "bits", "elements"];
+        match String::deserialize(deserializer)?.as_str() {
+            "none" => Ok(Unit::None),
+            "bytes" => Ok(Unit::Bytes),
+            "bits" => Ok(Unit::Bits),
+            "elements" => Ok(Unit::Elements),
+            other => Err(de::Error::unknown_variant(other, VARIANTS)),
+        }
+    }
+}
+
+impl Serialize for Priority {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match self {
+            Priority::Normal => "normal",
+            Priority::High => "high",
+            Priority::Realtime => "realtime",
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Priority {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        const VARIANTS: &[&str] = &["normal", "high", "realtime"];
+        match String::deserialize(deserializer)?.as_str() {
+            "normal" => Ok(Priority::Normal),
+            "high" => Ok(Priority::High),
+            "realtime" => Ok(Priority::Realtime),
+            other => Err(de::Error::unknown_variant(other, VARIANTS)),
+        }
+    }
+}
+
+impl Serialize for StopReason {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for StopReason {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        const VARIANTS: &[&str] = &[
+            "max_samples",
+            "converged",
+            "max_duration",
+            "sample_timeout",
+            "interrupted",
+            "reporter",
+            "rule",
+        ];
+        let name = String::deserialize(deserializer)?;
+        StopReason::from_name(&name).ok_or_else(|| de::Error::unknown_variant(&name, VARIANTS))
+    }
+}
+
+const OPTIONS_FIELDS: &[&str] = &[
+    "iterations",
+    "warmup_iterations",
+    "warmup_time",
+    "sample_time",
+    "min_samples",
+    "max_samples",
+    "max_rsd",
+    "max_duration",
+    "sample_timeout",
+    "abort_on_sample_timeout",
+    "verbose",
+    "dry_run",
+    "detect_throttling",
+    "throttling_retries",
+    "iteration_jitter",
+    "priority",
+    "cold_cache",
+    "auto_iterations",
+    "cooldown",
+    "discard_samples",
+    "extend_samples",
+    "max_ci_width",
+    "resource_usage",
+];
+
+impl Serialize for Options {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Options", OPTIONS_FIELDS.len())?;
+        s.serialize_field("iterations", &self.iterations)?;
+        s.serialize_field("warmup_iterations", &self.warmup_iterations)?;
+        s.serialize_field("warmup_time", &self.warmup_time)?;
+        s.serialize_field("sample_time", &self.sample_time)?;
+        s.serialize_field("min_samples", &self.min_samples)?;
+        s.serialize_field("max_samples", &self.max_samples)?;
+        s.serialize_field("max_rsd", &self.max_rsd)?;
+        s.serialize_field("max_duration", &self.max_duration)?;
+        s.serialize_field("sample_timeout", &self.sample_timeout)?;
+        s.serialize_field("abort_on_sample_timeout", &self.abort_on_sample_timeout)?;
+        s.serialize_field("verbose", &self.verbose)?;
+        s.serialize_field("dry_run", &self.dry_run)?;
+        s.serialize_field("detect_throttling", &self.detect_throttling)?;
+        s.serialize_field("throttling_retries", &self.throttling_retries)?;
+        s.serialize_field("iteration_jitter", &self.iteration_jitter)?;
+        s.serialize_field("priority", &self.priority)?;
+        s.serialize_field("cold_cache", &self.cold_cache)?;
+        s.serialize_field("auto_iterations", &self.auto_iterations)?;
+        s.serialize_field("cooldown", &self.cooldown)?;
+        s.serialize_field("discard_samples", &self.discard_samples)?;
+        s.serialize_field("extend_samples", &self.extend_samples)?;
+        s.serialize_field("max_ci_width", &self.max_ci_width)?;
+        s.serialize_field("resource_usage", &self.resource_usage)?;
+        s.end()
+    }
+}
+
+/// Missing options keep their default values, without environment overrides.
+impl<'de> Deserialize<'de> for Options {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("Options", OPTIONS_FIELDS, StructVisitor::new("options"))
+    }
+}
+
+impl FromMap for Options {
+    fn from_map<'de, A: MapAccess<'de>>(mut map: A) -> Result<Self, A::Error> {
+        let mut options = Options::base();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "iterations" => options.iterations = map.next_value()?,
+                "warmup_iterations" => options.warmup_iterations = map.next_value()?,
+                "warmup_time" => options.warmup_time = map.next_value()?,
+                "sample_time" => options.sample_time = map.next_value()?,
+                "min_samples" => options.min_samples = map.next_value()?,
+                "max_samples" => options.max_samples = map.next_value()?,
+                "max_rsd" => options.max_rsd = map.next_value()?,
+                "max_duration" => options.max_duration = map.next_value()?,
+                "sample_timeout" => options.sample_timeout = map.next_value()?,
+                "abort_on_sample_timeout" => options.abort_on_sample_timeout = map.next_value()?,
+                "verbose" => options.verbose = map.next_value()?,
+                "dry_run" => options.dry_run = map.next_value()?,
+                "detect_throttling" => options.detect_throttling = map.next_value()?,
+                "throttling_retries" => options.throttling_retries = map.next_value()?,
+                "iteration_jitter" => options.iteration_jitter = map.next_value()?,
+                "priority" => options.priority = map.next_value()?,
+                "cold_cache" => options.cold_cache = map.next_value()?,
+                "auto_iterations" => options.auto_iterations = map.next_value()?,
+                "cooldown" => options.cooldown = map.next_value()?,
+                "discard_samples" => options.discard_samples = map.next_value()?,
+                "extend_samples" => options.extend_samples = map.next_value()?,
+                "max_ci_width" => options.max_ci_width = map.next_value()?,
+                "resource_usage" => options.resource_usage = map.next_value()?,
+                other => return Err(de::Error::unknown_field(other, OPTIONS_FIELDS)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+const RESULT_FIELDS: &[&str] = &[
+    "ns",
+    "options",
+    "location",
+    "rsd",
+    "process_rsd",
+    "samples",
+    "sample_timed_out",
+    "interrupted",
+    "cpu_throttled",
+    "throttled_samples",
+    "near_resolution",
+    "threads",
+    "counters",
+    "name",
+    "parameters",
+    "timestamps",
+    "resource_usage",
+    "stop_reason",
+];
+
+impl Serialize for BenchResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let samples: Vec<u64> = self
+            .samples
+            .iter()
+            .map(|sample| sample.as_nanos() as u64)
+            .collect();
+        let mut s = serializer.serialize_struct("BenchResult", RESULT_FIELDS.len())?;
+        s.serialize_field("ns", &self.as_ns())?;
+        s.serialize_field("options", &*self.options)?;
+        s.serialize_field("location", &self.location.to_string())?;
+        s.serialize_field("rsd", &self.rsd)?;
+        s.serialize_field("process_rsd", &self.process_rsd)?;
+        s.serialize_field("samples", &samples)?;
+        s.serialize_field("sample_timed_out", &self.sample_timed_out)?;
+        s.serialize_field("interrupted", &self.interrupted)?;
+        s.serialize_field("cpu_throttled", &self.cpu_throttled)?;
+        s.serialize_field("throttled_samples", &self.throttled_samples)?;
+        s.serialize_field("near_resolution", &self.near_resolution)?;
+        s.serialize_field("threads", &self.threads)?;
+        s.serialize_field("counters", &NamedValues(&self.counters))?;
+        s.serialize_field("name", &self.name())?;
+        s.serialize_field("parameters", &NamedValues(&self.parameters))?;
+        let timestamps: Vec<u64> = self
+            .timestamps
+            .iter()
+            .map(|timestamp| {
+                timestamp
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_nanos() as u64)
+            })
+            .collect();
+        s.serialize_field("timestamps", &timestamps)?;
+        s.serialize_field("resource_usage", &*self.resource_usage)?;
+        s.serialize_field("stop_reason", &self.stop_reason)?;
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for BenchResult {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct(
+            "BenchResult",
+            RESULT_FIELDS,
+            StructVisitor::new("a benchmark result"),
+        )
+    }
+}
+
+impl FromMap for BenchResult {
+    fn from_map<'de, A: MapAccess<'de>>(mut map: A) -> Result<Self, A::Error> {
+        let precision = shared_timer();
+        let mut result = BenchResult {
+            elapsed: Elapsed::from_ticks(0),
+            precision: precision.clone(),
+            options: Arc::new(Options::base()),
+            location: Location::caller(),
+            sample_timed_out: false,
+            interrupted: false,
+            cpu_throttled: false,
+            throttled_samples: 0,
+            near_resolution: false,
+            rsd: None,
+            process_rsd: None,
+            samples: Arc::new([]),
+            time_unit: TimeUnit::Auto,
+            threads: None,
+            counters: Arc::new([]),
+            name: None,
+            parameters: Arc::new([]),
+            timestamps: Arc::new([]),
+            resource_usage: Arc::new([]),
+            stop_reason: None,
+        };
+        let mut ns = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "ns" => ns = Some(map.next_value::<u64>()?),
+                "options" => result.options = Arc::new(map.next_value()?),
+                "location" => {
+                    map.next_value::<de::IgnoredAny>()?;
+                }
+                "rsd" => result.rsd = map.next_value()?,
+                "process_rsd" => result.process_rsd = map.next_value()?,
+                "samples" => {
+                    result.samples = map
+                        .next_value::<Vec<u64>>()?
+                        .into_iter()
+                        .map(Duration::from_nanos)
+                        .collect()
+                }
+                "sample_timed_out" => result.sample_timed_out = map.next_value()?,
+                "interrupted" => result.interrupted = map.next_value()?,
+                "cpu_throttled" => result.cpu_throttled = map.next_value()?,
+                "throttled_samples" => result.throttled_samples = map.next_value()?,
+                "near_resolution" => result.near_resolution = map.next_value()?,
+                "threads" => result.threads = map.next_value()?,
+                "counters" => {
+                    result.counters = map
+                        .next_value::<BTreeMap<String, f64>>()?
+                        .into_iter()
+                        .collect()
+                }
+                "name" => result.name = map.next_value::<Option<String>>()?.map(Into::into),
+                "timestamps" => {
+                    result.timestamps = map
+                        .next_value::<Vec<u64>>()?
+                        .into_iter()
+                        .map(|ns| UNIX_EPOCH + Duration::from_nanos(ns))
+                        .collect()
+                }
+                "resource_usage" => {
+                    result.resource_usage = map.next_value::<Vec<ResourceUsage>>()?.into()
+                }
+                "stop_reason" => result.stop_reason = map.next_value()?,
+                "parameters" => {
+                    result.parameters = map
+                        .next_value::<BTreeMap<String, String>>()?
+                        .into_iter()
+                        .collect()
+                }
+                other => return Err(de::Error::unknown_field(other, RESULT_FIELDS)),
+            }
+        }
+        let ns = ns.ok_or_else(|| de::Error::missing_field("ns"))?;
+        let ticks = ns as u128 * precision.frequency() as u128 / 1_000_000_000;
+        result.elapsed = Elapsed::from_ticks(ticks as u64);
+        Ok(result)
+    }
+}
+
+/// Named values, such as counters, serialized as a map from their name to
+/// their value.
+struct NamedValues<'a, T>(&'a [(String, T)]);
+
+impl<T: Serialize> Serialize for NamedValues<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut m = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, value) in self.0 {
+            m.serialize_entry(name, value)?;
+        }
+        m.end()
+    }
+}
+
+const THROUGHPUT_FIELDS: &[&str] = &["volume", "unit", "value", "result", "others"];
+
+/// The throughput value (`as_f64()`) is included for convenience, and
+/// ignored when deserializing.
+impl Serialize for Throughput {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Throughput", THROUGHPUT_FIELDS.len())?;
+        s.serialize_field("volume", &self.volume)?;
+        s.serialize_field("unit", &self.unit)?;
+        s.serialize_field("value", &self.as_f64())?;
+        s.serialize_field("result", &self.result)?;
+        s.serialize_field("others", &self.others)?;
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Throughput {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct(
+            "Throughput",
+            THROUGHPUT_FIELDS,
+            StructVisitor::new("a throughput"),
+        )
+    }
+}
+
+impl FromMap for Throughput {
+    fn from_map<'de, A: MapAccess<'de>>(mut map: A) -> Result<Self, A::Error> {
+        let (mut volume, mut unit, mut result, mut others) = (None, Unit::None, None, Vec::new());
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "volume" => volume = Some(map.next_value()?),
+                "unit" => unit = map.next_value()?,
+                "value" => {
+                    map.next_value::<de::IgnoredAny>()?;
+                }
+                "result" => result = Some(map.next_value()?),
+                "others" => others = map.next_value()?,
+                other => return Err(de::Error::unknown_field(other, THROUGHPUT_FIELDS)),
+            }
+        }
+        Ok(Throughput {
+            volume: volume.ok_or_else(|| de::Error::missing_field("volume"))?,
+            result: result.ok_or_else(|| de::Error::missing_field("result"))?,
+            unit,
+            binary_prefixes: false,
+            others,
+        })
+    }
+}
+
+/// A type deserialized from the entries of a map.
+const RESOURCE_USAGE_FIELDS: &[&str] = &[
+    "minor_faults",
+    "major_faults",
+    "voluntary_switches",
+    "involuntary_switches",
+];
+
+impl Serialize for ResourceUsage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("ResourceUsage", RESOURCE_USAGE_FIELDS.len())?;
+        s.serialize_field("minor_faults", &self.minor_faults)?;
+        s.serialize_field("major_faults", &self.major_faults)?;
+        s.serialize_field("voluntary_switches", &self.voluntary_switches)?;
+        s.serialize_field("involuntary_switches", &self.involuntary_switches)?;
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ResourceUsage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct(
+            "ResourceUsage",
+            RESOURCE_USAGE_FIELDS,
+            StructVisitor::new("resource usage"),
+        )
+    }
+}
+
+impl FromMap for ResourceUsage {
+    fn from_map<'de, A: MapAccess<'de>>(mut map: A) -> Result<Self, A::Error> {
+        let mut usage = ResourceUsage::default();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "minor_faults" => usage.minor_faults = map.next_value()?,
+                "major_faults" => usage.major_faults = map.next_value()?,
+                "voluntary_switches" => usage.voluntary_switches = map.next_value()?,
+                "involuntary_switches" => usage.involuntary_switches = map.next_value()?,
+                other => return Err(de::Error::unknown_field(other, RESOURCE_USAGE_FIELDS)),
+            }
+        }
+        Ok(usage)
+    }
+}
+
+trait FromMap: Sized {
+    fn from_map<'de, A: MapAccess<'de>>(map: A) -> Result<Self, A::Error>;
+}
+
+struct StructVisitor<T> {
+    expecting: &'static str,
+    marker: PhantomData<T>,
+}
+
+impl<T> StructVisitor<T> {
+    fn new(expecting: &'static str) -> Self {
+        StructVisitor {
+            expecting,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, T: FromMap> Visitor<'de> for StructVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.expecting)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<T, A::Error> {
+        T::from_map(map)
+    }
+}
@@ -0,0 +1,172 @@
+//! A SQLite database of results.
+//!
+//! Only available with the `sqlite` feature. SQLite is built into the
+//! crate, so nothing needs to be installed.
+
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+
+use crate::Report;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    git_commit TEXT,
+    target TEXT,
+    cpu_model TEXT
+);
+CREATE TABLE IF NOT EXISTS benchmarks (
+    id INTEGER PRIMARY KEY,
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    name TEXT NOT NULL,
+    iterations INTEGER NOT NULL,
+    ns INTEGER NOT NULL,
+    rsd REAL
+);
+CREATE INDEX IF NOT EXISTS benchmarks_name ON benchmarks(name, run_id);
+CREATE TABLE IF NOT EXISTS samples (
+    benchmark_id INTEGER NOT NULL REFERENCES benchmarks(id),
+    position INTEGER NOT NULL,
+    ns INTEGER NOT NULL
+);
+";
+
+/// A result stored in a [`ResultsStore`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct StoredResult {
+    /// Identifier of the run the result belongs to.
+    pub run_id: i64,
+    /// Time of the run, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Git commit the benchmarks were built from, if known.
+    pub git_commit: Option<String>,
+    /// Number of iterations of a sample.
+    pub iterations: u64,
+    /// Duration of the fastest sample, in nanoseconds.
+    pub ns: u64,
+    /// Relative standard deviation of the samples, if available.
+    pub rsd: Option<f64>,
+}
+
+/// A SQLite database of results, with tables for runs, benchmarks and
+/// samples, so that a long history remains easy to query.
+#[derive(Debug)]
+pub struct ResultsStore {
+    connection: Connection,
+}
+
+impl ResultsStore {
+    /// Open a database such as `results.db`, creating it and its tables if
+    /// needed.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let connection = Connection::open(path).map_err(sql_error)?;
+        connection.execute_batch(SCHEMA).map_err(sql_error)?;
+        Ok(ResultsStore { connection })
+    }
+
+    /// Store the results of a report as a new run, and return its identifier.
+    pub fn insert(&self, report: &Report) -> io::Result<i64> {
+        let env = report.environment();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let transaction = self.connection.unchecked_transaction().map_err(sql_error)?;
+        transaction
+            .execute(
+                "INSERT INTO runs (timestamp, git_commit, target, cpu_model) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    timestamp as i64,
+                    env.git_commit,
+                    env.target,
+                    env.cpu_model
+                ],
+            )
+            .map_err(sql_error)?;
+        let run_id = transaction.last_insert_rowid();
+        {
+            let mut insert_benchmark = transaction
+                .prepare(
+                    "INSERT INTO benchmarks (run_id, name, iterations, ns, rsd) VALUES (?1, ?2, ?3, ?4, ?5)",
+                )
+                .map_err(sql_error)?;
+            let mut insert_sample = transaction
+                .prepare("INSERT INTO samples (benchmark_id, position, ns) VALUES (?1, ?2, ?3)")
+                .map_err(sql_error)?;
+            for (name, result) in report.sorted() {
+                insert_benchmark
+                    .execute(params![
+                        run_id,
+                        name,
+                        result.options.iterations as i64,
+                        result.as_ns() as i64,
+                        result.rsd()
+                    ])
+                    .map_err(sql_error)?;
+                let benchmark_id = transaction.last_insert_rowid();
+                for (position, sample) in result.samples().iter().enumerate() {
+                    insert_sample
+                        .execute(params![
+                            benchmark_id,
+                            position as i64,
+                            sample.as_nanos() as i64
+                        ])
+                        .map_err(sql_error)?;
+                }
+            }
+        }
+        transaction.commit().map_err(sql_error)?;
+        Ok(run_id)
+    }
+
+    /// Returns the results of the `n` latest runs of a benchmark, most
+    /// recent first.
+    pub fn latest(&self, name: &str, n: usize) -> io::Result<Vec<StoredResult>> {
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT runs.id, runs.timestamp, runs.git_commit, benchmarks.iterations, benchmarks.ns, benchmarks.rsd \
+                 FROM benchmarks JOIN runs ON runs.id = benchmarks.run_id \
+                 WHERE benchmarks.name = ?1 ORDER BY runs.id DESC LIMIT ?2",
+            )
+            .map_err(sql_error)?;
+        let rows = statement
+            .query_map(params![name, n as i64], |row| {
+                Ok(StoredResult {
+                    run_id: row.get(0)?,
+                    timestamp: row.get::<_, i64>(1)? as u64,
+                    git_commit: row.get(2)?,
+                    iterations: row.get::<_, i64>(3)? as u64,
+                    ns: row.get::<_, i64>(4)? as u64,
+                    rsd: row.get(5)?,
+                })
+            })
+            .map_err(sql_error)?;
+        rows.collect::<Result<_, _>>().map_err(sql_error)
+    }
+
+    /// Returns the sample durations of a benchmark in a run, in nanoseconds.
+    pub fn samples(&self, run_id: i64, name: &str) -> io::Result<Vec<u64>> {
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT samples.ns FROM samples JOIN benchmarks ON benchmarks.id = samples.benchmark_id \
+                 WHERE benchmarks.run_id = ?1 AND benchmarks.name = ?2 ORDER BY samples.position",
+            )
+            .map_err(sql_error)?;
+        let rows = statement
+            .query_map(
+                params![run_id, name],
+                |row| Ok(row.get::<_, i64>(0)? as u64),
+            )
+            .map_err(sql_error)?;
+        rows.collect::<Result<_, _>>().map_err(sql_error)
+    }
+}
+
+fn sql_error(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
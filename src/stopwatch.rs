@@ -0,0 +1,131 @@
+//! Manual timing of work spread across calls.
+
+use std::panic::Location;
+use std::sync::Arc;
+use std::time::Duration;
+
+use precision::{Elapsed, Timestamp};
+
+use crate::{stats, Bench, BenchResult, Options};
+
+/// A stopwatch accumulating the time between calls to [`Stopwatch::start()`]
+/// and [`Stopwatch::stop()`], returned by [`Bench::stopwatch()`].
+///
+/// This times work that can't be wrapped into a closure, such as the time
+/// spent in a handler across the turns of an event loop.
+///
+/// ```rust
+/// use benchmark_simple::*;
+///
+/// let bench = Bench::new();
+/// let mut stopwatch = bench.stopwatch();
+/// for event in 0..1000u64 {
+///     stopwatch.start();
+///     let _ = black_box(event).to_string();
+///     stopwatch.stop();
+/// }
+/// assert_eq!(stopwatch.laps(), 1000);
+/// let res = BenchResult::from(stopwatch);
+/// println!("{} per event", res.per_iteration_duration().as_nanos());
+/// ```
+#[derive(Clone)]
+pub struct Stopwatch {
+    bench: Bench,
+    location: &'static Location<'static>,
+    start: Option<Timestamp>,
+    laps: Vec<Elapsed>,
+}
+
+impl Stopwatch {
+    /// Start a lap. This does nothing if the stopwatch is already running.
+    pub fn start(&mut self) {
+        if self.start.is_none() {
+            self.start = Some(self.bench.precision.now());
+        }
+    }
+
+    /// End the current lap, and return its duration. This returns zero if
+    /// the stopwatch wasn't running.
+    pub fn stop(&mut self) -> Duration {
+        let start = match self.start.take() {
+            Some(start) => start,
+            None => return Duration::ZERO,
+        };
+        let elapsed = self.bench.precision.now() - start;
+        self.laps.push(elapsed);
+        self.as_duration(elapsed)
+    }
+
+    /// Returns `true` if a lap has been started and not stopped yet.
+    pub fn is_running(&self) -> bool {
+        self.start.is_some()
+    }
+
+    /// Returns the number of completed laps.
+    pub fn laps(&self) -> usize {
+        self.laps.len()
+    }
+
+    /// Returns the total duration of the completed laps.
+    pub fn accumulated(&self) -> Duration {
+        self.as_duration(self.total())
+    }
+
+    /// Clear the laps, and stop the stopwatch.
+    pub fn reset(&mut self) {
+        self.start = None;
+        self.laps.clear();
+    }
+
+    fn total(&self) -> Elapsed {
+        Elapsed::from_ticks(self.laps.iter().map(|elapsed| elapsed.ticks()).sum())
+    }
+
+    fn as_duration(&self, elapsed: Elapsed) -> Duration {
+        Duration::from_nanos(self.bench.precision.as_ns(elapsed))
+    }
+}
+
+impl From<Stopwatch> for BenchResult {
+    /// A result with the accumulated time of all the laps, each lap being an
+    /// iteration. The samples are the durations of the individual laps. A
+    /// running lap is stopped first.
+    fn from(mut stopwatch: Stopwatch) -> BenchResult {
+        stopwatch.stop();
+        let bench = &stopwatch.bench;
+        let options = Options {
+            iterations: (stopwatch.laps.len() as u64).max(1),
+            min_samples: 1,
+            max_samples: 1,
+            ..Options::base()
+        };
+        let secs: Vec<f64> = stopwatch
+            .laps
+            .iter()
+            .map(|elapsed| bench.precision.as_secs_f64(*elapsed))
+            .collect();
+        let mut result = bench.result(stopwatch.total(), Arc::new(options), stopwatch.location);
+        result.rsd = stats::rsd(&secs);
+        result.samples = stopwatch
+            .laps
+            .iter()
+            .map(|&elapsed| stopwatch.as_duration(elapsed))
+            .collect();
+        result
+    }
+}
+
+impl Bench {
+    /// Returns a stopped [`Stopwatch`].
+    ///
+    /// The caller's source location is recorded in the result.
+    #[track_caller]
+    pub fn stopwatch(&self) -> Stopwatch {
+        Stopwatch {
+            bench: self.clone(),
+            location: Location::caller(),
+            start: None,
+            laps: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,844 @@
+//! Suites of benchmarks, run together.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe, Location};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "toml")]
+use crate::config::OptionsConfig;
+use crate::data::Rng;
+use crate::filter::Filter;
+use crate::isolation;
+use crate::watchdog::{self, SharedFn};
+use precision::Elapsed;
+
+use crate::{
+    black_box_ref, is_interrupted, stats, Bench, BenchResult, Environment, Options, Report, Run,
+    RunConfig, StopReason,
+};
+
+struct Benchmark<'a> {
+    name: String,
+    tags: Vec<String>,
+    f: Box<dyn FnMut() + 'a>,
+    /// The function, if it can run on a monitored worker thread.
+    monitored: Option<SharedFn>,
+    location: &'static Location<'static>,
+    options: Option<Options>,
+}
+
+impl Benchmark<'_> {
+    /// Run the benchmark, on a monitored worker thread if it has one.
+    fn run(&mut self, bench: &Bench, options: &Options) -> BenchResult {
+        match &self.monitored {
+            Some(f) => bench.run_monitored_at(options, Some(&self.name), self.location, f),
+            None => bench.run_named_at(options, Some(&self.name), self.location, &mut self.f),
+        }
+    }
+}
+
+/// What to do when a benchmark is registered with a name already in use.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DuplicateNames {
+    /// Panic, reporting both registrations.
+    #[default]
+    Panic,
+    /// Prefix the name with the name of the file it was registered from
+    /// (`file/name`), and add a number if it is still not unique (`name #2`).
+    Namespace,
+}
+
+/// A collection of benchmarks sharing the same options.
+pub struct Suite<'a> {
+    bench: Bench,
+    options: Options,
+    total_budget: Option<Duration>,
+    filter: Option<Filter>,
+    duplicate_names: DuplicateNames,
+    isolate: bool,
+    catch_panics: bool,
+    calibrate: bool,
+    processes: usize,
+    shuffle_seed: Option<u64>,
+    benchmarks: Vec<Benchmark<'a>>,
+    #[cfg(feature = "toml")]
+    config: Option<OptionsConfig>,
+}
+
+impl<'a> Suite<'a> {
+    /// Create a new, empty suite.
+    ///
+    /// If the `BENCHMARK_FILTER` environment variable is set, it is used as
+    /// a filter. See [`Suite::filter`].
+    pub fn new(options: Options) -> Self {
+        Suite {
+            bench: Bench::new(),
+            options,
+            total_budget: None,
+            filter: std::env::var("BENCHMARK_FILTER")
+                .ok()
+                .map(|pattern| Filter::new(&pattern)),
+            duplicate_names: DuplicateNames::default(),
+            isolate: false,
+            catch_panics: false,
+            calibrate: false,
+            processes: 1,
+            shuffle_seed: None,
+            benchmarks: Vec::new(),
+            #[cfg(feature = "toml")]
+            config: None,
+        }
+    }
+
+    /// Create a new, empty suite, using the options and the filter of a
+    /// validated run configuration.
+    pub fn from_run_config(config: &RunConfig) -> Self {
+        #[cfg(feature = "toml")]
+        let mut suite = match &config.config {
+            Some(options_config) => Suite::with_config(options_config.clone()),
+            None => Suite::new(config.options.clone()),
+        };
+        #[cfg(not(feature = "toml"))]
+        let mut suite = Suite::new(config.options.clone());
+        if config.filter.is_some() {
+            suite.filter = config.filter.clone();
+        }
+        suite.shuffle_seed = config.shuffle_seed;
+        suite.calibrate = config.calibrate;
+        #[cfg(all(feature = "profiling", unix))]
+        if let Some(dir) = &config.flamegraphs {
+            suite.set_flamegraphs(dir.clone());
+        }
+        suite
+    }
+
+    /// Create a new, empty suite, using options from a configuration.
+    ///
+    /// Benchmarks with a section in the configuration use their own options.
+    #[cfg(feature = "toml")]
+    pub fn with_config(config: OptionsConfig) -> Self {
+        let mut suite = Suite::new(config.defaults());
+        suite.config = Some(config);
+        suite
+    }
+
+    /// Register a benchmark.
+    #[track_caller]
+    pub fn register<F, G>(&mut self, name: impl Into<String>, mut f: F) -> &mut Self
+    where
+        F: FnMut() -> G + 'a,
+    {
+        let location = Location::caller();
+        let name = self.unique_name(name.into(), location);
+        #[cfg(feature = "toml")]
+        let options = self
+            .config
+            .as_ref()
+            .map(|config| config.for_benchmark(&name));
+        #[cfg(not(feature = "toml"))]
+        let options = None;
+        self.benchmarks.push(Benchmark {
+            name,
+            tags: Vec::new(),
+            f: Box::new(move || {
+                black_box_ref(&f());
+            }),
+            monitored: None,
+            location,
+            options,
+        });
+        self
+    }
+
+    /// Register a benchmark that runs on a worker thread, watched by the
+    /// caller, when it has a `sample_timeout`.
+    ///
+    /// If a sample doesn't complete in time, the benchmark is flagged as
+    /// timed out and the suite moves on to the next one, leaving the worker
+    /// thread behind. See [`Bench::run_monitored()`].
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    /// use std::time::Duration;
+    ///
+    /// let options = Options::quick()
+    ///     .into_builder()
+    ///     .iterations(1)
+    ///     .warmup_iterations(0)
+    ///     .sample_timeout(Duration::from_millis(50))
+    ///     .build()
+    ///     .unwrap();
+    /// let mut suite = Suite::new(options);
+    /// suite.register_monitored("hang", || std::thread::sleep(Duration::from_secs(3600)));
+    /// suite.register_monitored("sum", || (0..100u64).sum::<u64>());
+    /// let report = suite.run();
+    /// assert!(report.get("hang").unwrap().sample_timed_out());
+    /// assert!(!report.get("sum").unwrap().sample_timed_out());
+    /// ```
+    #[track_caller]
+    pub fn register_monitored<F, G>(&mut self, name: impl Into<String>, f: F) -> &mut Self
+    where
+        F: FnMut() -> G + Send + 'static,
+    {
+        let f = watchdog::shared_fn(f);
+        let monitored = f.clone();
+        self.register(name, move || (watchdog::lock_fn(&f))());
+        if let Some(benchmark) = self.benchmarks.last_mut() {
+            benchmark.monitored = Some(monitored);
+        }
+        self
+    }
+
+    /// Set what to do when a benchmark is registered with a name already in
+    /// use. By default, registering a name twice panics, so that results
+    /// can't be mixed up.
+    pub fn duplicate_names(&mut self, policy: DuplicateNames) -> &mut Self {
+        self.duplicate_names = policy;
+        self
+    }
+
+    fn unique_name(&self, name: String, location: &'static Location<'static>) -> String {
+        let existing = |name: &str| {
+            self.benchmarks
+                .iter()
+                .find(|benchmark| benchmark.name == name)
+        };
+        let previous = match existing(&name) {
+            Some(previous) => previous,
+            None => return name,
+        };
+        if self.duplicate_names == DuplicateNames::Panic {
+            panic!(
+                "Benchmark [{}] registered at {} is already registered at {}",
+                name, location, previous.location
+            );
+        }
+        let file = std::path::Path::new(location.file())
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let namespaced = format!("{}/{}", file, name);
+        if existing(&namespaced).is_none() {
+            return namespaced;
+        }
+        (2..)
+            .map(|n| format!("{} #{}", namespaced, n))
+            .find(|name| existing(name).is_none())
+            .unwrap()
+    }
+
+    /// Register a benchmark once per named input distribution.
+    ///
+    /// Each distribution (e.g. `uniform`, `sorted`, `adversarial`) becomes a
+    /// benchmark named `name/distribution`, so that reports show the results
+    /// side by side, grouped under `name`.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// let mut suite = Suite::new(Options::default());
+    /// let sorted: Vec<u32> = (0..1000).collect();
+    /// let reversed: Vec<u32> = sorted.iter().rev().copied().collect();
+    /// suite.register_with_inputs(
+    ///     "sort",
+    ///     [("sorted", sorted), ("reversed", reversed)],
+    ///     |v| {
+    ///         let mut v = v.clone();
+    ///         v.sort_unstable();
+    ///         v
+    ///     },
+    /// );
+    /// ```
+    #[track_caller]
+    pub fn register_with_inputs<N, I, F, G>(
+        &mut self,
+        name: impl Into<String>,
+        inputs: impl IntoIterator<Item = (N, I)>,
+        f: F,
+    ) -> &mut Self
+    where
+        N: Into<String>,
+        I: 'a,
+        F: FnMut(&I) -> G + 'a,
+    {
+        let name = name.into();
+        let f = Rc::new(RefCell::new(f));
+        for (distribution, input) in inputs {
+            let f = f.clone();
+            self.register(format!("{}/{}", name, distribution.into()), move || {
+                (f.borrow_mut())(&input)
+            });
+        }
+        self
+    }
+
+    /// Register all the functions annotated with `#[benchmark]`, sorted by name.
+    #[cfg(feature = "macros")]
+    pub fn register_annotated(&mut self) -> &mut Self {
+        let mut registrations: Vec<_> = inventory::iter::<crate::__private::Registration>
+            .into_iter()
+            .collect();
+        registrations.sort_by_key(|registration| registration.name);
+        for registration in registrations {
+            (registration.register)(self);
+        }
+        self
+    }
+
+    /// Set where verbose output is written to. The default is the standard output.
+    pub fn set_output(&mut self, output: impl std::io::Write + Send + 'static) -> &mut Self {
+        self.bench.set_output(output);
+        self
+    }
+
+    /// Emit progress events as newline-delimited JSON objects.
+    /// See [`Bench::set_events`].
+    pub fn set_events(&mut self, events: impl std::io::Write + Send + 'static) -> &mut Self {
+        self.bench.set_events(events);
+        self
+    }
+
+    /// Set a reporter, notified as benchmarks run. See [`Bench::set_reporter`].
+    pub fn set_reporter(&mut self, reporter: impl crate::Reporter + Send + 'static) -> &mut Self {
+        self.bench.set_reporter(reporter);
+        self
+    }
+
+    /// Add a profiler, started and stopped around the measured samples of
+    /// every benchmark. See [`Bench::with_profiler`].
+    pub fn add_profiler(&mut self, profiler: impl crate::Profiler + Send + 'static) -> &mut Self {
+        self.bench.add_profiler(profiler);
+        self
+    }
+
+    /// Save a flamegraph of every benchmark in `dir`. See
+    /// [`Bench::set_flamegraphs`].
+    ///
+    /// Only available with the `profiling` feature, on Unix systems.
+    #[cfg(all(feature = "profiling", unix))]
+    pub fn set_flamegraphs(&mut self, dir: impl Into<std::path::PathBuf>) -> &mut Self {
+        self.bench.set_flamegraphs(dir);
+        self
+    }
+
+    /// Set a time budget for the whole suite.
+    ///
+    /// The remaining budget is evenly divided among the benchmarks that still
+    /// have to run, lowering their `max_duration` accordingly. Time left over
+    /// by fast benchmarks is given to the following ones.
+    pub fn total_budget(&mut self, budget: Duration) -> &mut Self {
+        self.total_budget = Some(budget);
+        self
+    }
+
+    /// Run every benchmark in a fresh child process, so that allocator
+    /// state, caches and lazily initialized statics left by earlier
+    /// benchmarks don't affect the following ones.
+    ///
+    /// The current executable is spawned again with the same arguments, and
+    /// has to register the same benchmarks, as executables defined with
+    /// [`main!`](crate::main) do. A benchmark whose child process fails is
+    /// reported on the standard error, and listed among the
+    /// [`failures`](Report::failures) of the report instead of its results.
+    pub fn isolate(&mut self, isolate: bool) -> &mut Self {
+        self.isolate = isolate;
+        self
+    }
+
+    /// Catch the panics of benchmarks, so that one broken benchmark doesn't
+    /// stop the whole suite: a benchmark that panics is listed among the
+    /// [`failures`](Report::failures) of the report, and the following ones
+    /// still run.
+    ///
+    /// This applies to [`Suite::run()`]. The panic message is still printed
+    /// by the panic hook, and this has no effect if panics abort.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// let mut suite = Suite::new(Options::quick());
+    /// suite
+    ///     .catch_panics(true)
+    ///     .register("broken", || -> u64 { panic!("not implemented") })
+    ///     .register("sum", || (0..1000u64).sum::<u64>());
+    /// let report = suite.run();
+    /// assert!(report.get("sum").is_some());
+    /// assert_eq!(report.failures().next(), Some(("broken", "not implemented")));
+    /// ```
+    pub fn catch_panics(&mut self, catch_panics: bool) -> &mut Self {
+        self.catch_panics = catch_panics;
+        self
+    }
+
+    /// Measure the [calibration score](Bench::calibration_score) of the
+    /// machine before running the benchmarks, and store it in the
+    /// environment of the report, so that results from different machines
+    /// can be compared with [`BaselineDiff::normalized()`](crate::BaselineDiff::normalized).
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// let mut suite = Suite::new(Options::quick());
+    /// suite.calibrate(true).register("sum", || (0..1000u64).sum::<u64>());
+    /// let report = suite.run();
+    /// assert!(report.environment().calibration_score.unwrap() > 0.0);
+    /// ```
+    pub fn calibrate(&mut self, calibrate: bool) -> &mut Self {
+        self.calibrate = calibrate;
+        self
+    }
+
+    /// Returns the environment of a report, with the calibration score of
+    /// the machine if requested.
+    fn environment(&self) -> Environment {
+        let mut environment = Environment::detect();
+        if self.calibrate {
+            environment.calibration_score = Some(Bench::new().calibration_score());
+        }
+        environment
+    }
+
+    /// Run every benchmark in `processes` fresh child processes, and
+    /// aggregate their results.
+    ///
+    /// Every process gets a different address space and code layout, which
+    /// can make a benchmark faster or slower by a few percent. The median of
+    /// the results of the processes is reported, and
+    /// [`BenchResult::process_rsd`](crate::BenchResult::process_rsd) returns
+    /// how much they differed. `max_duration` is shared by the processes.
+    ///
+    /// This implies [`Suite::isolate`].
+    pub fn processes(&mut self, processes: usize) -> &mut Self {
+        self.processes = processes.max(1);
+        self
+    }
+
+    /// Run the benchmarks in a random order, so that drift during the run,
+    /// such as a machine getting hotter, doesn't always penalize the same
+    /// benchmarks.
+    ///
+    /// The order is derived from `seed`, or from a random seed if it is
+    /// `None`. The seed is printed when the suite runs, so that an order
+    /// can be reproduced.
+    pub fn shuffle(&mut self, seed: Option<u64>) -> &mut Self {
+        self.shuffle_seed = Some(seed.unwrap_or_else(random_seed));
+        self
+    }
+
+    /// Add a tag to the last registered benchmark.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    ///
+    /// let mut suite = Suite::new(Options::default());
+    /// suite.register("sum", || (0..1000u64).sum::<u64>()).tag("fast");
+    /// ```
+    pub fn tag(&mut self, tag: impl Into<String>) -> &mut Self {
+        if let Some(benchmark) = self.benchmarks.last_mut() {
+            benchmark.tags.push(tag.into());
+        }
+        self
+    }
+
+    /// Override options of the last registered benchmark, starting from the
+    /// options it would otherwise use: the suite options, or its section of
+    /// the configuration file.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    /// use std::time::Duration;
+    ///
+    /// let mut suite = Suite::new(Options::default());
+    /// suite
+    ///     .register("add", || 1u64 + 1)
+    ///     .override_options(|options| options.iterations = 100_000);
+    /// suite
+    ///     .register("sleep", || std::thread::sleep(Duration::from_millis(1)))
+    ///     .override_options(|options| options.max_duration = Some(Duration::from_secs(10)));
+    /// ```
+    pub fn override_options(&mut self, f: impl FnOnce(&mut Options)) -> &mut Self {
+        let suite_options = &self.options;
+        if let Some(benchmark) = self.benchmarks.last_mut() {
+            f(benchmark
+                .options
+                .get_or_insert_with(|| suite_options.clone()));
+        }
+        self
+    }
+
+    /// Only run the benchmarks matching `pattern`.
+    ///
+    /// `tag:<tag>` selects the benchmarks with that tag, and `group:<group>`
+    /// the benchmarks whose hierarchical name is below `group`, such as
+    /// `group:aead/aes256gcm` for `aead/aes256gcm/encrypt/16384`. Other
+    /// patterns select the benchmarks whose name contains them or, with the `regex` feature,
+    /// whose name matches them as a regular expression.
+    pub fn filter(&mut self, pattern: impl AsRef<str>) -> &mut Self {
+        self.filter = Some(Filter::new(pattern.as_ref()));
+        self
+    }
+
+    /// Run all the registered benchmarks, returning a report by `deadline`.
+    ///
+    /// The time left until the deadline is shared among the benchmarks, as
+    /// with [`Suite::total_budget`]. Every benchmark collects at least one
+    /// sample, so that the report is complete even if it is late; results
+    /// with few samples are less reliable, which
+    /// [`BenchResult::margin_of_error`](crate::BenchResult::margin_of_error) tells.
+    pub fn run_deadline(&mut self, deadline: Instant) -> Report {
+        let total_budget = self.total_budget;
+        self.total_budget = Some(deadline.saturating_duration_since(Instant::now()));
+        let report = self.run();
+        self.total_budget = total_budget;
+        report
+    }
+
+    /// Run all the registered benchmarks.
+    pub fn run(&mut self) -> Report {
+        let suite = isolation::next_suite();
+        if let Some((name, child_suite, max_duration)) = isolation::child_benchmark() {
+            if child_suite.is_none_or(|child_suite| child_suite == suite) {
+                self.run_child(&name, max_duration);
+            }
+            // Another suite of the executable runs it.
+            return Report::new(Environment::detect());
+        }
+        let mut report = Report::new(self.environment());
+        let start = self.bench.precision.now();
+        let mut selected = select(&mut self.benchmarks, self.filter.as_ref());
+        if let Some(seed) = self.shuffle_seed {
+            eprintln!("Running the benchmarks in a random order (seed: {}).", seed);
+            shuffle(&mut selected, seed);
+        }
+        let count = selected.len();
+        let suite_options = &self.options;
+        for (i, benchmark) in selected.iter_mut().enumerate() {
+            if is_interrupted() {
+                break;
+            }
+            let mut options = benchmark
+                .options
+                .clone()
+                .unwrap_or_else(|| suite_options.clone());
+            if let Some(total_budget) = self.total_budget {
+                let remaining = total_budget.saturating_sub(self.bench.elapsed_since(start));
+                let share = remaining / (count - i) as u32;
+                options.max_duration = Some(match options.max_duration {
+                    Some(max_duration) => max_duration.min(share),
+                    None => share,
+                });
+            }
+            if options.verbose {
+                log_info!(self.bench, "Running {}.", benchmark.name);
+            }
+            let result = if self.processes > 1 {
+                if let Some(max_duration) = options.max_duration {
+                    options.max_duration = Some(max_duration / self.processes as u32);
+                }
+                let mut results = Vec::with_capacity(self.processes);
+                let mut error = None;
+                for process in 1..=self.processes {
+                    if is_interrupted() {
+                        break;
+                    }
+                    match self.bench.run_isolated(
+                        &benchmark.name,
+                        suite,
+                        &options,
+                        benchmark.location,
+                    ) {
+                        Ok(result) => {
+                            if options.verbose {
+                                log!(self.bench, "Process {}: {}", process, result);
+                            }
+                            results.push(result);
+                        }
+                        Err(e) => {
+                            eprintln!(
+                                "Benchmark [{}] failed in process {}: {}",
+                                benchmark.name, process, e
+                            );
+                            error = Some(e);
+                        }
+                    }
+                }
+                if results.is_empty() {
+                    if let Some(e) = error {
+                        report.push_failure(benchmark.name.clone(), e);
+                    }
+                    continue;
+                }
+                isolation::aggregate(results)
+            } else if self.isolate {
+                match self
+                    .bench
+                    .run_isolated(&benchmark.name, suite, &options, benchmark.location)
+                {
+                    Ok(result) => result,
+                    Err(e) => {
+                        eprintln!("Benchmark [{}] failed: {}", benchmark.name, e);
+                        report.push_failure(benchmark.name.clone(), e);
+                        continue;
+                    }
+                }
+            } else {
+                let bench = &self.bench;
+                let mut run = || benchmark.run(bench, &options);
+                if !self.catch_panics {
+                    run()
+                } else {
+                    match panic::catch_unwind(AssertUnwindSafe(run)) {
+                        Ok(result) => result,
+                        Err(payload) => {
+                            report.push_failure(benchmark.name.clone(), panic_message(&*payload));
+                            continue;
+                        }
+                    }
+                }
+            };
+            report.push(benchmark.name.clone(), result);
+        }
+        report.wall_time = Some(self.bench.elapsed_since(start));
+        report
+    }
+
+    /// Run a single benchmark in a child process, send its result to the
+    /// parent, and exit.
+    ///
+    /// The child exits with an error if the benchmark isn't registered, such
+    /// as when benchmarks are registered conditionally.
+    fn run_child(&mut self, name: &str, max_duration: Option<Duration>) -> ! {
+        let suite_options = &self.options;
+        let benchmark = match self
+            .benchmarks
+            .iter_mut()
+            .find(|benchmark| benchmark.name == name)
+        {
+            Some(benchmark) => benchmark,
+            None => {
+                eprintln!(
+                    "Benchmark [{}] isn't registered in the child process: isolated benchmarks have to be registered the same way on every run of the executable.",
+                    name
+                );
+                std::process::exit(2);
+            }
+        };
+        let mut options = benchmark
+            .options
+            .clone()
+            .unwrap_or_else(|| suite_options.clone());
+        if max_duration.is_some() {
+            options.max_duration = max_duration;
+        }
+        let result = benchmark.run(&self.bench, &options);
+        isolation::send(&result);
+        std::process::exit(0);
+    }
+}
+
+impl Suite<'_> {
+    /// Run the registered benchmarks as a race between variants, and return
+    /// their results.
+    ///
+    /// Instead of giving every benchmark the same time, samples are collected
+    /// in rounds, and a variant stops being sampled once it is clearly faster
+    /// or slower than all the others (its confidence interval doesn't overlap
+    /// theirs), once its RSD is below `max_rsd`, or after `max_samples`
+    /// samples. Samples are spent on the comparisons that are still
+    /// ambiguous, so that comparing many variants finishes faster.
+    ///
+    /// All the variants use the options of the suite, and the suite budget,
+    /// or `max_duration`, bounds the whole race. Apart from being
+    /// interleaved, samples are collected as by [`Suite::run`], so that all
+    /// the options, reporters and profilers apply, and the
+    /// [`stop_reason`](crate::BenchResult::stop_reason) of a variant that
+    /// settled because it was separated from the others is
+    /// [`StopReason::Rule`].
+    pub fn race(&mut self) -> Report {
+        self.run_rounds(true)
+    }
+
+    /// Run the registered benchmarks in rounds, taking one sample of every
+    /// benchmark per round, and return their results.
+    ///
+    /// Noise from the machine, such as another process waking up or the CPU
+    /// getting hotter, is spread evenly across the benchmarks, instead of
+    /// affecting the ones that happened to run at that time, which makes
+    /// comparisons between the benchmarks of a run fairer.
+    ///
+    /// Every benchmark uses its own options, and stops being sampled once its
+    /// RSD is below `max_rsd` after `min_samples` samples, or after
+    /// `max_samples` samples. The suite budget, or the `max_duration` of the
+    /// suite options, bounds the whole run. Apart from being interleaved,
+    /// samples are collected as by [`Suite::run`].
+    pub fn run_round_robin(&mut self) -> Report {
+        self.run_rounds(false)
+    }
+
+    /// Collect samples in rounds, with a sampling engine per benchmark. In a
+    /// race, all the benchmarks use the suite options, and a benchmark also
+    /// settles once it is separated from all the others.
+    fn run_rounds(&mut self, race: bool) -> Report {
+        let mut report = Report::new(self.environment());
+        let suite_start = self.bench.precision.now();
+        let suite_options = &self.options;
+        let budget = self.total_budget.or(suite_options.max_duration);
+        let bench = &self.bench;
+        let mut selected = select(&mut self.benchmarks, self.filter.as_ref());
+        let options: Vec<Options> = selected
+            .iter()
+            .map(|benchmark| {
+                let mut options = match &benchmark.options {
+                    Some(options) if !race => options.clone(),
+                    _ => suite_options.clone(),
+                };
+                // The benchmarks are sampled at the same time, so each of
+                // them gets the whole budget.
+                if let Some(budget) = budget {
+                    options.max_duration =
+                        Some(options.max_duration.map_or(budget, |d| d.min(budget)));
+                }
+                options
+            })
+            .collect();
+        if suite_options.dry_run {
+            for (benchmark, options) in selected.into_iter().zip(options) {
+                (benchmark.f)();
+                let result = bench.result(
+                    Elapsed::from_ticks(0),
+                    Arc::new(options),
+                    benchmark.location,
+                );
+                report.push(benchmark.name.clone(), result);
+            }
+            return report;
+        }
+        let count = selected.len();
+        let cancelled = || false;
+        let mut runs: Vec<Run<'_>> = selected
+            .iter_mut()
+            .zip(&options)
+            .map(|(benchmark, options)| {
+                let name = Some(benchmark.name.as_str());
+                Run::new(
+                    bench,
+                    options,
+                    name,
+                    benchmark.location,
+                    true,
+                    &cancelled,
+                    &mut benchmark.f,
+                )
+            })
+            .collect();
+        let _priority = bench.elevate_priority(suite_options.priority);
+        let mut engines: Vec<_> = runs.iter_mut().map(|run| run.engine(&cancelled)).collect();
+        let mut samplings: Vec<_> = engines
+            .iter_mut()
+            .zip(selected.iter_mut())
+            .map(|(engine, benchmark)| engine.start_at(benchmark.location, &mut benchmark.f))
+            .collect();
+        while samplings
+            .iter()
+            .any(|sampling| sampling.stop_reason().is_none())
+        {
+            for (sampling, benchmark) in samplings.iter_mut().zip(selected.iter_mut()) {
+                sampling.sample(&mut benchmark.f);
+            }
+            if !race {
+                continue;
+            }
+            // A variant clearly faster or slower than all the others settles.
+            let intervals: Vec<_> = samplings
+                .iter()
+                .map(|sampling| confidence_interval(&secs(sampling.samples())))
+                .collect();
+            for (i, sampling) in samplings.iter_mut().enumerate() {
+                if sampling.samples().len() < std::cmp::max(2, suite_options.min_samples) {
+                    continue;
+                }
+                let (low, high) = intervals[i];
+                if (0..count)
+                    .filter(|&j| j != i)
+                    .all(|j| intervals[j].1 < low || intervals[j].0 > high)
+                {
+                    sampling.stop(StopReason::Rule);
+                }
+            }
+        }
+        let samplings: Vec<_> = samplings
+            .into_iter()
+            .map(|sampling| sampling.finish())
+            .collect();
+        drop(engines);
+        for ((run, sampling), benchmark) in runs.into_iter().zip(samplings).zip(selected) {
+            report.push(benchmark.name.clone(), run.finish(sampling));
+        }
+        report.wall_time = Some(bench.elapsed_since(suite_start));
+        report
+    }
+}
+
+/// Returns the benchmarks matching `filter`.
+///
+/// If a filter doesn't match any benchmarks, the closest names are suggested.
+fn select<'s, 'a>(
+    benchmarks: &'s mut [Benchmark<'a>],
+    filter: Option<&Filter>,
+) -> Vec<&'s mut Benchmark<'a>> {
+    if let Some(filter) = filter {
+        let all = benchmarks
+            .iter()
+            .map(|benchmark| (benchmark.name.as_str(), benchmark.tags.as_slice()));
+        if !benchmarks.is_empty() && !all.clone().any(|(name, tags)| filter.matches(name, tags)) {
+            eprintln!("{}", filter.no_match_message(all));
+        }
+    }
+    benchmarks
+        .iter_mut()
+        .filter(|benchmark| {
+            filter.is_none_or(|filter| filter.matches(&benchmark.name, &benchmark.tags))
+        })
+        .collect()
+}
+
+/// Shuffle `items` in an order derived from `seed` (Fisher-Yates).
+fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut rng = Rng::new(seed);
+    for i in (1..items.len()).rev() {
+        let j = rng.below(i as u64 + 1) as usize;
+        items.swap(i, j);
+    }
+}
+
+/// Returns a seed that differs between runs.
+pub(crate) fn random_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64)
+}
+
+fn secs(samples: &[Duration]) -> Vec<f64> {
+    samples.iter().map(Duration::as_secs_f64).collect()
+}
+
+/// Returns an approximate 95% confidence interval for the mean of `xs`.
+fn confidence_interval(xs: &[f64]) -> (f64, f64) {
+    let (mean, variance) = stats::mean_variance(xs);
+    let half_width = 1.96 * (variance / xs.len() as f64).sqrt();
+    (mean - half_width, mean + half_width)
+}
+
+/// Returns the message of a panic.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "panicked".to_string(),
+        },
+    }
+}
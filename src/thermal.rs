@@ -0,0 +1,48 @@
+//! Detection of thermal throttling between samples.
+//!
+//! A short reference kernel is timed around every sample. As the kernel
+//! always performs the same work, a slower run than the fastest one seen so
+//! far means that the CPU is running at a lower frequency.
+
+use crate::black_box;
+use crate::timer::Timer;
+
+/// How much slower than the fastest run the reference kernel has to be for
+/// the CPU to be considered throttled.
+const SLOWDOWN_THRESHOLD: f64 = 1.10;
+
+/// Times a reference kernel to detect frequency drops.
+pub(crate) struct ThrottlingDetector {
+    precision: Timer,
+    fastest: Option<u64>,
+}
+
+impl ThrottlingDetector {
+    pub(crate) fn new(precision: Timer) -> Self {
+        ThrottlingDetector {
+            precision,
+            fastest: None,
+        }
+    }
+
+    /// Run the reference kernel, and return `true` if it was significantly
+    /// slower than its fastest run.
+    pub(crate) fn probe(&mut self) -> bool {
+        let start = self.precision.now();
+        reference_kernel();
+        let ns = self.precision.as_ns(self.precision.now() - start);
+        let fastest = *self.fastest.get_or_insert(ns);
+        if ns < fastest {
+            self.fastest = Some(ns);
+        }
+        ns as f64 > fastest as f64 * SLOWDOWN_THRESHOLD
+    }
+}
+
+/// A fixed amount of integer work, that can't be optimized away.
+fn reference_kernel() {
+    let mut x = 0u64;
+    for i in 0..10_000 {
+        x = black_box(x.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(i));
+    }
+}
@@ -0,0 +1,145 @@
+//! Benchmarks timed with a caller-supplied tick counter.
+//!
+//! This only requires `core`, so that benchmarks can run where `std` is not
+//! available, such as in kernels, enclaves or firmware.
+
+use core::fmt::{self, Display, Formatter};
+
+use crate::black_box_ref;
+
+/// Options of a [`TickBench`].
+#[derive(Clone, Copy, Debug)]
+pub struct TickOptions {
+    /// Number of iterations of a sample.
+    pub iterations: u64,
+    /// Number of warm-up iterations to perform.
+    pub warmup_iterations: u64,
+    /// Number of samples to collect.
+    pub samples: usize,
+}
+
+impl Default for TickOptions {
+    fn default() -> Self {
+        TickOptions {
+            iterations: 1,
+            warmup_iterations: 0,
+            samples: 5,
+        }
+    }
+}
+
+/// A benchmarking environment reading time from a tick counter, such as a
+/// cycle counter, instead of the timer of the operating system.
+///
+/// The counter is a function returning the current number of ticks, and
+/// `frequency` is the number of ticks per second. Elapsed ticks are computed
+/// with a wrapping subtraction.
+///
+/// ```rust
+/// use benchmark_simple::*;
+/// use std::time::Instant;
+///
+/// let origin = Instant::now();
+/// let mut bench = TickBench::new(|| origin.elapsed().as_nanos() as u64, 1_000_000_000);
+/// let options = TickOptions {
+///     iterations: 100,
+///     ..Default::default()
+/// };
+/// let res = bench.run(&options, || (0..100u64).sum::<u64>());
+/// println!("result: {}", res);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TickBench<T> {
+    ticks: T,
+    frequency: u64,
+}
+
+impl<T: FnMut() -> u64> TickBench<T> {
+    /// Create a benchmarking environment from a tick counter and its
+    /// frequency, in ticks per second.
+    pub fn new(ticks: T, frequency: u64) -> Self {
+        assert!(frequency > 0, "the frequency must be at least 1 Hz");
+        TickBench { ticks, frequency }
+    }
+
+    /// Returns the frequency of the tick counter, in ticks per second.
+    pub fn frequency(&self) -> u64 {
+        self.frequency
+    }
+
+    /// Run a single test.
+    pub fn run<F, G>(&mut self, options: &TickOptions, mut f: F) -> TickResult
+    where
+        F: FnMut() -> G,
+    {
+        for _ in 0..options.warmup_iterations {
+            black_box_ref(&f());
+        }
+        let (mut min, mut total) = (u64::MAX, 0u128);
+        let samples = options.samples.max(1);
+        for _ in 0..samples {
+            let start = (self.ticks)();
+            for _ in 0..options.iterations {
+                black_box_ref(&f());
+            }
+            let elapsed = (self.ticks)().wrapping_sub(start);
+            min = min.min(elapsed);
+            total += elapsed as u128;
+        }
+        TickResult {
+            ticks: min,
+            mean_ticks: (total / samples as u128) as u64,
+            iterations: options.iterations,
+            samples,
+            frequency: self.frequency,
+        }
+    }
+}
+
+/// The result of a [`TickBench`] benchmark.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TickResult {
+    ticks: u64,
+    mean_ticks: u64,
+    iterations: u64,
+    samples: usize,
+    frequency: u64,
+}
+
+impl TickResult {
+    /// Returns the ticks of the fastest sample, for all its iterations.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Returns the mean ticks of the samples.
+    pub fn mean_ticks(&self) -> u64 {
+        self.mean_ticks
+    }
+
+    /// Returns the ticks of a single iteration of the fastest sample.
+    pub fn ticks_per_iteration(&self) -> f64 {
+        self.ticks as f64 / self.iterations.max(1) as f64
+    }
+
+    /// Returns the number of iterations of a sample.
+    pub fn iterations(&self) -> u64 {
+        self.iterations
+    }
+
+    /// Returns the number of collected samples.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Returns the elapsed time of the fastest sample, in nanoseconds.
+    pub fn as_ns(&self) -> u64 {
+        (self.ticks as u128 * 1_000_000_000 / self.frequency as u128) as u64
+    }
+}
+
+impl Display for TickResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ticks ({} ns)", self.ticks, self.as_ns())
+    }
+}
@@ -0,0 +1,138 @@
+//! The timer, and the cache of its calibration.
+//!
+//! `precision` measures the frequency of its counter against the system
+//! clock, which takes a while every time a benchmark executable starts. The
+//! frequency is cached per host, so that later runs only set up the counter.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use precision::{Config, Elapsed, Precision, Timestamp};
+
+use crate::environment;
+
+/// Overrides the file caching the calibration. The cache is disabled if it
+/// is empty.
+const CACHE_ENV: &str = "BENCHMARK_SIMPLE_CALIBRATION_CACHE";
+
+/// A calibrated counter, converting ticks with its own frequency rather
+/// than the one measured by `precision`.
+#[derive(Clone)]
+pub(crate) struct Timer {
+    precision: Precision,
+    frequency: u64,
+}
+
+impl Timer {
+    /// Calibrate the timer, or reuse the frequency cached for this host.
+    pub(crate) fn calibrate() -> Self {
+        let key = environment::host_key();
+        let path = cache_path();
+        if let Some(frequency) = path.as_deref().and_then(|path| load(path, &key)) {
+            return Timer::with_frequency(frequency);
+        }
+        let precision = Precision::new(Default::default()).unwrap();
+        let frequency = precision.frequency();
+        if let Some(path) = path {
+            if let Err(e) = store(&path, &key, frequency) {
+                eprintln!(
+                    "Unable to cache the calibration of the timer [{}]: {}",
+                    path.display(),
+                    e
+                );
+            }
+        }
+        Timer {
+            precision,
+            frequency,
+        }
+    }
+
+    /// Returns a timer whose counter runs at a known `frequency`, in ticks
+    /// per second.
+    pub(crate) fn with_frequency(frequency: u64) -> Self {
+        // The counter still has to be set up, but its measured frequency
+        // is ignored, so a short calibration will do.
+        let precision = Precision::new(Config {
+            setup_duration: Duration::from_millis(1),
+        })
+        .unwrap();
+        Timer {
+            precision,
+            frequency,
+        }
+    }
+
+    pub(crate) fn now(&self) -> Timestamp {
+        self.precision.now()
+    }
+
+    /// Returns the frequency of the counter, in ticks per second.
+    pub(crate) fn frequency(&self) -> u64 {
+        self.frequency
+    }
+
+    pub(crate) fn as_ns(&self, elapsed: Elapsed) -> u64 {
+        let ns = elapsed.ticks() as u128 * 1_000_000_000 / self.frequency.max(1) as u128;
+        ns.min(u64::MAX as u128) as u64
+    }
+
+    pub(crate) fn as_millis(&self, elapsed: Elapsed) -> u64 {
+        self.as_ns(elapsed) / 1_000_000
+    }
+
+    pub(crate) fn as_secs(&self, elapsed: Elapsed) -> u64 {
+        self.as_ns(elapsed) / 1_000_000_000
+    }
+
+    pub(crate) fn as_secs_f64(&self, elapsed: Elapsed) -> f64 {
+        elapsed.ticks() as f64 / self.frequency.max(1) as f64
+    }
+}
+
+/// Returns the file caching the calibration: `BENCHMARK_SIMPLE_CALIBRATION_CACHE`,
+/// or `benchmark-simple/calibration` in the Cargo target directory the
+/// executable was built in. Without a target directory, nothing is cached.
+fn cache_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(CACHE_ENV) {
+        return (!path.is_empty()).then(|| path.into());
+    }
+    let target = match env::var_os("CARGO_TARGET_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            // Cargo tags its target directories as caches.
+            let exe = env::current_exe().ok()?;
+            exe.ancestors()
+                .find(|dir| dir.join("CACHEDIR.TAG").is_file())?
+                .to_path_buf()
+        }
+    };
+    Some(target.join("benchmark-simple").join("calibration"))
+}
+
+/// Returns the cached frequency, if it was measured on the same host.
+fn load(path: &Path, key: &str) -> Option<u64> {
+    let cache = fs::read_to_string(path).ok()?;
+    let mut lines = cache.lines();
+    if lines.next()? != key {
+        return None;
+    }
+    lines
+        .next()?
+        .parse()
+        .ok()
+        .filter(|&frequency| frequency > 0)
+}
+
+fn store(path: &Path, key: &str, frequency: u64) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Benchmarks running concurrently never see a partial file.
+    let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&tmp, format!("{}\n{}\n", key, frequency))?;
+    fs::rename(&tmp, path)
+}
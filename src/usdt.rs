@@ -0,0 +1,99 @@
+//! Statically defined tracepoints (USDT), fired at the start and at the end
+//! of every measured sample.
+//!
+//! Only available with the `usdt` feature. Probes are SystemTap SDT notes,
+//! understood by `bpftrace`, `perf` and SystemTap, and are emitted on Linux
+//! on x86_64 and aarch64. Elsewhere, the functions do nothing.
+//!
+//! The provider is `benchmark_simple`, and the probes are:
+//!
+//! * `sample_start(name, sample)`: before a sample, `name` being a pointer to
+//!   the NUL-terminated name of the benchmark and `sample` the number of the
+//!   sample, from 1.
+//! * `sample_end(name, sample, ns)`: after a sample, with its duration in
+//!   nanoseconds.
+//!
+//! An unused probe is a single `nop` instruction.
+
+use std::ffi::CStr;
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+macro_rules! probe {
+    ($name:literal, $args:literal, $($operands:tt)*) => {
+        // SAFETY: the probe is a `nop`; the rest only adds an ELF note
+        // describing its location and its arguments.
+        #[allow(named_asm_labels)]
+        unsafe {
+            core::arch::asm!(
+                "990: nop",
+                ".pushsection .note.stapsdt, \"\", \"note\"",
+                ".balign 4",
+                ".4byte 992f-991f, 994f-993f, 3",
+                "991: .asciz \"stapsdt\"",
+                "992: .balign 4",
+                "993: .8byte 990b",
+                ".8byte _.stapsdt.base",
+                ".8byte 0",
+                ".asciz \"benchmark_simple\"",
+                concat!(".asciz \"", $name, "\""),
+                concat!(".asciz \"", $args, "\""),
+                "994: .balign 4",
+                ".popsection",
+                ".ifndef _.stapsdt.base",
+                ".pushsection .stapsdt.base, \"aGR\", \"progbits\", .stapsdt.base, comdat",
+                ".weak _.stapsdt.base",
+                ".hidden _.stapsdt.base",
+                "_.stapsdt.base: .space 1",
+                ".size _.stapsdt.base, 1",
+                ".popsection",
+                ".endif",
+                $($operands)*
+                // Tracers read the name while the probe fires.
+                options(readonly, nostack, preserves_flags)
+            )
+        }
+    };
+}
+
+/// Fire the `sample_start` probe.
+#[inline(always)]
+pub(crate) fn sample_start(name: &CStr, sample: usize) {
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    probe!("sample_start", "8@%{0} 8@%{1}", in(reg) name.as_ptr(), in(reg) sample as u64,);
+    #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+    probe!("sample_start", "8@{0} 8@{1}", in(reg) name.as_ptr(), in(reg) sample as u64,);
+    #[cfg(not(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    )))]
+    let _ = (name, sample);
+}
+
+/// Fire the `sample_end` probe.
+#[inline(always)]
+pub(crate) fn sample_end(name: &CStr, sample: usize, ns: u64) {
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    probe!(
+        "sample_end",
+        "8@%{0} 8@%{1} 8@%{2}",
+        in(reg) name.as_ptr(),
+        in(reg) sample as u64,
+        in(reg) ns,
+    );
+    #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+    probe!(
+        "sample_end",
+        "8@{0} 8@{1} 8@{2}",
+        in(reg) name.as_ptr(),
+        in(reg) sample as u64,
+        in(reg) ns,
+    );
+    #[cfg(not(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    )))]
+    let _ = (name, sample, ns);
+}
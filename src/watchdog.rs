@@ -0,0 +1,256 @@
+//! Sample timeouts.
+//!
+//! A monitored benchmark runs on a worker thread, while the calling thread
+//! watches its samples. If a sample doesn't complete in time, the worker is
+//! left behind, and the caller gets a result flagged as timed out.
+
+use std::panic::{self, Location};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use precision::{Elapsed, Timestamp};
+
+use crate::{black_box_ref, Bench, BenchResult, Options, StopReason};
+
+/// A benchmark function that can be moved to a worker thread.
+pub(crate) type SharedFn = Arc<Mutex<Box<dyn FnMut() + Send>>>;
+
+/// Returns a [`SharedFn`] calling `f`.
+pub(crate) fn shared_fn<F, G>(mut f: F) -> SharedFn
+where
+    F: FnMut() -> G + Send + 'static,
+{
+    Arc::new(Mutex::new(Box::new(move || {
+        black_box_ref(&f());
+    })))
+}
+
+/// Lock a [`SharedFn`], even if a previous run of it panicked.
+pub(crate) fn lock_fn(f: &SharedFn) -> MutexGuard<'_, Box<dyn FnMut() + Send>> {
+    match f.lock() {
+        Ok(f) => f,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// The progress of a monitored run, updated by the worker thread.
+#[derive(Default)]
+pub(crate) struct Monitor {
+    state: Mutex<MonitorState>,
+}
+
+#[derive(Default)]
+struct MonitorState {
+    /// Options of the run, once the number of iterations is calibrated.
+    options: Option<Arc<Options>>,
+    /// The sample being measured and when it started. Sample 0 is the
+    /// warm-up and the calibration.
+    current: Option<(usize, Timestamp)>,
+    /// The samples measured so far.
+    completed: Vec<Elapsed>,
+}
+
+impl Monitor {
+    fn state(&self) -> MutexGuard<'_, MonitorState> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    pub(crate) fn set_options(&self, options: &Arc<Options>) {
+        self.state().options = Some(options.clone());
+    }
+
+    pub(crate) fn start(&self, sample: usize, start: Timestamp) {
+        self.state().current = Some((sample, start));
+    }
+
+    pub(crate) fn stop(&self) {
+        self.state().current = None;
+    }
+
+    pub(crate) fn complete(&self, elapsed: Elapsed) {
+        self.state().completed.push(elapsed);
+    }
+}
+
+impl Bench {
+    /// Run a single test on a worker thread, so that a sample exceeding
+    /// `options.sample_timeout` doesn't block the caller.
+    ///
+    /// The calling thread watches the samples. If one doesn't complete in
+    /// time, a diagnostic is printed, and the result is returned right away,
+    /// flagged with [`BenchResult::sample_timed_out()`]. It is computed from
+    /// the samples completed so far, the unfinished one counting for the
+    /// time it ran so far. The worker thread is left running, or blocked,
+    /// until the process exits, and keeps the function borrowed.
+    ///
+    /// The warm-up and the calibration of the number of iterations are
+    /// given `sample_timeout`, plus `warmup_time` and twice the target
+    /// sample time.
+    ///
+    /// Without a `sample_timeout`, this is the same as [`Bench::run()`].
+    /// A panic in `f` is propagated to the caller.
+    ///
+    /// ```rust
+    /// use benchmark_simple::*;
+    /// use std::time::Duration;
+    ///
+    /// let bench = Bench::new();
+    /// let options = Options::quick()
+    ///     .into_builder()
+    ///     .iterations(1)
+    ///     .warmup_iterations(0)
+    ///     .sample_timeout(Duration::from_millis(50))
+    ///     .build()
+    ///     .unwrap();
+    /// let res = bench.run_monitored(&options, || std::thread::sleep(Duration::from_secs(3600)));
+    /// assert!(res.sample_timed_out());
+    /// ```
+    #[track_caller]
+    pub fn run_monitored<F, G>(&self, options: &Options, f: F) -> BenchResult
+    where
+        F: FnMut() -> G + Send + 'static,
+    {
+        self.run_monitored_at(options, None, Location::caller(), &shared_fn(f))
+    }
+
+    /// Run a single test on a monitored worker thread, `name` identifying
+    /// the benchmark in profiles and diagnostics.
+    pub(crate) fn run_monitored_at(
+        &self,
+        options: &Options,
+        name: Option<&str>,
+        location: &'static Location<'static>,
+        f: &SharedFn,
+    ) -> BenchResult {
+        let timeout = match options.sample_timeout {
+            Some(timeout) if !options.dry_run => timeout,
+            _ => return self.run_named_at(options, name, location, &mut **lock_fn(f)),
+        };
+        let sample_time = match options.sample_time {
+            Some(sample_time) => sample_time,
+            None if options.auto_iterations => self.min_sample_time(),
+            None => Duration::ZERO,
+        };
+        let setup_timeout = timeout + options.warmup_time.unwrap_or_default() + sample_time * 2;
+        let monitor = Arc::new(Monitor::default());
+        let (tx, rx) = mpsc::channel();
+        let spawned = {
+            let bench = Bench {
+                monitor: Some(monitor.clone()),
+                ..self.clone()
+            };
+            let options = options.clone();
+            let name = name.map(str::to_string);
+            let f = f.clone();
+            let monitor = monitor.clone();
+            thread::Builder::new()
+                .name("benchmark".to_string())
+                .spawn(move || {
+                    // A worker left behind by a previous timeout may still hold the function.
+                    monitor.start(0, bench.precision.now());
+                    let mut f = lock_fn(&f);
+                    let result = bench.run_named_at(&options, name.as_deref(), location, &mut **f);
+                    let _ = tx.send(result);
+                })
+        };
+        let worker = match spawned {
+            Ok(worker) => worker,
+            Err(e) => {
+                eprintln!("Unable to spawn a benchmark thread: {}", e);
+                return self.run_named_at(options, name, location, &mut **lock_fn(f));
+            }
+        };
+        let poll = (timeout / 10).clamp(Duration::from_millis(1), Duration::from_millis(100));
+        let (sample, elapsed) = loop {
+            match rx.recv_timeout(poll) {
+                Ok(result) => {
+                    let _ = worker.join();
+                    return result;
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => match worker.join() {
+                    Err(payload) => panic::resume_unwind(payload),
+                    Ok(()) => unreachable!("the benchmark thread exited without a result"),
+                },
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+            }
+            if let Some((sample, start)) = monitor.state().current {
+                let elapsed = self.precision.now() - start;
+                let limit = if sample == 0 { setup_timeout } else { timeout };
+                if self.precision.as_ns(elapsed) > limit.as_nanos() as u64 {
+                    break (sample, elapsed);
+                }
+            }
+        };
+        let name = name.map_or_else(|| location.to_string(), |name| format!("[{}]", name));
+        let state = monitor.state();
+        if sample == 0 {
+            eprintln!(
+                "Benchmark {}: didn't get past the warm-up within {:?}, abandoning its thread.",
+                name, setup_timeout
+            );
+        } else {
+            eprintln!(
+                "Benchmark {}: sample {} didn't complete within {:?} ({} samples completed), abandoning its thread.",
+                name,
+                sample,
+                timeout,
+                state.completed.len()
+            );
+        }
+        let options = state
+            .options
+            .clone()
+            .unwrap_or_else(|| Arc::new(options.clone()));
+        let elapsed = state
+            .completed
+            .iter()
+            .copied()
+            .chain(Some(elapsed))
+            .min_by_key(|elapsed| elapsed.ticks())
+            .unwrap();
+        let mut result = self.result(elapsed, options, location);
+        result.sample_timed_out = true;
+        result.stop_reason = Some(StopReason::SampleTimeout);
+        self.report(|reporter| reporter.on_finish(&result));
+        result
+    }
+}
+
+/// Exits the process if a sample doesn't complete in time.
+pub(crate) struct Watchdog {
+    tx: mpsc::Sender<Option<usize>>,
+}
+
+impl Watchdog {
+    pub(crate) fn spawn(timeout: Duration, location: &'static Location<'static>) -> Self {
+        let (tx, rx) = mpsc::channel::<Option<usize>>();
+        thread::spawn(move || {
+            while let Ok(Some(sample)) = rx.recv() {
+                match rx.recv_timeout(timeout) {
+                    Ok(_) => continue,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        eprintln!(
+                            "Benchmark at {}: sample {} didn't complete within {:?}, aborting.",
+                            location, sample, timeout
+                        );
+                        std::process::exit(1);
+                    }
+                }
+            }
+        });
+        Watchdog { tx }
+    }
+
+    pub(crate) fn start(&self, sample: usize) {
+        let _ = self.tx.send(Some(sample));
+    }
+
+    pub(crate) fn stop(&self) {
+        let _ = self.tx.send(None);
+    }
+}
@@ -0,0 +1,111 @@
+//! Search for inputs maximizing the running time of a function.
+
+use std::panic::Location;
+
+use crate::{is_interrupted, Bench, BenchResult, Options};
+
+/// The slowest input found by [`Bench::search_worst_case()`].
+#[derive(Clone, Debug)]
+pub struct WorstCase<I> {
+    input: I,
+    result: BenchResult,
+    round: usize,
+    rounds: usize,
+}
+
+impl<I> WorstCase<I> {
+    /// Returns the slowest input found.
+    pub fn input(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns the slowest input found, consuming the search result.
+    pub fn into_input(self) -> I {
+        self.input
+    }
+
+    /// Returns the result of the benchmark for the slowest input.
+    pub fn result(&self) -> &BenchResult {
+        &self.result
+    }
+
+    /// Returns the round in which the slowest input was found (0 for the initial input).
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    /// Returns the number of mutation rounds that were run.
+    pub fn rounds(&self) -> usize {
+        self.rounds
+    }
+}
+
+impl Bench {
+    /// Search for an input maximizing the running time of `f` (experimental).
+    ///
+    /// Starting from `input`, every round derives a candidate from the slowest
+    /// input found so far with `mutate`, and benchmarks `f` with it. Candidates
+    /// that are slower replace the current worst case. This is useful to find
+    /// inputs triggering algorithmic-complexity issues, such as hash collisions
+    /// or quadratic paths.
+    ///
+    /// Each candidate is a complete benchmark run, so `Options::quick()` or a low
+    /// `max_samples` keeps the search fast. The search stops early when the
+    /// process is interrupted.
+    ///
+    /// ```rust
+    /// use benchmark_simple::{Bench, Options};
+    ///
+    /// let bench = Bench::new();
+    /// let worst = bench.search_worst_case(
+    ///     &Options::quick(),
+    ///     vec![0u32; 64],
+    ///     10,
+    ///     |v| v.iter().map(|x| x.wrapping_mul(31).wrapping_add(7)).collect(),
+    ///     |v| v.iter().filter(|&&x| x % 2 == 0).count(),
+    /// );
+    /// println!("{}: {:?}", worst.result(), &worst.input()[..4]);
+    /// ```
+    #[track_caller]
+    pub fn search_worst_case<I, M, F, G>(
+        &self,
+        options: &Options,
+        input: I,
+        rounds: usize,
+        mut mutate: M,
+        mut f: F,
+    ) -> WorstCase<I>
+    where
+        M: FnMut(&I) -> I,
+        F: FnMut(&I) -> G,
+    {
+        let location = Location::caller();
+        let result = self.run_at(options, location, || f(&input));
+        let mut worst = WorstCase {
+            input,
+            result,
+            round: 0,
+            rounds: 0,
+        };
+        if options.dry_run {
+            return worst;
+        }
+        for round in 1..=rounds {
+            if is_interrupted() {
+                break;
+            }
+            let candidate = mutate(&worst.input);
+            let result = self.run_at(options, location, || f(&candidate));
+            worst.rounds = round;
+            if result.as_ns() > worst.result.as_ns() {
+                if options.verbose {
+                    log_info!(self, "Round {}: new worst case: {}", round, result);
+                }
+                worst.input = candidate;
+                worst.result = result;
+                worst.round = round;
+            }
+        }
+        worst
+    }
+}